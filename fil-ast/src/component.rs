@@ -1,4 +1,5 @@
-use super::{Command, Id, Signature};
+use super::{Command, FnDef, Id, Signature};
+use std::path::PathBuf;
 
 #[derive(Default)]
 /// A component in Filament
@@ -23,8 +24,13 @@ pub struct Namespace {
     pub externs: Vec<(String, Vec<Signature>)>,
     /// Components defined in this file
     pub components: Vec<Component>,
+    /// Functions over parameters defined in this file
+    pub fns: Vec<FnDef>,
     /// Top-level component id
     pub toplevel: String,
+    /// Library files that have not been imported along with the import that
+    /// provides them
+    pub library: Vec<(String, PathBuf)>,
 }
 
 impl Namespace {
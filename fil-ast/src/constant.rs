@@ -0,0 +1,112 @@
+use itertools::Itertools;
+use std::fmt::{self, Display};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// A constant driven onto a port, e.g., `8'hFF`, `4'b1010`, `0x1F`, or `42`.
+/// Constants can be wider than 64 bits.
+pub struct Constant {
+    /// Value of the constant as little-endian 64-bit words without trailing
+    /// zero words
+    words: Vec<u64>,
+    /// Width given by the literal. Unsized constants take the width of the
+    /// port they are connected to.
+    pub width: Option<u64>,
+}
+
+impl Constant {
+    /// Parse the digits of a literal in the given radix. Underscores are
+    /// ignored. Returns `None` if the digits are not valid in the radix.
+    pub fn parse(digits: &str, radix: u32, width: Option<u64>) -> Option<Self> {
+        let mut words: Vec<u64> = vec![];
+        for c in digits.chars().filter(|c| *c != '_') {
+            // words = words * radix + digit
+            let mut carry = c.to_digit(radix)? as u128;
+            for w in words.iter_mut() {
+                let v = (*w as u128) * (radix as u128) + carry;
+                *w = v as u64;
+                carry = v >> 64;
+            }
+            if carry > 0 {
+                words.push(carry as u64);
+            }
+        }
+        Some(Self { words, width })
+    }
+
+    /// Value of the constant as little-endian 64-bit words. Zero has no words.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Value of the constant if it fits in 64 bits
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.words[..] {
+            [] => Some(0),
+            [w] => Some(w),
+            _ => None,
+        }
+    }
+
+    /// Number of bits needed to represent the value
+    pub fn bits(&self) -> u64 {
+        match self.words.last() {
+            None => 0,
+            Some(w) => {
+                64 * (self.words.len() as u64 - 1)
+                    + (64 - w.leading_zeros() as u64)
+            }
+        }
+    }
+
+    /// The `width` bits of the value starting at bit `lo`. The width must be
+    /// at most 64.
+    pub fn extract(&self, lo: u64, width: u64) -> u64 {
+        assert!(width <= 64, "cannot extract {width} bits into a u64");
+        let word = |i: u64| self.words.get(i as usize).copied().unwrap_or(0);
+        let (idx, off) = (lo / 64, lo % 64);
+        let mut v = word(idx) >> off;
+        if off > 0 {
+            v |= word(idx + 1) << (64 - off);
+        }
+        if width < 64 {
+            v &= (1 << width) - 1;
+        }
+        v
+    }
+
+    /// The constant truncated to the given width
+    pub fn resize(&self, width: u64) -> Self {
+        let mut words = (0..width)
+            .step_by(64)
+            .map(|lo| self.extract(lo, (width - lo).min(64)))
+            .collect_vec();
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        Self {
+            words,
+            width: Some(width),
+        }
+    }
+
+    /// Value of the constant in hexadecimal without a prefix
+    pub fn hex(&self) -> String {
+        let mut words = self.words.iter().rev();
+        let Some(first) = words.next() else {
+            return "0".to_string();
+        };
+        std::iter::once(format!("{first:x}"))
+            .chain(words.map(|w| format!("{w:016x}")))
+            .join("")
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.width, self.as_u64()) {
+            (Some(w), _) => write!(f, "{w}'h{}", self.hex()),
+            (None, Some(v)) => write!(f, "{v}"),
+            (None, None) => write!(f, "0x{}", self.hex()),
+        }
+    }
+}
@@ -93,6 +93,17 @@ impl OrderConstraint<Expr> {
     }
 }
 
+impl<T: std::fmt::Display> std::fmt::Display for OrderConstraint<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            OrderOp::Gt => ">",
+            OrderOp::Gte => ">=",
+            OrderOp::Eq => "==",
+        };
+        write!(f, "{} {op} {}", self.left, self.right)
+    }
+}
+
 impl OrderConstraint<Time> {
     pub fn resolve_event(self, bindings: &Binding<Time>) -> Self {
         OrderConstraint {
@@ -129,6 +140,94 @@ impl OrderConstraint<TimeSub> {
     }
 }
 
+/// A boolean combination of ordering constraints, e.g.,
+/// `N == 1 || !(W < 8 && W > 2)`.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub enum Formula<T> {
+    Cmp(OrderConstraint<T>),
+    Not(Box<Formula<T>>),
+    And(Box<Formula<T>>, Box<Formula<T>>),
+    Or(Box<Formula<T>>, Box<Formula<T>>),
+}
+
+impl<T> Formula<T> {
+    pub fn and(l: Formula<T>, r: Formula<T>) -> Self {
+        Formula::And(Box::new(l), Box::new(r))
+    }
+
+    pub fn or(l: Formula<T>, r: Formula<T>) -> Self {
+        Formula::Or(Box::new(l), Box::new(r))
+    }
+
+    /// The comparisons in the formula
+    pub fn cmps(&self) -> Vec<&OrderConstraint<T>> {
+        match self {
+            Formula::Cmp(c) => vec![c],
+            Formula::Not(f) => f.cmps(),
+            Formula::And(l, r) | Formula::Or(l, r) => {
+                let mut cmps = l.cmps();
+                cmps.extend(r.cmps());
+                cmps
+            }
+        }
+    }
+}
+
+impl Formula<Expr> {
+    pub fn resolve_expr(self, binding: &Binding<Expr>) -> Self {
+        match self {
+            Formula::Cmp(c) => Formula::Cmp(c.resolve_expr(binding)),
+            Formula::Not(f) => !f.resolve_expr(binding),
+            Formula::And(l, r) => {
+                Formula::and(l.resolve_expr(binding), r.resolve_expr(binding))
+            }
+            Formula::Or(l, r) => {
+                Formula::or(l.resolve_expr(binding), r.resolve_expr(binding))
+            }
+        }
+    }
+
+    pub fn exprs(&self) -> Vec<&Expr> {
+        self.cmps().into_iter().flat_map(|c| c.exprs()).collect()
+    }
+}
+
+impl<T> std::ops::Not for Formula<T> {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Formula::Not(Box::new(self))
+    }
+}
+
+impl<T> From<OrderConstraint<T>> for Formula<T> {
+    fn from(con: OrderConstraint<T>) -> Self {
+        Formula::Cmp(con)
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Formula<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Conjunctions bind tighter than disjunctions so only disjunctions
+        // nested in conjunctions need parentheses.
+        let paren = |p: &Formula<T>| match p {
+            Formula::Or(..) => format!("({p})"),
+            _ => format!("{p}"),
+        };
+        match self {
+            Formula::Cmp(c) => write!(f, "{c}"),
+            Formula::Not(p) => match **p {
+                Formula::Not(_) => write!(f, "!{p}"),
+                _ => write!(f, "!({p})"),
+            },
+            Formula::And(l, r) => {
+                write!(f, "{} && {}", paren(l), paren(r))
+            }
+            Formula::Or(l, r) => write!(f, "{l} || {r}"),
+        }
+    }
+}
+
 /// A ordering constraint over time expressions or time ranges.
 #[derive(Clone, Hash)]
 pub enum Constraint {
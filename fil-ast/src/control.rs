@@ -1,5 +1,5 @@
 use super::{
-    Binding, Expr, Id, Implication, Loc, OrderConstraint, Range, Time,
+    Binding, Constant, Expr, Formula, Id, Implication, Loc, Range, Time,
 };
 use struct_variant::struct_variant;
 
@@ -49,6 +49,8 @@ pub enum Port {
         port: Loc<Id>,
         access: Loc<Access>,
     },
+    /// A constant value
+    Constant(Constant),
 }
 
 impl Port {
@@ -266,14 +268,14 @@ impl ForLoop {
 /// The `then` branch is checked assuming that the condition is true and the `else` branch is checked
 /// assuming that the condition is false.
 pub struct If {
-    pub cond: OrderConstraint<Expr>,
+    pub cond: Formula<Expr>,
     pub then: Vec<Command>,
     pub alt: Vec<Command>,
 }
 
 impl If {
     pub fn new(
-        cond: OrderConstraint<Expr>,
+        cond: Formula<Expr>,
         then: Vec<Command>,
         alt: Vec<Command>,
     ) -> Self {
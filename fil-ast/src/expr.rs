@@ -25,7 +25,8 @@ impl std::fmt::Display for Op {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd)]
-/// A unary uninterpreted function over integers.
+/// A function over integers. Functions other than the built-in ones are
+/// defined using `fn` declarations in the program.
 pub enum Fn {
     /// The `pow2` function
     Pow2,
@@ -34,6 +35,19 @@ pub enum Fn {
     /// Returns the 32 bit floating point bits of the sine
     SinB,
     CosB,
+    /// The smaller of two numbers
+    Min,
+    /// The larger of two numbers
+    Max,
+    /// Division rounding up
+    CeilDiv,
+    /// Number of bits needed to represent `n` distinct values, i.e., the
+    /// smallest `k` such that `pow2(k) >= n`
+    Clog2,
+    /// Absolute difference of two numbers
+    AbsDiff,
+    /// A function defined in the program
+    User(Id),
 }
 impl std::fmt::Display for Fn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -42,11 +56,48 @@ impl std::fmt::Display for Fn {
             Fn::Log2 => write!(f, "log2"),
             Fn::SinB => write!(f, "sin_bits"),
             Fn::CosB => write!(f, "cos_bits"),
+            Fn::Min => write!(f, "min"),
+            Fn::Max => write!(f, "max"),
+            Fn::CeilDiv => write!(f, "ceil_div"),
+            Fn::Clog2 => write!(f, "clog2"),
+            Fn::AbsDiff => write!(f, "abs_diff"),
+            Fn::User(name) => write!(f, "{name}"),
         }
     }
 }
 
 impl Fn {
+    /// The built-in function with the given name
+    pub fn builtin(name: &str) -> Option<Self> {
+        Some(match name {
+            "pow2" => Fn::Pow2,
+            "log2" => Fn::Log2,
+            "sin_bits" => Fn::SinB,
+            "cos_bits" => Fn::CosB,
+            "min" => Fn::Min,
+            "max" => Fn::Max,
+            "ceil_div" => Fn::CeilDiv,
+            "clog2" => Fn::Clog2,
+            "abs_diff" => Fn::AbsDiff,
+            _ => return None,
+        })
+    }
+
+    /// Number of arguments taken by a built-in function. The arity of
+    /// functions defined in the program is given by their definition.
+    pub fn arity(self) -> Option<usize> {
+        match self {
+            Fn::Pow2 | Fn::Log2 | Fn::Clog2 => Some(1),
+            Fn::SinB
+            | Fn::CosB
+            | Fn::Min
+            | Fn::Max
+            | Fn::CeilDiv
+            | Fn::AbsDiff => Some(2),
+            Fn::User(_) => None,
+        }
+    }
+
     pub fn eval(self, args: Vec<u64>) -> u64 {
         match (self, &*args) {
             (Fn::Pow2, &[n]) => 2u64.pow(n as u32),
@@ -61,6 +112,16 @@ impl Fn {
                     as f32)
                     .to_bits() as u64
             }
+            (Fn::Min, &[l, r]) => l.min(r),
+            (Fn::Max, &[l, r]) => l.max(r),
+            (Fn::CeilDiv, &[n, d]) => n / d + u64::from(n % d != 0),
+            (Fn::Clog2, &[n]) => {
+                (u64::BITS - n.saturating_sub(1).leading_zeros()) as u64
+            }
+            (Fn::AbsDiff, &[l, r]) => l.abs_diff(r),
+            (Fn::User(name), _) => unreachable!(
+                "Function `{name}' cannot be evaluated without its definition."
+            ),
             _ => unreachable!(
                 "Function {} did not expect {} arguments.",
                 self,
@@ -80,7 +141,7 @@ pub enum Expr {
         param: Loc<Id>,
     },
     App {
-        func: Fn,
+        func: Loc<Fn>,
         args: Vec<Expr>,
     },
     Op {
@@ -127,7 +188,7 @@ impl Expr {
     }
 
     /// Function application
-    pub fn func(func: Fn, args: Vec<Expr>) -> Self {
+    pub fn func(func: Loc<Fn>, args: Vec<Expr>) -> Self {
         Expr::App { func, args }
     }
 
@@ -0,0 +1,50 @@
+use super::{Binding, Expr, Formula, Id, Loc};
+use itertools::Itertools;
+
+#[derive(Clone)]
+/// A function over parameters defined in the program:
+/// `fn name(a, b) -> expr where a > b;`
+pub struct FnDef {
+    pub name: Loc<Id>,
+    pub params: Vec<Loc<Id>>,
+    pub body: Loc<Expr>,
+    /// Constraints on the arguments that every call must satisfy
+    pub pres: Vec<Loc<Formula<Expr>>>,
+}
+
+impl FnDef {
+    pub fn new(
+        name: Loc<Id>,
+        params: Vec<Loc<Id>>,
+        body: Loc<Expr>,
+        pres: Vec<Loc<Formula<Expr>>>,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            body,
+            pres,
+        }
+    }
+
+    /// Binding from the parameters of the function to the arguments of a call
+    pub fn binding(&self, args: Vec<Expr>) -> Binding<Expr> {
+        Binding::new(self.params.iter().map(|p| *p.inner()).zip(args))
+    }
+}
+
+impl std::fmt::Display for FnDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fn {}({}) -> {}",
+            self.name,
+            self.params.iter().join(", "),
+            self.body
+        )?;
+        if !self.pres.is_empty() {
+            write!(f, " where {}", self.pres.iter().format(", "))?;
+        }
+        write!(f, ";")
+    }
+}
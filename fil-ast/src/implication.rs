@@ -1,11 +1,11 @@
-use super::{Binding, Expr, OrderConstraint};
+use super::{Binding, Expr, Formula};
 use itertools::Itertools;
 
 /// A type representing the expression a => b
 #[derive(Clone)]
 pub struct Implication<T> {
-    pub guard: Option<OrderConstraint<T>>,
-    pub cons: OrderConstraint<T>,
+    pub guard: Option<Formula<T>>,
+    pub cons: Formula<T>,
 }
 
 impl<T> Implication<T>
@@ -13,23 +13,17 @@ where
     T: Clone,
 {
     /// Creates an [Implication] `guard => cons`
-    pub fn implies(
-        guard: OrderConstraint<T>,
-        cons: OrderConstraint<T>,
-    ) -> Self {
+    pub fn implies(guard: Formula<T>, cons: Formula<T>) -> Self {
         Implication::new_opt(Some(guard), cons)
     }
 
     /// Creates an [Implication] with no guard, something that must always be true
-    pub fn fact(cons: OrderConstraint<T>) -> Self {
+    pub fn fact(cons: Formula<T>) -> Self {
         Implication::new_opt(None, cons)
     }
 
     /// Creates a pair of [Implication]s representing an iff (`a <=> b`) clause
-    pub fn iff(
-        left: OrderConstraint<T>,
-        right: OrderConstraint<T>,
-    ) -> [Self; 2] {
+    pub fn iff(left: Formula<T>, right: Formula<T>) -> [Self; 2] {
         [
             Implication::implies(left.clone(), right.clone()),
             Implication::implies(right, left),
@@ -37,10 +31,7 @@ where
     }
 
     /// Creates a new [Implication] with an optional guard
-    fn new_opt(
-        guard: Option<OrderConstraint<T>>,
-        cons: OrderConstraint<T>,
-    ) -> Self {
+    fn new_opt(guard: Option<Formula<T>>, cons: Formula<T>) -> Self {
         Implication { guard, cons }
     }
 }
@@ -64,11 +55,11 @@ impl Implication<Expr> {
     }
 }
 
-impl<T> From<OrderConstraint<T>> for Implication<T>
+impl<T> From<Formula<T>> for Implication<T>
 where
     T: Clone,
 {
-    fn from(cons: OrderConstraint<T>) -> Self {
+    fn from(cons: Formula<T>) -> Self {
         Implication::fact(cons)
     }
 }
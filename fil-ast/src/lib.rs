@@ -1,29 +1,35 @@
 mod bind_map;
 mod component;
+mod constant;
 mod constraint;
 mod control;
 mod expr;
+mod fn_def;
 mod implication;
 mod interval;
 mod loc;
 mod parser;
 mod port;
+mod printer;
 mod signature;
 mod time;
 
 pub use bind_map::Binding;
 pub use component::{Component, Namespace};
-pub use constraint::{Constraint, OrderConstraint, OrderOp};
+pub use constant::Constant;
+pub use constraint::{Constraint, Formula, OrderConstraint, OrderOp};
 pub use control::{
     Access, Bundle, BundleType, Command, Connect, Exists, Fact, ForLoop, If,
     Instance, Invoke, ParamLet, Port,
 };
 pub use expr::{Expr, Fn, Op};
 pub use fil_utils::Id;
+pub use fn_def::FnDef;
 pub use implication::Implication;
 pub use interval::Range;
 pub use loc::Loc;
 pub use parser::FilamentParser;
 pub use port::{InterfaceDef, PortDef};
-pub use signature::{EventBind, ParamBind, SigBind, Signature};
+pub use printer::format_source;
+pub use signature::{EventBind, ParamBind, ParamRange, SigBind, Signature};
 pub use time::{Time, TimeSub};
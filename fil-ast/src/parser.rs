@@ -5,6 +5,7 @@ use crate::{self as ast, Loc, TimeSub};
 use fil_utils::{self as utils, FilamentResult};
 use fil_utils::{FileIdx, GPosIdx, GlobalPositionTable};
 use itertools::Itertools;
+use pest::error::{ErrorVariant, InputLocation};
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest_consume::{match_nodes, Error, Parser};
 use std::fs;
@@ -27,6 +28,9 @@ type Ports = Vec<Loc<ast::PortDef>>;
 // include the grammar file so that Cargo knows to rebuild this file on grammar changes
 const _GRAMMAR: &str = include_str!("syntax.pest");
 
+/// Maximum number of tokens listed in a syntax error
+const MAX_TOKENS: usize = 6;
+
 // Define the precedence of binary operations. We use `lazy_static` so that
 // this is only ever constructed once.
 lazy_static::lazy_static! {
@@ -34,21 +38,28 @@ lazy_static::lazy_static! {
     PrattParser::new()
         .op(Op::infix(Rule::op_add, Assoc::Left) | Op::infix(Rule::op_sub, Assoc::Left))
         .op(Op::infix(Rule::op_mul, Assoc::Left) | Op::infix(Rule::op_div, Assoc::Left) | Op::infix(Rule::op_mod, Assoc::Left));
+
+    static ref CMP_PRATT: PrattParser<Rule> =
+    PrattParser::new()
+        .op(Op::infix(Rule::op_or, Assoc::Left))
+        .op(Op::infix(Rule::op_and, Assoc::Left))
+        .op(Op::prefix(Rule::op_not));
 }
 
 pub enum ExtOrComp {
     Ext((String, Vec<ast::Signature>)),
     Comp(ast::Component),
+    Fn(ast::FnDef),
 }
 
 #[derive(Clone)]
 pub enum FCons {
-    ExprC(ast::OrderConstraint<ast::Expr>),
+    ExprC(ast::Formula<ast::Expr>),
     TimeC(ast::OrderConstraint<ast::Time>),
 }
 
 pub enum Port {
-    Pd(Loc<ast::PortDef>),
+    Pd(Box<Loc<ast::PortDef>>),
     Int(ast::InterfaceDef),
     Un((ast::Id, u64)),
 }
@@ -75,26 +86,11 @@ impl FilamentParser {
         // Parse the file
         let inputs =
             FilamentParser::parse_with_userdata(Rule::file, content, user_data)
-                .map_err(|e| {
-                    utils::Error::misc(format!(
-                        "Failed to parse {}: {}",
-                        e.with_path(&path.to_string_lossy()),
-                        path.to_string_lossy(),
-                    ))
-                })?;
-        let input = inputs.single().map_err(|e| {
-            utils::Error::misc(format!(
-                "Failed to parse {}: {}",
-                e.with_path(&path.to_string_lossy()),
-                path.to_string_lossy(),
-            ))
-        })?;
-        let out = FilamentParser::file(input).map_err(|e| {
-            utils::Error::misc(format!(
-                "Failed to parse {}",
-                e.with_path(&path.to_string_lossy()),
-            ))
-        })?;
+                .map_err(|e| Self::error(e, file, content))?;
+        let input =
+            inputs.single().map_err(|e| Self::error(e, file, content))?;
+        let out = FilamentParser::file(input)
+            .map_err(|e| Self::error(e, file, content))?;
         log::info!(
             "Parsed `{}` in {}ms",
             path.to_string_lossy(),
@@ -103,6 +99,171 @@ impl FilamentParser {
         Ok(out)
     }
 
+    /// Convert a parsing error into an error pointing at its location in the
+    /// file. The first line of a custom error is its message and the rest are
+    /// hints.
+    fn error(e: Error<Rule>, file: FileIdx, content: &str) -> utils::Error {
+        let (start, end) = match e.location {
+            InputLocation::Pos(p) => (p, p),
+            InputLocation::Span(sp) => sp,
+        };
+        match e.variant {
+            ErrorVariant::CustomError { message } => {
+                let mut lines = message.lines();
+                let msg = lines.next().unwrap_or_default().to_string();
+                let pos = Self::add_pos(file, start, end);
+                utils::Error::syntax(
+                    msg,
+                    pos,
+                    "",
+                    lines.map(String::from).collect(),
+                )
+            }
+            ErrorVariant::ParsingError { positives, .. } => {
+                Self::syntax_error(start, &positives, file, content)
+            }
+        }
+    }
+
+    fn add_pos(file: FileIdx, start: usize, end: usize) -> GPosIdx {
+        GPosIdx(GlobalPositionTable::as_mut().add_pos(file, start, end))
+    }
+
+    /// Describe the rules that can be used at the location of a syntax error
+    fn describe(rule: Rule) -> Option<&'static str> {
+        Some(match rule {
+            Rule::identifier | Rule::param_var | Rule::unknown_fn => {
+                "an identifier"
+            }
+            Rule::bitwidth => "a number",
+            Rule::constant
+            | Rule::radix_lit
+            | Rule::hex_digits
+            | Rule::bin_digits
+            | Rule::dec_digits => "a constant",
+            Rule::string_lit => "a string",
+            Rule::event | Rule::quote => "an event",
+            Rule::expr | Rule::expr_base | Rule::r#fn | Rule::builtin_fn => {
+                "an expression"
+            }
+            Rule::time => "a time expression",
+            Rule::order_op
+            | Rule::gt
+            | Rule::gte
+            | Rule::lt
+            | Rule::lte
+            | Rule::eq => "a comparison",
+            Rule::op_add
+            | Rule::op_sub
+            | Rule::op_mul
+            | Rule::op_div
+            | Rule::op_mod
+            | Rule::op_and
+            | Rule::op_or
+            | Rule::op_not => "an operator",
+            Rule::command => "a command",
+            Rule::port => "a port",
+            Rule::port_def => "a port definition",
+            Rule::comp_or_ext | Rule::component | Rule::external => {
+                "a component"
+            }
+            Rule::fn_def => "a function",
+            Rule::param_range | Rule::range_incl | Rule::range_excl => {
+                "a range"
+            }
+            Rule::EOI => "end of file",
+            _ => return None,
+        })
+    }
+
+    /// Error for input that does not match the grammar at position `pos`.
+    /// The file is parsed again while tracking the tokens that were attempted
+    /// to report the expected tokens.
+    fn syntax_error(
+        pos: usize,
+        rules: &[Rule],
+        file: FileIdx,
+        content: &str,
+    ) -> utils::Error {
+        pest::set_error_detail(true);
+        let attempts = FilamentParser::parse(Rule::file, content)
+            .err()
+            .and_then(|e| e.parse_attempts());
+        pest::set_error_detail(false);
+
+        let (pos, tokens) = match attempts {
+            Some(a) => (a.max_position, a.expected_tokens()),
+            None => (pos, vec![]),
+        };
+        // Only report literal tokens and skip comments, character ranges,
+        // and builtins
+        let tokens = tokens
+            .into_iter()
+            .map(|t| t.to_string())
+            .filter(|t| {
+                let range =
+                    matches!(t.chars().collect_vec()[..], [_, '.', '.', _]);
+                !(range
+                    || t.trim().is_empty()
+                    || t == "BUILTIN_RULE"
+                    || t.starts_with("//")
+                    || t.starts_with("/*"))
+            })
+            .collect_vec();
+        let descs = rules
+            .iter()
+            .filter_map(|r| Self::describe(*r))
+            .unique()
+            .map(String::from)
+            .collect_vec();
+        // Long lists of tokens are summarized by the rules they belong to but
+        // closing delimiters are always mentioned
+        let summarize = tokens.len() > MAX_TOKENS && !descs.is_empty();
+        let expected = tokens
+            .iter()
+            .filter(|t| !summarize || matches!(t.as_str(), "}" | ")" | "]"))
+            .map(|t| format!("`{t}'"))
+            .chain(descs)
+            .collect_vec();
+
+        // The token at the error
+        let rest = &content[pos..];
+        let len = match rest.chars().next() {
+            None => 0,
+            Some(c) if c.is_alphanumeric() || c == '_' => rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len()),
+            Some(c) => c.len_utf8(),
+        };
+        let found = &rest[..len];
+        let msg = if found.is_empty() {
+            "unexpected end of file".to_string()
+        } else {
+            format!("unexpected `{found}'")
+        };
+        let label = match &expected[..] {
+            [] => "syntax error".to_string(),
+            [e] => format!("expected {e}"),
+            [es @ .., last] => format!("expected {} or {last}", es.join(", ")),
+        };
+
+        let mut help = vec![];
+        let before = content[..pos].trim_end();
+        if tokens.iter().any(|t| t == ";")
+            && content[before.len()..pos].contains('\n')
+        {
+            help.push(
+                "help: add `;' at the end of the previous line".to_string(),
+            );
+        }
+        if found == "=" && tokens.iter().any(|t| t == "==") {
+            help.push("help: use `==' to compare values".to_string());
+        }
+
+        let pos = Self::add_pos(file, pos, pos + len);
+        utils::Error::syntax(msg, pos, label, help)
+    }
+
     fn get_span(node: &Node) -> GPosIdx {
         let ud = node.user_data();
         let sp = node.as_span();
@@ -139,6 +300,32 @@ impl FilamentParser {
             })
             .parse(pairs)
     }
+
+    fn cmp_helper(
+        ud: UserData,
+        pairs: pest::iterators::Pairs<Rule>,
+    ) -> ParseResult<ast::Formula<ast::Expr>> {
+        CMP_PRATT
+            .map_primary(|primary| match primary.as_rule() {
+                Rule::cmp_base => Self::cmp_base(Node::new_with_user_data(
+                    primary,
+                    ud.clone(),
+                )),
+                x => unreachable!("Unexpected rule `{:?}' for cmp_helper", x),
+            })
+            .map_prefix(|op, f| match op.as_rule() {
+                Rule::op_not => Ok(!f?),
+                _ => unreachable!("Unknown prefix operator"),
+            })
+            .map_infix(|lhs, op, rhs| {
+                Ok(match op.as_rule() {
+                    Rule::op_and => ast::Formula::and(lhs?, rhs?),
+                    Rule::op_or => ast::Formula::or(lhs?, rhs?),
+                    _ => unreachable!("Unknown boolean operator"),
+                })
+            })
+            .parse(pairs)
+    }
 }
 
 #[pest_consume::parser]
@@ -171,7 +358,7 @@ impl FilamentParser {
         match_nodes!(
             input.clone().into_children();
             [quote(_), identifier(id)] => Ok(id),
-            [identifier(id)] => Err(input.error(format!("try replacing this with '{id}. Event must start with a single quote")))
+            [identifier(id)] => Err(input.error(format!("event `{id}' must begin with a quote\nhelp: write `'{id}' to refer to the event")))
         )
     }
 
@@ -191,12 +378,33 @@ impl FilamentParser {
         )
     }
 
+    fn range_incl(input: Node) -> ParseResult<()> {
+        Ok(())
+    }
+
+    fn range_excl(input: Node) -> ParseResult<()> {
+        Ok(())
+    }
+
+    fn param_range(input: Node) -> ParseResult<Loc<ast::ParamRange>> {
+        let sp = Self::get_span(&input);
+        let out = match_nodes!(
+            input.into_children();
+            [expr(lo), range_incl(_), expr(hi)] => ast::ParamRange::new(lo.take(), Some(hi.take()), true),
+            [expr(lo), range_excl(_), expr(hi)] => ast::ParamRange::new(lo.take(), Some(hi.take()), false),
+            [expr(lo), range_excl(_)] => ast::ParamRange::new(lo.take(), None, false)
+        );
+        Ok(Loc::new(out, sp))
+    }
+
     fn param_bind(input: Node) -> ParseResult<Loc<ast::ParamBind>> {
         let sp = Self::get_span(&input);
         let out = match_nodes!(
             input.into_children();
-            [param_var(param), expr(e)] => ast::ParamBind::new(param, Some(e.take())),
-            [param_var(param)] => ast::ParamBind::new(param, None)
+            [param_var(param), expr(e)] => ast::ParamBind::new(param, Some(e.take()), None),
+            [param_var(param), param_range(r), expr(e)] => ast::ParamBind::new(param, Some(e.take()), Some(r)),
+            [param_var(param)] => ast::ParamBind::new(param, None, None),
+            [param_var(param), param_range(r)] => ast::ParamBind::new(param, None, Some(r))
         );
         Ok(Loc::new(out, sp))
     }
@@ -212,6 +420,48 @@ impl FilamentParser {
         ))
     }
 
+    fn hex_digits(input: Node) -> ParseResult<(String, u32)> {
+        Ok((input.as_str().to_string(), 16))
+    }
+
+    fn bin_digits(input: Node) -> ParseResult<(String, u32)> {
+        Ok((input.as_str().to_string(), 2))
+    }
+
+    fn dec_digits(input: Node) -> ParseResult<(String, u32)> {
+        Ok((input.as_str().to_string(), 10))
+    }
+
+    fn radix_lit(input: Node) -> ParseResult<(String, u32)> {
+        Ok(match_nodes!(
+            input.into_children();
+            [hex_digits(d)] => d,
+            [bin_digits(d)] => d,
+            [dec_digits(d)] => d,
+        ))
+    }
+
+    fn constant(input: Node) -> ParseResult<ast::Constant> {
+        let ((digits, radix), width) = match_nodes!(
+            input.clone().into_children();
+            [bitwidth(w), radix_lit(d)] => (d, Some(w)),
+            [hex_digits(d)] => (d, None),
+            [bin_digits(d)] => (d, None),
+            [dec_digits(d)] => (d, None),
+        );
+        let c = ast::Constant::parse(&digits, radix, width)
+            .ok_or_else(|| input.error("malformed constant"))?;
+        match width {
+            Some(0) => Err(input.error("constants must be at least one bit wide")),
+            Some(w) if c.bits() > w => Err(input.error(format!(
+                "constant `{}' does not fit in {w} bits\nhelp: the value needs {} bits",
+                input.as_str(),
+                c.bits()
+            ))),
+            _ => Ok(c),
+        }
+    }
+
     fn bitwidth(input: Node) -> ParseResult<u64> {
         input
             .as_str()
@@ -228,7 +478,7 @@ impl FilamentParser {
             [expr(sts), event(ev)] => Ok(Loc::new(ast::Time::new(ev.take(), sts.take()), sp)),
             [event(ev)] => Ok(Loc::new(ast::Time::new(ev.take(), ast::Expr::default()), sp)),
             [expr(_)] => {
-                Err(input.error("time expressions must have the form `E+n' where `E' is an event and `n' is a concrete number or sum of parameters"))
+                Err(input.error("time expressions must have the form `E+n'\nhelp: `E' is an event and `n' is a concrete number or sum of parameters"))
             }
         )
     }
@@ -250,32 +500,40 @@ impl FilamentParser {
     }
 
     fn builtin_fn(input: Node) -> ParseResult<ast::Fn> {
-        Ok(match input.as_str() {
-            "pow2" => ast::Fn::Pow2,
-            "log2" => ast::Fn::Log2,
-            "sin_bits" => ast::Fn::SinB,
-            "cos_bits" => ast::Fn::CosB,
-            _ => unreachable!(),
-        })
+        Ok(ast::Fn::builtin(input.as_str()).unwrap())
     }
     fn unknown_fn(input: Node) -> ParseResult<ast::Fn> {
-        Err(input.error("Unknown function"))
+        // Calls to functions defined in the program are resolved later
+        Ok(ast::Fn::User(ast::Id::from(input.as_str())))
     }
-    fn r#fn(input: Node) -> ParseResult<ast::Fn> {
+    fn r#fn(input: Node) -> ParseResult<Loc<ast::Fn>> {
+        let sp = Self::get_span(&input);
         Ok(match_nodes!(
             input.into_children();
-            [builtin_fn(f)] => f,
-            [unknown_fn(_)] => unreachable!(),
+            [builtin_fn(f)] => Loc::new(f, sp),
+            [unknown_fn(f)] => Loc::new(f, sp),
         ))
     }
 
     fn expr_base(input: Node) -> ParseResult<ast::Expr> {
         Ok(match_nodes!(
-            input.into_children();
+            input.clone().into_children();
             [identifier(inst), identifier(param)] => ast::Expr::ParamAccess{ inst, param },
             [param_var(id)] => ast::Expr::abs(id),
             [bitwidth(c)] => c.into(),
-            [r#fn(f), expr(exprs)..] => ast::Expr::func(f, exprs.into_iter().map(|e| e.take()).collect()),
+            [r#fn(f), expr(exprs)..] => {
+                let args = exprs.into_iter().map(|e| e.take()).collect_vec();
+                match f.inner().arity() {
+                    Some(n) if n != args.len() => {
+                        return Err(input.error(format!(
+                            "`{f}' expects {n} arguments but was given {}",
+                            args.len()
+                        )));
+                    }
+                    _ => (),
+                }
+                ast::Expr::func(f, args)
+            },
             [expr(e)] => e.take(),
         ))
     }
@@ -298,7 +556,7 @@ impl FilamentParser {
                 Ok(Port::Un((name.take(), n)))
             },
             [bundle_def(bd)] => {
-                Ok(Port::Pd(Loc::new(bd.into(), sp)))
+                Ok(Port::Pd(Box::new(Loc::new(bd.into(), sp))))
             },
         )
     }
@@ -353,7 +611,7 @@ impl FilamentParser {
                 let mut unannotated_ports = vec![];
                 for m in ins {
                     match m {
-                        Port::Pd(port) => ports.push(port),
+                        Port::Pd(port) => ports.push(*port),
                         Port::Int(int) => interface_signals.push(int),
                         Port::Un(un) => unannotated_ports.push(un)
                     }
@@ -445,7 +703,7 @@ impl FilamentParser {
         let sp = Self::get_span(&input);
         match_nodes!(
             input.clone().into_children();
-            [bitwidth(_)] => Err(input.error("constant ports are not supported. Use the `Const[Width, Val]' primitive instead.")),
+            [constant(c)] => Ok(Loc::new(ast::Port::Constant(c), sp)),
             [identifier(name)] => Ok(Loc::new(ast::Port::this(name), sp)),
             [identifier(name), access(range)] => Ok(Loc::new(ast::Port::bundle(name, range), sp)),
             [identifier(comp), identifier(name)] => Ok(Loc::new(ast::Port::inv_port(comp, name), sp)),
@@ -536,18 +794,7 @@ impl FilamentParser {
                 };
                 Ok(Loc::new(FCons::TimeC(con), sp))
             },
-            [
-                expr(l),
-                order_op((op, rev)),
-                expr(r)
-            ] => {
-                let con = if !rev {
-                    ast::OrderConstraint::new(l.take(), r.take(), op)
-                } else {
-                    ast::OrderConstraint::new(r.take(), l.take(), op)
-                };
-                Ok(Loc::new(FCons::ExprC(con), sp))
-            }
+            [expr_cmp(con)] => Ok(Loc::new(FCons::ExprC(con), sp))
         )
     }
 
@@ -555,7 +802,7 @@ impl FilamentParser {
     fn constraints(
         input: Node,
     ) -> ParseResult<(
-        Vec<Loc<ast::OrderConstraint<ast::Expr>>>,
+        Vec<Loc<ast::Formula<ast::Expr>>>,
         Vec<Loc<ast::OrderConstraint<ast::Time>>>,
     )> {
         Ok(match_nodes!(
@@ -647,23 +894,31 @@ impl FilamentParser {
         ))
     }
 
-    fn expr_cmp(input: Node) -> ParseResult<ast::OrderConstraint<ast::Expr>> {
+    fn cmp_base(input: Node) -> ParseResult<ast::Formula<ast::Expr>> {
         Ok(match_nodes!(
             input.into_children();
+            [expr_cmp(f)] => f,
             [expr(l), order_op((op, rev)), expr(r)] => {
                 if !rev {
-                    ast::OrderConstraint::new(l.take(), r.take(), op)
+                    ast::OrderConstraint::new(l.take(), r.take(), op).into()
                 } else {
-                    ast::OrderConstraint::new(r.take(), l.take(), op)
+                    ast::OrderConstraint::new(r.take(), l.take(), op).into()
                 }
             }
         ))
     }
 
+    fn expr_cmp(input: Node) -> ParseResult<ast::Formula<ast::Expr>> {
+        let ud = input.user_data().clone();
+        Self::cmp_helper(ud, input.into_pair().into_inner())
+    }
+
     fn if_stmt(input: Node) -> ParseResult<ast::If> {
         Ok(match_nodes!(
             input.into_children();
             [expr_cmp(cond), commands(then), commands(else_)] => ast::If::new(cond, then, else_),
+            // `else if` is a conditional nested in the `else` branch
+            [expr_cmp(cond), commands(then), if_stmt(alt)] => ast::If::new(cond, then, vec![alt.into()]),
             [expr_cmp(cond), commands(then)] => ast::If::new(cond, then, vec![])
         ))
     }
@@ -785,11 +1040,36 @@ impl FilamentParser {
         ))
     }
 
+    fn fn_params(input: Node) -> ParseResult<Vec<Loc<ast::Id>>> {
+        Ok(match_nodes!(
+            input.into_children();
+            [param_var(params)..] => params.collect(),
+        ))
+    }
+
+    fn fn_def(input: Node) -> ParseResult<ast::FnDef> {
+        match_nodes!(
+            input.clone().into_children();
+            [
+                identifier(name),
+                fn_params(params),
+                expr(body),
+                constraints((pres, time))
+            ] => {
+                if !time.is_empty() {
+                    return Err(input.error("Cannot specify event constraints in a function precondition"));
+                }
+                Ok(ast::FnDef::new(name, params, body, pres))
+            }
+        )
+    }
+
     fn comp_or_ext(input: Node) -> ParseResult<ExtOrComp> {
         Ok(match_nodes!(
             input.into_children();
             [external(sig)] => ExtOrComp::Ext(sig),
             [component(comp)] => ExtOrComp::Comp(comp),
+            [fn_def(def)] => ExtOrComp::Fn(def),
         ))
     }
 
@@ -808,12 +1088,15 @@ impl FilamentParser {
                     imports: imps,
                     externs: vec![],
                     components: vec![],
+                    fns: vec![],
                     toplevel: "main".to_string(),
+                    library: vec![],
                 };
                 for m in mixed {
                     match m {
                         ExtOrComp::Ext(sig) => namespace.externs.push(sig),
                         ExtOrComp::Comp(comp) => namespace.components.push(comp),
+                        ExtOrComp::Fn(def) => namespace.fns.push(def),
                     }
                 }
                 namespace
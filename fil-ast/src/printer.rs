@@ -0,0 +1,805 @@
+//! Formatter for Filament programs.
+//! The formatter prints the parse tree generated by the grammar instead of the
+//! AST so that the program is printed as it was written. Comments are not a
+//! part of the parse tree and are recovered from the source text between the
+//! tokens.
+use crate::parser::{FilamentParser, Rule};
+use fil_utils::{self as utils, FilamentResult};
+use pest::iterators::Pair;
+use pest_consume::Parser;
+use std::path::Path;
+
+type Node<'i> = Pair<'i, Rule>;
+
+/// Indentation for each level of nesting
+const INDENT: &str = "    ";
+
+/// A comment in the source program
+struct Comment {
+    text: String,
+    /// The comment is on the same line as the previous token
+    trailing: bool,
+    /// The comment is preceded by a blank line
+    blank: bool,
+}
+
+struct Printer<'i> {
+    src: &'i str,
+    out: String,
+    /// The line being printed
+    line: String,
+    indent: usize,
+    /// Offset in the source up to which comments have been collected
+    pos: usize,
+    /// Comments that have been collected but not printed
+    pending: Vec<Comment>,
+    /// The source has a blank line right before `pos`
+    blank: bool,
+    /// Nothing has been printed in the current block
+    fresh: bool,
+}
+
+impl<'i> Printer<'i> {
+    fn new(src: &'i str) -> Self {
+        Self {
+            src,
+            out: String::new(),
+            line: String::new(),
+            indent: 0,
+            pos: 0,
+            pending: vec![],
+            blank: false,
+            fresh: true,
+        }
+    }
+
+    /// Collect the comments between the last token and `upto`.
+    fn gap(&mut self, upto: usize) {
+        if upto <= self.pos {
+            return;
+        }
+        let text = &self.src[self.pos..upto];
+        // Comments at the start of the file are never trailing
+        let mut newlines = usize::from(self.pos == 0);
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let len = if rest.starts_with("//") {
+                rest.find('\n').unwrap_or(rest.len())
+            } else if rest.starts_with("/*") {
+                rest.find("*/").map_or(rest.len(), |e| e + 2)
+            } else {
+                let c = rest.chars().next().unwrap();
+                if c == '\n' {
+                    newlines += 1;
+                } else if !c.is_whitespace() {
+                    newlines = 0;
+                }
+                i += c.len_utf8();
+                continue;
+            };
+            self.pending.push(Comment {
+                text: rest[..len].trim_end().to_string(),
+                trailing: newlines == 0,
+                blank: newlines > 1,
+            });
+            newlines = 0;
+            i += len;
+        }
+        self.blank = newlines > 1;
+        self.pos = upto;
+    }
+
+    /// Offset of the first token at or after `from`
+    fn skip(&self, from: usize) -> usize {
+        let mut i = from;
+        while i < self.src.len() {
+            let rest = &self.src[i..];
+            if rest.starts_with("//") {
+                i += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                i += rest.find("*/").map_or(rest.len(), |e| e + 2);
+            } else {
+                let c = rest.chars().next().unwrap();
+                if !c.is_whitespace() {
+                    break;
+                }
+                i += c.len_utf8();
+            }
+        }
+        i
+    }
+
+    /// Offset of the keyword that precedes `node` but is not a part of it
+    fn keyword(&self, node: &Node, kw: &str) -> usize {
+        let start = node.as_span().start();
+        self.src[..start].rfind(kw).unwrap_or(start)
+    }
+
+    fn w(&mut self, s: &str) {
+        self.line.push_str(s);
+    }
+
+    /// Print a token from the source
+    fn tok(&mut self, node: &Node) {
+        let span = node.as_span();
+        self.gap(span.start());
+        self.w(span.as_str());
+        self.pos = span.end();
+    }
+
+    /// End the current line
+    fn nl(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if !line.is_empty() {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.out.push_str(line.trim_end());
+        }
+        self.out.push('\n');
+        self.fresh = false;
+    }
+
+    /// Print a blank line unless nothing has been printed in the block
+    fn blank_line(&mut self) {
+        if !self.fresh && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Print the comments before `upto`. Trailing comments are added to the
+    /// end of the last line and the others are printed on their own lines.
+    /// If `sep` is true, the comments are separated from the previous line
+    /// using a blank line.
+    /// Returns true if no comments were printed on their own lines.
+    fn comments(&mut self, upto: usize, sep: bool) -> bool {
+        self.gap(upto);
+        let mut first = true;
+        for c in std::mem::take(&mut self.pending) {
+            if c.trailing && first && !self.out.is_empty() {
+                let trimmed = self.out.trim_end_matches('\n').len();
+                let newlines = self.out.len() - trimmed;
+                self.out.truncate(trimmed);
+                self.out.push(' ');
+                self.out.push_str(&c.text);
+                self.out.push_str(&"\n".repeat(newlines));
+                continue;
+            }
+            if c.blank || (sep && first) {
+                self.blank_line();
+            }
+            first = false;
+            self.w(&c.text);
+            self.nl();
+        }
+        first
+    }
+
+    /// Start a new item in a block. Blank lines between items in the source
+    /// are preserved.
+    fn item(&mut self, node: &Node, sep: bool) {
+        self.item_at(node.as_span().start(), sep)
+    }
+
+    /// Start a new item that begins at `start`
+    fn item_at(&mut self, start: usize, sep: bool) {
+        let first = self.comments(start, sep);
+        if self.blank || (sep && first) {
+            self.blank_line();
+        }
+    }
+
+    fn open(&mut self) {
+        self.nl();
+        self.indent += 1;
+        self.fresh = true;
+    }
+
+    /// Close a block that ends at `upto`
+    fn close(&mut self, upto: usize) {
+        self.comments(upto, false);
+        self.indent -= 1;
+    }
+
+    /// Print a comma-separated list
+    fn list<F>(&mut self, nodes: impl IntoIterator<Item = Node<'i>>, mut f: F)
+    where
+        F: FnMut(&mut Self, Node<'i>),
+    {
+        for (i, node) in nodes.into_iter().enumerate() {
+            if i > 0 {
+                self.w(", ");
+            }
+            f(self, node);
+        }
+    }
+
+    fn file(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::imports => {
+                    for imp in n.into_inner() {
+                        self.item_at(self.keyword(&imp, "import"), false);
+                        self.w("import ");
+                        self.tok(&imp);
+                        self.w(";");
+                        self.nl();
+                    }
+                }
+                Rule::comp_or_ext => {
+                    let n = n.into_inner().next().unwrap();
+                    // Consecutive functions are kept together
+                    self.item(&n, n.as_rule() != Rule::fn_def);
+                    match n.as_rule() {
+                        Rule::component => self.component(n),
+                        Rule::external => self.external(n),
+                        Rule::fn_def => self.fn_def(n),
+                        r => unreachable!("unexpected rule `{r:?}'"),
+                    }
+                }
+                Rule::EOI => {
+                    self.comments(n.as_span().start(), false);
+                }
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    fn component(&mut self, node: Node<'i>) {
+        let end = node.as_span().end();
+        let mut nodes = node.into_inner();
+        self.w("comp ");
+        self.signature(nodes.next().unwrap());
+        self.w(" {");
+        self.open();
+        for cmd in nodes {
+            self.command(cmd);
+        }
+        self.close(end - 1);
+        self.w("}");
+        self.nl();
+    }
+
+    fn external(&mut self, node: Node<'i>) {
+        let end = node.as_span().end();
+        let mut nodes = node.into_inner();
+        self.w("extern ");
+        self.tok(&nodes.next().unwrap());
+        self.w(" {");
+        self.open();
+        for sig in nodes {
+            self.item_at(self.keyword(&sig, "comp"), false);
+            self.w("comp ");
+            self.signature(sig);
+            self.w(";");
+            self.nl();
+        }
+        self.close(end - 1);
+        self.w("}");
+        self.nl();
+    }
+
+    fn fn_def(&mut self, node: Node<'i>) {
+        let mut nodes = node.into_inner();
+        self.w("fn ");
+        self.tok(&nodes.next().unwrap());
+        self.w("(");
+        self.list(nodes.next().unwrap().into_inner(), |this, n| this.tok(&n));
+        self.w(") -> ");
+        for n in nodes {
+            match n.as_rule() {
+                Rule::expr => self.expr(n),
+                Rule::constraints => self.constraints(n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+        self.w(";");
+        self.nl();
+    }
+
+    // ============== Signatures ==============
+
+    fn signature(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::identifier => self.tok(&n),
+                Rule::params => {
+                    if n.clone().into_inner().next().is_some() {
+                        self.w("[");
+                        self.list(n.into_inner(), Self::param_bind);
+                        self.w("]");
+                    }
+                }
+                Rule::abstract_var => {
+                    self.w("<");
+                    self.list(n.into_inner(), Self::event_bind);
+                    self.w(">");
+                }
+                Rule::io => self.io(n),
+                Rule::sig_bindings => {
+                    let end = n.as_span().end();
+                    let binds = n.into_inner().collect::<Vec<_>>();
+                    if !binds.is_empty() {
+                        self.w(" with {");
+                        self.open();
+                        for b in binds {
+                            self.item(&b, false);
+                            self.sig_bind(b);
+                            self.nl();
+                        }
+                        self.close(end - 1);
+                        self.w("}");
+                    }
+                }
+                Rule::constraints => self.constraints(n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    fn param_bind(&mut self, node: Node<'i>) {
+        let mut nodes = node.into_inner().collect::<Vec<_>>();
+        let default = (nodes.last().unwrap().as_rule() == Rule::expr)
+            .then(|| nodes.pop().unwrap());
+        if default.is_some() {
+            self.w("?");
+        }
+        let mut nodes = nodes.into_iter();
+        self.tok(&nodes.next().unwrap());
+        let range = nodes.next();
+        if let Some(range) = &range {
+            self.w(": ");
+            self.param_range(range.clone());
+        }
+        if let Some(default) = default {
+            // A range without an upper bound must be separated from the
+            // default so that it does not parse as an inclusive range.
+            self.w(if range.is_some() { " = " } else { "=" });
+            self.expr(default);
+        }
+    }
+
+    fn param_range(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::expr => self.expr(n),
+                _ => self.tok(&n),
+            }
+        }
+    }
+
+    fn event_bind(&mut self, node: Node<'i>) {
+        let mut nodes = node.into_inner().collect::<Vec<_>>();
+        let default = (nodes.len() == 3).then(|| nodes.pop().unwrap());
+        if default.is_some() {
+            self.w("?");
+        }
+        let [event, delay] = <[_; 2]>::try_from(nodes).unwrap();
+        self.event(event);
+        self.w(": ");
+        self.delay(delay);
+        if let Some(time) = default {
+            self.w("=");
+            self.time(time);
+        }
+    }
+
+    fn delay(&mut self, node: Node<'i>) {
+        let nodes = node.into_inner().collect::<Vec<_>>();
+        match nodes.as_slice() {
+            [e] => self.expr(e.clone()),
+            [end, start] => {
+                self.time(end.clone());
+                self.w("-(");
+                self.time(start.clone());
+                self.w(")");
+            }
+            _ => unreachable!("malformed delay"),
+        }
+    }
+
+    fn io(&mut self, node: Node<'i>) {
+        let end = node.as_span().end();
+        let (mut inputs, mut arrow, mut outputs) = (None, None, None);
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::ports if arrow.is_none() => inputs = Some(n),
+                Rule::ports => outputs = Some(n),
+                Rule::arrow => arrow = Some(n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+        let arrow = arrow.unwrap();
+        self.w("(");
+        self.ports(inputs, arrow.as_span().start());
+        self.w(") ");
+        self.tok(&arrow);
+        self.w(" (");
+        self.ports(outputs, end - 1);
+        self.w(")");
+    }
+
+    /// Print each port on its own line
+    fn ports(&mut self, ports: Option<Node<'i>>, end: usize) {
+        let Some(ports) = ports else {
+            return;
+        };
+        self.open();
+        for port in ports.into_inner() {
+            self.item(&port, false);
+            self.port_def(port);
+            self.w(",");
+            self.nl();
+        }
+        self.close(end);
+    }
+
+    fn port_def(&mut self, node: Node<'i>) {
+        let nodes = node.into_inner().collect::<Vec<_>>();
+        match nodes.as_slice() {
+            [bundle] => self.bundle_def(bundle.clone()),
+            [name, typ] => {
+                self.tok(name);
+                self.w(": ");
+                match typ.as_rule() {
+                    Rule::bitwidth => self.tok(typ),
+                    Rule::interface => {
+                        self.w("interface[");
+                        self.event(typ.clone().into_inner().next().unwrap());
+                        self.w("]");
+                    }
+                    r => unreachable!("unexpected rule `{r:?}'"),
+                }
+            }
+            _ => unreachable!("malformed port definition"),
+        }
+    }
+
+    fn bundle_def(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::identifier => self.tok(&n),
+                Rule::expr => {
+                    self.w("[");
+                    self.expr(n);
+                    self.w("]");
+                }
+                Rule::bundle_typ => {
+                    self.w(": ");
+                    self.bundle_typ(n);
+                }
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    fn bundle_typ(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::param_var => {
+                    self.w("for<");
+                    self.tok(&n);
+                    self.w("> ");
+                }
+                Rule::interval_range => {
+                    self.w("[");
+                    self.list(n.into_inner(), Self::time);
+                    self.w("] ");
+                }
+                Rule::expr => self.expr(n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    fn sig_bind(&mut self, node: Node<'i>) {
+        let nodes = node.into_inner().collect::<Vec<_>>();
+        let [var, n] = nodes.as_slice() else {
+            unreachable!("malformed signature binding")
+        };
+        match n.as_rule() {
+            Rule::expr => {
+                self.w("let ");
+                self.tok(var);
+                self.w(" = ");
+                self.expr(n.clone());
+            }
+            Rule::constraints => {
+                self.w("exists ");
+                self.tok(var);
+                self.constraints(n.clone());
+            }
+            r => unreachable!("unexpected rule `{r:?}'"),
+        }
+        self.w(";");
+    }
+
+    fn constraints(&mut self, node: Node<'i>) {
+        if node.clone().into_inner().next().is_some() {
+            self.w(" where ");
+            self.list(node.into_inner(), Self::constraint);
+        }
+    }
+
+    /// Print a constraint over expressions or times
+    fn constraint(&mut self, node: Node<'i>) {
+        let first = node.clone().into_inner().next().unwrap();
+        match first.as_rule() {
+            Rule::expr_cmp => self.expr_cmp(first),
+            _ => self.comparison(node),
+        }
+    }
+
+    /// Print a boolean combination of comparisons
+    fn expr_cmp(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::op_not => self.tok(&n),
+                Rule::op_and | Rule::op_or => {
+                    self.w(" ");
+                    self.tok(&n);
+                    self.w(" ");
+                }
+                Rule::cmp_base => {
+                    let first = n.clone().into_inner().next().unwrap();
+                    if first.as_rule() == Rule::expr_cmp {
+                        self.w("(");
+                        self.expr_cmp(first);
+                        self.w(")");
+                    } else {
+                        self.comparison(n);
+                    }
+                }
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    /// Print a comparison between two expressions or times
+    fn comparison(&mut self, node: Node<'i>) {
+        for (i, n) in node.into_inner().enumerate() {
+            match n.as_rule() {
+                Rule::expr => self.expr(n),
+                Rule::time => self.time(n),
+                Rule::order_op => self.tok(&n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+            if i < 2 {
+                self.w(" ");
+            }
+        }
+    }
+
+    // ============== Expressions ==============
+
+    fn expr(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::expr_base => self.expr_base(n),
+                _ => self.tok(&n),
+            }
+        }
+    }
+
+    fn expr_base(&mut self, node: Node<'i>) {
+        let mut nodes = node.into_inner().peekable();
+        let first = nodes.next().unwrap();
+        match first.as_rule() {
+            Rule::r#fn => {
+                self.tok(&first);
+                self.w("(");
+                self.list(nodes, Self::expr);
+                self.w(")");
+            }
+            Rule::expr => {
+                self.w("(");
+                self.expr(first);
+                self.w(")");
+            }
+            Rule::identifier => {
+                self.tok(&first);
+                self.w("::");
+                self.tok(&nodes.next().unwrap());
+            }
+            _ => self.tok(&first),
+        }
+    }
+
+    fn event(&mut self, node: Node<'i>) {
+        for n in node.into_inner() {
+            self.tok(&n);
+        }
+    }
+
+    fn time(&mut self, node: Node<'i>) {
+        for (i, n) in node.into_inner().enumerate() {
+            if i > 0 {
+                self.w("+");
+            }
+            match n.as_rule() {
+                Rule::event => self.event(n),
+                Rule::expr => self.expr(n),
+                r => unreachable!("unexpected rule `{r:?}'"),
+            }
+        }
+    }
+
+    // ============== Commands ==============
+
+    fn command(&mut self, node: Node<'i>) {
+        self.item(&node, false);
+        let cmd = node.into_inner().next().unwrap();
+        match cmd.as_rule() {
+            Rule::bundle => {
+                self.w("bundle ");
+                self.bundle_def(cmd.into_inner().next().unwrap());
+                self.w(";");
+            }
+            Rule::instance => {
+                let mut nodes = cmd.into_inner();
+                self.tok(&nodes.next().unwrap());
+                self.w(" := new ");
+                self.tok(&nodes.next().unwrap());
+                for n in nodes {
+                    match n.as_rule() {
+                        Rule::conc_params => {
+                            if n.clone().into_inner().next().is_some() {
+                                self.w("[");
+                                self.list(n.into_inner(), Self::expr);
+                                self.w("]");
+                            }
+                        }
+                        Rule::invoke_args => self.invoke_args(n),
+                        r => unreachable!("unexpected rule `{r:?}'"),
+                    }
+                }
+                self.w(";");
+            }
+            Rule::invocation => {
+                let mut nodes = cmd.into_inner();
+                self.tok(&nodes.next().unwrap());
+                self.w(" := ");
+                self.tok(&nodes.next().unwrap());
+                self.invoke_args(nodes.next().unwrap());
+                self.w(";");
+            }
+            Rule::connect => {
+                let mut nodes = cmd.into_inner();
+                self.port(nodes.next().unwrap());
+                self.w(" = ");
+                self.port(nodes.next().unwrap());
+                self.w(";");
+            }
+            Rule::for_loop => {
+                let end = cmd.as_span().end();
+                let mut nodes = cmd.into_inner();
+                self.w("for ");
+                self.tok(&nodes.next().unwrap());
+                self.w(" in ");
+                self.expr(nodes.next().unwrap());
+                self.w("..");
+                self.expr(nodes.next().unwrap());
+                self.w(" {");
+                self.block(nodes.next().unwrap(), end - 1);
+                self.w("}");
+            }
+            Rule::if_stmt => self.if_stmt(cmd),
+            Rule::fact => {
+                let mut nodes = cmd.into_inner();
+                self.tok(&nodes.next().unwrap());
+                self.w(" ");
+                let imp = nodes.next().unwrap();
+                for (i, n) in imp.into_inner().enumerate() {
+                    if i > 0 {
+                        self.w(" => ");
+                    }
+                    self.expr_cmp(n);
+                }
+                self.w(";");
+            }
+            Rule::param_let | Rule::exists => {
+                self.w(if cmd.as_rule() == Rule::param_let {
+                    "let "
+                } else {
+                    "exists "
+                });
+                let mut nodes = cmd.into_inner();
+                self.tok(&nodes.next().unwrap());
+                self.w(" = ");
+                self.expr(nodes.next().unwrap());
+                self.w(";");
+            }
+            r => unreachable!("unexpected rule `{r:?}'"),
+        }
+        self.nl();
+    }
+
+    /// Print a conditional along with its `else if` chain
+    fn if_stmt(&mut self, node: Node<'i>) {
+        let end = node.as_span().end();
+        let mut nodes = node.into_inner();
+        self.w("if ");
+        self.expr_cmp(nodes.next().unwrap());
+        self.w(" {");
+        let then = nodes.next().unwrap();
+        let alt = nodes.next();
+        let then_end = match alt {
+            Some(_) => self.skip(then.as_span().end()),
+            None => end - 1,
+        };
+        self.block(then, then_end);
+        self.w("}");
+        match alt {
+            Some(alt) if alt.as_rule() == Rule::if_stmt => {
+                self.w(" else ");
+                self.if_stmt(alt);
+            }
+            Some(alt) => {
+                self.w(" else {");
+                self.block(alt, end - 1);
+                self.w("}");
+            }
+            None => (),
+        }
+    }
+
+    /// Print the commands in a block that ends at `end`
+    fn block(&mut self, node: Node<'i>, end: usize) {
+        self.open();
+        for cmd in node.into_inner() {
+            self.command(cmd);
+        }
+        self.close(end);
+    }
+
+    fn invoke_args(&mut self, node: Node<'i>) {
+        let mut nodes = node.into_inner();
+        self.w("<");
+        self.list(nodes.next().unwrap().into_inner(), Self::time);
+        self.w(">(");
+        self.list(nodes.next().unwrap().into_inner(), Self::port);
+        self.w(")");
+    }
+
+    fn port(&mut self, node: Node<'i>) {
+        let mut first = true;
+        for n in node.into_inner() {
+            match n.as_rule() {
+                Rule::access => {
+                    self.w("{");
+                    for e in n.into_inner() {
+                        match e.as_rule() {
+                            Rule::dots => self.tok(&e),
+                            _ => self.expr(e),
+                        }
+                    }
+                    self.w("}");
+                }
+                _ => {
+                    if !first {
+                        self.w(".");
+                    }
+                    self.tok(&n);
+                }
+            }
+            first = false;
+        }
+    }
+}
+
+/// Format the program in `src` using the standard style. `path` is only used
+/// to report parse errors.
+pub fn format_source(src: &str, path: &Path) -> FilamentResult<String> {
+    let file = FilamentParser::parse(Rule::file, src)
+        .and_then(|nodes| nodes.single())
+        .map_err(|e| {
+            utils::Error::misc(format!(
+                "Failed to parse {}",
+                e.with_path(&path.to_string_lossy())
+            ))
+        })?;
+    let mut printer = Printer::new(src);
+    printer.file(file.into_pair());
+    Ok(printer.out)
+}
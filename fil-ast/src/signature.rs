@@ -1,6 +1,6 @@
 use super::{
-    Binding, Expr, Id, InterfaceDef, Loc, OrderConstraint, PortDef, Time,
-    TimeSub,
+    Binding, Expr, Formula, Id, InterfaceDef, Loc, OrderConstraint, PortDef,
+    Time, TimeSub,
 };
 use fil_utils::GPosIdx;
 
@@ -44,16 +44,71 @@ impl EventBind {
     }
 }
 
+#[derive(Clone)]
+/// The range of values a signature parameter can take: `lo..hi`, `lo..=hi`,
+/// or `lo..`. Like for loops, `lo..hi` excludes the upper bound.
+pub struct ParamRange {
+    pub lo: Expr,
+    pub hi: Option<Expr>,
+    /// The upper bound is included in the range
+    pub inclusive: bool,
+}
+
+impl ParamRange {
+    pub fn new(lo: Expr, hi: Option<Expr>, inclusive: bool) -> Self {
+        Self { lo, hi, inclusive }
+    }
+
+    /// Constraints that restrict the parameter to the range
+    pub fn constraints(&self, param: Loc<Id>) -> Vec<Formula<Expr>> {
+        let p = Expr::abs(param);
+        let mut cons =
+            vec![OrderConstraint::gte(p.clone(), self.lo.clone()).into()];
+        if let Some(hi) = &self.hi {
+            let hi = if self.inclusive {
+                OrderConstraint::lte(p, hi.clone())
+            } else {
+                OrderConstraint::lt(p, hi.clone())
+            };
+            cons.push(hi.into());
+        }
+        cons
+    }
+}
+
+impl std::fmt::Display for ParamRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..", self.lo)?;
+        if self.inclusive {
+            write!(f, "=")?;
+        }
+        if let Some(hi) = &self.hi {
+            write!(f, "{hi}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 /// A parameter bound in the signature
 pub struct ParamBind {
     pub param: Loc<Id>,
     pub default: Option<Expr>,
+    /// Values the parameter is allowed to take
+    pub range: Option<Loc<ParamRange>>,
 }
 
 impl ParamBind {
-    pub fn new(param: Loc<Id>, default: Option<Expr>) -> Self {
-        Self { param, default }
+    pub fn new(
+        param: Loc<Id>,
+        default: Option<Expr>,
+        range: Option<Loc<ParamRange>>,
+    ) -> Self {
+        Self {
+            param,
+            default,
+            range,
+        }
     }
 
     pub fn name(&self) -> Id {
@@ -67,7 +122,7 @@ impl ParamBind {
 
 impl From<Loc<Id>> for ParamBind {
     fn from(value: Loc<Id>) -> Self {
-        ParamBind::new(value, None)
+        ParamBind::new(value, None, None)
     }
 }
 
@@ -85,17 +140,14 @@ pub enum SigBind {
     /// An existentially quantified type
     Exists {
         param: Loc<Id>,
-        cons: Vec<Loc<OrderConstraint<Expr>>>,
+        cons: Vec<Loc<Formula<Expr>>>,
     },
 }
 impl SigBind {
     pub fn let_(param: Loc<Id>, bind: Expr) -> Self {
         Self::Let { param, bind }
     }
-    pub fn exists(
-        param: Loc<Id>,
-        cons: Vec<Loc<OrderConstraint<Expr>>>,
-    ) -> Self {
+    pub fn exists(param: Loc<Id>, cons: Vec<Loc<Formula<Expr>>>) -> Self {
         Self::Exists { param, cons }
     }
 }
@@ -117,7 +169,7 @@ pub struct Signature {
     /// Names of abstract variables bound by the component
     pub events: Vec<Loc<EventBind>>,
     /// Constraints over the parameters in the signature
-    pub param_constraints: Vec<Loc<OrderConstraint<Expr>>>,
+    pub param_constraints: Vec<Loc<Formula<Expr>>>,
     /// Constraints over events in the signature
     pub event_constraints: Vec<Loc<OrderConstraint<Time>>>,
     /// All the input/output ports.
@@ -136,7 +188,7 @@ impl Signature {
         interface_signals: Vec<InterfaceDef>,
         mut inputs: Vec<Loc<PortDef>>,
         mut outputs: Vec<Loc<PortDef>>,
-        param_constraints: Vec<Loc<OrderConstraint<Expr>>>,
+        param_constraints: Vec<Loc<Formula<Expr>>>,
         event_constraints: Vec<Loc<OrderConstraint<Time>>>,
         sig_bindings: Vec<Loc<SigBind>>,
     ) -> Self {
@@ -164,6 +216,20 @@ impl Signature {
     pub fn params(&self) -> impl Iterator<Item = Loc<Id>> + '_ {
         self.params.iter().map(|eb| &eb.param).cloned()
     }
+    /// Constraints over the parameters including the ones implied by the
+    /// declared parameter ranges
+    pub fn all_param_constraints(&self) -> Vec<Loc<Formula<Expr>>> {
+        self.params
+            .iter()
+            .filter_map(|pb| pb.range.as_ref().map(|r| (&pb.param, r)))
+            .flat_map(|(p, r)| {
+                r.constraints(p.clone())
+                    .into_iter()
+                    .map(|c| Loc::new(c, r.pos()))
+            })
+            .chain(self.param_constraints.iter().cloned())
+            .collect()
+    }
     /// Inputs of this signature
     pub fn inputs(&self) -> impl Iterator<Item = &Loc<PortDef>> {
         self.ports[..self.outputs_idx].iter()
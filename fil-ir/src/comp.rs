@@ -4,7 +4,7 @@ use super::{
     InvIdx, Invoke, MutCtx, Param, ParamIdx, Port, PortIdx, Prop, PropIdx,
     Time, TimeSub,
 };
-use crate::{utils::Idx, ParamOwner};
+use crate::{utils::Idx, Funcs, ParamOwner};
 use fil_ast as ast;
 use fil_derive::Ctx;
 use itertools::Itertools;
@@ -47,6 +47,12 @@ pub struct Component {
     // ============== Component structure ===============
     /// Assumptions for existential parameters.
     pub sig_assumes: Vec<(ParamIdx, Vec<PropIdx>)>,
+    /// Information for assumptions that have been folded into the path
+    /// conditions of assertions. Used to explain failing constraints.
+    pub assume_info: Vec<(PropIdx, InfoIdx)>,
+    /// Assumptions made outside of any `if` or loop. They hold on every path
+    /// through the component.
+    pub top_assumes: Vec<PropIdx>,
 
     /// Commands in the component
     pub cmds: Vec<Command>,
@@ -61,6 +67,15 @@ pub struct Component {
     pub src_info: Option<InterfaceSrc>,
     /// unannotated ports associated with this component
     pub unannotated_ports: Box<Vec<(ast::Id, u64)>>,
+    /// Name of the component in the source. Components generated by
+    /// monomorphization keep the name of the component they came from.
+    pub src_name: Option<ast::Id>,
+    /// Names and values of the parameters of the component this component
+    /// was monomorphized from. Used to report errors in monomorphized
+    /// components.
+    pub mono_binding: Vec<(String, u64)>,
+    /// Functions defined by the program
+    pub funcs: Funcs,
 }
 
 impl Component {
@@ -303,6 +318,36 @@ impl Component {
         self.prop_params_acc(prop, &mut acc);
         acc
     }
+
+    /// Events mentioned within a proposition
+    pub fn prop_events(&self, prop: PropIdx) -> Vec<EventIdx> {
+        let mut acc = Vec::new();
+        self.prop_events_acc(prop, &mut acc);
+        acc
+    }
+
+    fn prop_events_acc(&self, prop: PropIdx, acc: &mut Vec<EventIdx>) {
+        match self.get(prop) {
+            Prop::True | Prop::False | Prop::Cmp(_) => (),
+            Prop::TimeCmp(CmpOp { lhs, rhs, .. }) => {
+                acc.push(self.get(*lhs).event);
+                acc.push(self.get(*rhs).event);
+            }
+            Prop::TimeSubCmp(CmpOp { lhs, rhs, .. }) => {
+                for ts in [lhs, rhs] {
+                    if let TimeSub::Sym { l, r } = ts {
+                        acc.push(self.get(*l).event);
+                        acc.push(self.get(*r).event);
+                    }
+                }
+            }
+            Prop::Not(p) => self.prop_events_acc(*p, acc),
+            Prop::And(l, r) | Prop::Or(l, r) | Prop::Implies(l, r) => {
+                self.prop_events_acc(*l, acc);
+                self.prop_events_acc(*r, acc);
+            }
+        }
+    }
 }
 
 // =========== Context accessors for each type ===========
@@ -404,9 +449,9 @@ impl Component {
                 match p.as_concrete(self) {
                     Some(p) => {
                         if p {
-                            self.add(Prop::True)
-                        } else {
                             self.add(Prop::False)
+                        } else {
+                            self.add(Prop::True)
                         }
                     }
                     None => self.add(prop),
@@ -430,7 +475,13 @@ impl Component {
             Expr::Fn {op, args} => {
                 let args = args.iter().map(|arg| { let arg = self.get(*arg); self.func(arg.clone()) }).collect_vec();
                 let args = args.into_iter().map(|arg| arg.as_concrete(self).unwrap()).collect_vec();
-                self.add(Expr::Concrete(op.eval(args)))
+                let Some(n) = self.funcs.eval(op, args.clone()) else {
+                    self.internal_error(format!(
+                        "Function {op} is undefined for the arguments {}",
+                        args.iter().join(", ")
+                    ))
+                };
+                self.add(Expr::Concrete(n))
             }
         }
     }
@@ -483,13 +534,21 @@ impl AddCtx<Expr> for Component {
                         lhs: *rhs,
                         rhs: self.exprs.intern(Expr::Concrete(l)),
                     },
-                    (op, Some(l), Some(r)) => Expr::Concrete(match op {
-                        ast::Op::Add => l + r,
-                        ast::Op::Sub => l - r,
-                        ast::Op::Mul => l * r,
-                        ast::Op::Div => l / r,
-                        ast::Op::Mod => l % r,
-                    }),
+                    (op, Some(l), Some(r)) => {
+                        let n = match op {
+                            ast::Op::Add => l.checked_add(r),
+                            ast::Op::Sub => l.checked_sub(r),
+                            ast::Op::Mul => l.checked_mul(r),
+                            ast::Op::Div => l.checked_div(r),
+                            ast::Op::Mod => l.checked_rem(r),
+                        };
+                        // Expressions that are not natural numbers, like
+                        // `0-1`, are kept so that they can be reported.
+                        match n {
+                            Some(n) => Expr::Concrete(n),
+                            None => val,
+                        }
+                    }
                     _ => val,
                 };
                 self.exprs.intern(e)
@@ -498,7 +557,7 @@ impl AddCtx<Expr> for Component {
                 args.iter()
                     .map(|arg| arg.as_concrete(self))
                     .collect::<Option<Vec<_>>>()
-                    .map(|args| op.eval(args))
+                    .and_then(|args| self.funcs.eval(*op, args))
                     .map_or(val, Expr::Concrete),
             ),
         }
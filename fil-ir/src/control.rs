@@ -1,6 +1,6 @@
 use super::{
     Access, CompIdx, Component, Ctx, Event, ExprIdx, Fact, Foreign, InfoIdx,
-    InstIdx, InvIdx, ParamIdx, PortIdx, PropIdx, TimeIdx, TimeSub,
+    InstIdx, InvIdx, ParamIdx, PortIdx, PropIdx, Source, TimeIdx, TimeSub,
 };
 
 #[derive(Clone, PartialEq, Eq)]
@@ -99,7 +99,7 @@ impl InstIdx {
 #[derive(Clone, PartialEq, Eq)]
 /// A connection between two ports
 pub struct Connect {
-    pub src: Access,
+    pub src: Source,
     pub dst: Access,
     pub info: InfoIdx,
 }
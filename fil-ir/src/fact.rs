@@ -224,10 +224,12 @@ impl PropIdx {
 /// Queries over propositions
 impl PropIdx {
     /// Returns the consequent of an implication, if this proposition is an
-    /// implication. Otherwise, returns the proposition itself.
+    /// implication. Nested implications, such as the ranges of bundle
+    /// indices under a path condition, are peeled as well. Otherwise, returns
+    /// the proposition itself.
     pub fn consequent(self, ctx: &impl Ctx<Prop>) -> PropIdx {
         match ctx.get(self) {
-            Prop::Implies(_, cons) => *cons,
+            Prop::Implies(_, cons) => cons.consequent(ctx),
             _ => self,
         }
     }
@@ -1,6 +1,6 @@
 //! Convert the frontend AST to the IR.
 use super::build_ctx::{OwnedParam, OwnedPort};
-use super::{BuildCtx, Sig, SigMap};
+use super::{fn_defs, BuildCtx, Sig, SigMap};
 use crate as ir;
 use crate::utils::Idx;
 use crate::{
@@ -227,18 +227,76 @@ impl<'prog> BuildCtx<'prog> {
                 self.comp().add(ir::Expr::Bin { op, lhs, rhs })
             }
             ast::Expr::App { func, args } => {
+                self.fn_preconditions(&func, &args)?;
                 let args = args
                     .into_iter()
                     .map(|arg| self.expr(arg))
                     .collect::<Result<Vec<_>, _>>()?;
                 // The .add call simplifies the expression if possible
-                self.comp().add(ir::Expr::Fn { op: func, args })
+                self.comp().add(ir::Expr::Fn {
+                    op: func.take(),
+                    args,
+                })
             }
         };
         Ok(expr)
     }
 
+    /// Check a call to a function defined in the program and assert that its
+    /// arguments satisfy the preconditions of the function.
+    fn fn_preconditions(
+        &mut self,
+        func: &ast::Loc<ast::Fn>,
+        args: &[ast::Expr],
+    ) -> BuildRes<()> {
+        let ast::Fn::User(name) = *func.inner() else {
+            return Ok(());
+        };
+        let funcs = self.comp().funcs.clone();
+        if let Err(err) =
+            fn_defs::check_call(self.diag(), func, args.len(), funcs.iter())
+        {
+            return self.fail(err, []);
+        }
+        let def = funcs.get(name).unwrap();
+        let binding = def.binding(args.to_vec());
+        for pre in &def.pres {
+            let reason = self.comp().add(
+                ir::info::Reason::fn_precondition(name, func.pos(), pre.pos())
+                    .into(),
+            );
+            let prop =
+                self.expr_cons(pre.inner().clone().resolve_expr(&binding))?;
+            let assert = self.comp().assert(prop, reason);
+            self.fn_asserts.extend(assert);
+        }
+        Ok(())
+    }
+
     fn expr_cons(
+        &mut self,
+        cons: ast::Formula<ast::Expr>,
+    ) -> BuildRes<PropIdx> {
+        match cons {
+            ast::Formula::Cmp(c) => self.expr_cmp(c),
+            ast::Formula::Not(f) => {
+                let f = self.expr_cons(*f)?;
+                Ok(f.not(self.comp()))
+            }
+            ast::Formula::And(l, r) => {
+                let l = self.expr_cons(*l)?;
+                let r = self.expr_cons(*r)?;
+                Ok(l.and(r, self.comp()))
+            }
+            ast::Formula::Or(l, r) => {
+                let l = self.expr_cons(*l)?;
+                let r = self.expr_cons(*r)?;
+                Ok(l.or(r, self.comp()))
+            }
+        }
+    }
+
+    fn expr_cmp(
         &mut self,
         cons: ast::OrderConstraint<ast::Expr>,
     ) -> BuildRes<PropIdx> {
@@ -289,6 +347,23 @@ impl<'prog> BuildCtx<'prog> {
     ) -> ParamIdx {
         let (name, pos) = param.split();
         let info = self.comp().add(ir::Info::param(name, pos));
+        self.add_param(name, info, owner)
+    }
+
+    /// Add a parameter bound by the signature of the component.
+    fn sig_param(&mut self, pb: &ast::ParamBind) -> ParamIdx {
+        let (name, pos) = pb.param.clone().split();
+        let range = pb.range.as_ref().map(|r| r.to_string());
+        let info = self.comp().add(ir::Info::sig_param(name, pos, range));
+        self.add_param(name, info, ir::ParamOwner::Sig)
+    }
+
+    fn add_param(
+        &mut self,
+        name: ast::Id,
+        info: ir::InfoIdx,
+        owner: ir::ParamOwner,
+    ) -> ParamIdx {
         let owned = OwnedParam::param_owner(name, &owner);
         let ir_param = ir::Param::new(owner, info);
         let is_sig_owned = ir_param.is_sig_owned();
@@ -529,10 +604,21 @@ impl<'prog> BuildCtx<'prog> {
                 let (start, end) = self.access(access.take())?;
                 ir::Access { port, start, end }
             }
+            ast::Port::Constant(_) => {
+                unreachable!("constants are not port accesses")
+            }
         };
         Ok(acc)
     }
 
+    /// Get the value read from an AST port
+    fn get_source(&mut self, port: ast::Port) -> BuildRes<ir::Source> {
+        match port {
+            ast::Port::Constant(c) => Ok(ir::Source::Const(c)),
+            port => Ok(self.get_access(port, ir::Direction::Out)?.into()),
+        }
+    }
+
     fn sig(&mut self, idx: ir::CompIdx, sig: &ast::Signature) -> BuildRes<Sig> {
         let mut conv_sig = Sig::new(idx, sig);
 
@@ -542,7 +628,7 @@ impl<'prog> BuildCtx<'prog> {
         );
 
         for pb in &sig.params {
-            self.param(pb.param.clone(), ir::ParamOwner::Sig);
+            self.sig_param(pb);
         }
 
         // Binding from let-defined parameters in the signature to their values
@@ -614,7 +700,7 @@ impl<'prog> BuildCtx<'prog> {
             let prop = self.event_cons(ec.inner().clone())?;
             sig_cons.extend(self.comp().assume(prop, info));
         }
-        for pc in &sig.param_constraints {
+        for pc in sig.all_param_constraints() {
             let info = self.comp().add(ir::Info::assert(
                 ir::info::Reason::misc("Signature assumption", pc.pos()),
             ));
@@ -622,6 +708,7 @@ impl<'prog> BuildCtx<'prog> {
             sig_cons.extend(self.comp().assume(prop, info));
         }
 
+        sig_cons.append(&mut self.fn_asserts);
         self.comp().cmds.extend(sig_cons);
 
         Ok(conv_sig)
@@ -714,7 +801,7 @@ impl<'prog> BuildCtx<'prog> {
 
         let srcs = ports
             .into_iter()
-            .map(|p| p.try_map(|p| self.get_access(p, ir::Direction::Out)))
+            .map(|p| p.try_map(|p| self.get_source(p)))
             .collect::<BuildRes<Vec<_>>>()?;
 
         // Constraints on the events from the signature
@@ -822,11 +909,15 @@ impl<'prog> BuildCtx<'prog> {
         &mut self,
         cmds: Vec<ast::Command>,
     ) -> BuildRes<Vec<ir::Command>> {
+        // Assertions for calls in the enclosing command are added to its scope
+        let outer = std::mem::take(&mut self.fn_asserts);
         self.declare_cmds(&cmds)?;
         let mut new_cmds: Vec<ir::Command> = Vec::with_capacity(cmds.len());
         for cmd in cmds {
             new_cmds.extend(self.command(cmd)?);
+            new_cmds.append(&mut self.fn_asserts);
         }
+        self.fn_asserts = outer;
         Ok(new_cmds)
     }
 
@@ -878,7 +969,17 @@ impl<'prog> BuildCtx<'prog> {
             ast::Command::Connect(ast::Connect { src, dst }) => {
                 let info =
                     self.comp().add(ir::Info::connect(dst.pos(), src.pos()));
-                let src = self.get_access(src.take(), ir::Direction::Out)?;
+                if let ast::Port::Constant(c) = dst.inner() {
+                    let info = self.diag().add_info(
+                        format!("cannot assign to the constant `{c}'"),
+                        dst.pos(),
+                    );
+                    return self.fail(
+                        Error::malformed("constants cannot be assigned to"),
+                        [info],
+                    );
+                }
+                let src = self.get_source(src.take())?;
                 let dst = self.get_access(dst.take(), ir::Direction::In)?;
                 vec![ir::Connect { src, dst, info }.into()]
             }
@@ -978,6 +1079,9 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
         ..Default::default()
     };
 
+    let library = ns.library;
+    let funcs = fn_defs::check_fns(ns.fns)?;
+
     // Walk over signatures and compile signatures to build a SigMap
     // Contains a tuple containing three necessary bits of information:
     // 1. The (optional) name of the component (if it is an external)
@@ -1010,11 +1114,13 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
     }
 
     // uses the information above to compile the signatures of components and create their builders.
-    let (mut builders, sig_map): (Vec<_>, SigMap) = comps
+    let (mut builders, mut sig_map): (Vec<_>, SigMap) = comps
         .map(|(idx, (file, sig, body))| {
             let idx = ir::CompIdx::new(idx);
             let mut builder =
                 BuildCtx::new(ir::Component::new(body.is_none()), &sig_map);
+            builder.comp().funcs = funcs.clone();
+            builder.comp().src_name = Some(sig.name.copy());
 
             // enable source information saving if this is main or an external.
             if body.is_none() || Some(idx) == ctx.entrypoint {
@@ -1035,6 +1141,8 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
         .into_iter()
         .unzip();
 
+    sig_map.set_library(library);
+
     // Add the signature map to each builder
     builders.iter_mut().for_each(|Builder { builder, .. }| {
         builder.set_sig_map(&sig_map);
@@ -3,6 +3,7 @@ use super::{BuildRes, ScopeMap, SigMap};
 use crate::{self as ir, Ctx, DenseIndexInfo, PortIdx};
 use fil_ast as ast;
 use fil_utils::{self as utils, Error, Id};
+use itertools::Itertools;
 use std::rc::Rc;
 use utils::InfoIdx;
 
@@ -15,6 +16,18 @@ pub(super) enum OwnedPort {
 }
 
 impl OwnedPort {
+    /// Returns true if both ports are accessed through the same invocation or
+    /// are both accessed using just their names
+    fn same_owner(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Inv(i1, d1, _), Self::Inv(i2, d2, _)) => {
+                i1 == i2 && d1 == d2
+            }
+            (Self::Inv(..), _) | (_, Self::Inv(..)) => false,
+            _ => true,
+        }
+    }
+
     fn name(&self) -> Id {
         match self {
             Self::Sig(_, id) | Self::Inv(_, _, id) | Self::Local(id) => {
@@ -60,6 +73,21 @@ impl OwnedParam {
         Self::Instance(inst, id)
     }
 
+    fn name(&self) -> Id {
+        match self {
+            Self::Instance(_, id) | Self::Local(id) => *id,
+        }
+    }
+
+    /// Returns true if the parameters have the same owner
+    fn same_owner(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Instance(i1, _), Self::Instance(i2, _)) => i1 == i2,
+            (Self::Local(_), Self::Local(_)) => true,
+            _ => false,
+        }
+    }
+
     pub fn param_owner(id: ast::Id, owner: &ir::ParamOwner) -> Self {
         match owner {
             ir::ParamOwner::Sig
@@ -113,6 +141,10 @@ pub(super) struct BuildCtx<'prog> {
 
     /// Index for generating unique names
     name_idx: u32,
+
+    /// Assertions that the calls in the current command satisfy the
+    /// preconditions of the functions
+    pub fn_asserts: Vec<ir::Command>,
 }
 
 impl<'prog> BuildCtx<'prog> {
@@ -128,6 +160,7 @@ impl<'prog> BuildCtx<'prog> {
             inst_map: ScopeMap::new(),
             inv_map: ScopeMap::new(),
             inst_to_sig: DenseIndexInfo::default(),
+            fn_asserts: vec![],
         }
     }
 
@@ -198,6 +231,19 @@ impl<'prog> BuildCtx<'prog> {
         Err(std::mem::take(&mut self.diag))
     }
 
+    /// Add a note suggesting the candidates that are close to `name`
+    fn suggest(
+        &mut self,
+        err: Error,
+        name: Id,
+        candidates: impl IntoIterator<Item = Id>,
+    ) -> Error {
+        match utils::did_you_mean(name.as_ref(), candidates) {
+            Some(msg) => err.add_note(self.diag.add_message(msg)),
+            None => err,
+        }
+    }
+
     /// Get the signature if bound or return an error
     pub fn get_sig(&mut self, id: &ast::Loc<Id>) -> BuildRes<&'prog Sig> {
         let name = id.inner();
@@ -208,7 +254,18 @@ impl<'prog> BuildCtx<'prog> {
                     format!("signature `{id}' is not defined"),
                     id.pos(),
                 );
-                self.fail(Error::undefined(*name, "signature"), [info])
+                let mut err =
+                    Error::undefined(*name, "signature").add_note(info);
+                if let Some(file) = self.sigs.import_for(name) {
+                    let msg = format!(
+                        "help: `{name}' is defined in `{file}'. Add `import \"{file}\";' to use it"
+                    );
+                    err = err.add_note(self.diag.add_message(msg));
+                } else {
+                    let names = self.sigs.names().copied().collect_vec();
+                    err = self.suggest(err, *name, names);
+                }
+                self.fail(err, [])
             }
         }
     }
@@ -234,10 +291,17 @@ impl<'prog> BuildCtx<'prog> {
             Some(p) => Ok(*p),
             None => {
                 let info = self.diag.add_info("unknown parameter", pos);
-                self.fail(
-                    Error::undefined(format!("{}", param), "parameter"),
-                    [info],
-                )
+                let err = Error::undefined(format!("{}", param), "parameter")
+                    .add_note(info);
+                let names = self
+                    .param_map
+                    .as_flat_vec()
+                    .into_iter()
+                    .filter(|(p, _)| p.same_owner(param))
+                    .map(|(p, _)| p.name())
+                    .collect_vec();
+                let err = self.suggest(err, param.name(), names);
+                self.fail(err, [])
             }
         }
     }
@@ -274,11 +338,16 @@ impl<'prog> BuildCtx<'prog> {
         // We are going to error out anyways so attempt to find the scope for
         // *any* port with the same name but a different port owner.
         let name = port.name();
-        let other = self
-            .port_map
-            .as_flat_vec()
-            .into_iter()
-            .find_map(|(p, _)| if p.name() == name { Some(p) } else { None });
+        let ports = self.port_map.as_flat_vec();
+        let other = ports
+            .iter()
+            .find_map(|(p, _)| if p.name() == name { Some(p) } else { None })
+            .cloned();
+        let names = ports
+            .iter()
+            .filter(|(p, _)| p.same_owner(port))
+            .map(|(p, _)| p.name())
+            .collect_vec();
         let mut err = Error::undefined(name, "port");
         err =
             err.add_note(self.diag.add_info(
@@ -291,6 +360,7 @@ impl<'prog> BuildCtx<'prog> {
                 port.pos(),
             ));
         }
+        let err = self.suggest(err, name, names);
         self.fail(err, [])
     }
 
@@ -307,7 +377,15 @@ impl<'prog> BuildCtx<'prog> {
                     format!("instance `{name}' is not defined"),
                     id.pos(),
                 );
-                self.fail(Error::undefined(name, "instance"), [info])
+                let err = Error::undefined(name, "instance").add_note(info);
+                let names = self
+                    .inst_map
+                    .as_flat_vec()
+                    .into_iter()
+                    .map(|(n, _)| n)
+                    .collect_vec();
+                let err = self.suggest(err, name, names);
+                self.fail(err, [])
             }
         }
     }
@@ -325,7 +403,15 @@ impl<'prog> BuildCtx<'prog> {
                     format!("invocation `{name}' is not defined"),
                     id.pos(),
                 );
-                self.fail(Error::undefined(name, "invocation"), [info])
+                let err = Error::undefined(name, "invocation").add_note(info);
+                let names = self
+                    .inv_map
+                    .as_flat_vec()
+                    .into_iter()
+                    .map(|(n, _)| n)
+                    .collect_vec();
+                let err = self.suggest(err, name, names);
+                self.fail(err, [])
             }
         }
     }
@@ -339,7 +425,15 @@ impl<'prog> BuildCtx<'prog> {
         match self.event_map.get(&name) {
             Some(idx) => Ok(*idx),
             None => {
-                self.fail(Error::undefined(name, "event"), [])
+                let names = self
+                    .event_map
+                    .as_flat_vec()
+                    .into_iter()
+                    .map(|(n, _)| n)
+                    .collect_vec();
+                let err =
+                    self.suggest(Error::undefined(name, "event"), name, names);
+                self.fail(err, [])
                 // .add_note(diag.add_info(
                 //     format!("event `{name}' is not defined"),
                 //     id.pos(),
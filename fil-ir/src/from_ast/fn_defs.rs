@@ -0,0 +1,215 @@
+use super::BuildRes;
+use crate as ir;
+use fil_ast as ast;
+use fil_utils::{self as utils, Error, GPosIdx, Id};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Check that the call refers to a function defined in the program and
+/// passes it the right number of arguments.
+pub(super) fn check_call<'a>(
+    diag: &mut utils::Diagnostics,
+    func: &ast::Loc<ast::Fn>,
+    nargs: usize,
+    defs: impl Iterator<Item = &'a ast::FnDef> + Clone,
+) -> Result<(), Error> {
+    let ast::Fn::User(name) = *func.inner() else {
+        return Ok(());
+    };
+    let Some(def) = defs.clone().find(|d| *d.name.inner() == name) else {
+        let info = diag.add_info("unknown function", func.pos());
+        let mut err = Error::undefined(name, "function").add_note(info);
+        let names = defs.map(|d| *d.name.inner()).collect_vec();
+        if let Some(msg) = utils::did_you_mean(name.as_ref(), names) {
+            err = err.add_note(diag.add_message(msg));
+        }
+        return Err(err);
+    };
+    if def.params.len() != nargs {
+        let info = diag.add_info(
+            format!("`{name}' is called with {nargs} arguments"),
+            func.pos(),
+        );
+        let def_info = diag.add_info("function defined here", def.name.pos());
+        return Err(Error::malformed(format!(
+            "`{name}' expects {} arguments but was given {nargs}",
+            def.params.len()
+        ))
+        .add_note(info)
+        .add_note(def_info));
+    }
+    Ok(())
+}
+
+/// Checks the definitions of the functions in the program
+struct FnCheck<'a> {
+    defs: &'a [ast::FnDef],
+    diag: utils::Diagnostics,
+}
+
+impl<'a> FnCheck<'a> {
+    fn fail<T>(&mut self, err: Error) -> BuildRes<T> {
+        self.diag.add_error(err);
+        Err(std::mem::take(&mut self.diag))
+    }
+
+    /// Check that the definition binds new names
+    fn names(&mut self, idx: usize) -> BuildRes<()> {
+        let defs = self.defs;
+        let def = &defs[idx];
+        let name = *def.name.inner();
+        if ast::Fn::builtin(name.as_ref()).is_some() {
+            let info =
+                self.diag.add_info("function defined here", def.name.pos());
+            let err = Error::already_bound(name, "a built-in function")
+                .add_note(info);
+            return self.fail(err);
+        }
+        if let Some(prev) = defs[..idx].iter().find(|d| *d.name.inner() == name)
+        {
+            let info = self
+                .diag
+                .add_info("function is redefined here", def.name.pos());
+            let prev =
+                self.diag.add_info("previous definition", prev.name.pos());
+            let err = Error::already_bound(name, "another function")
+                .add_note(info)
+                .add_note(prev);
+            return self.fail(err);
+        }
+        for (i, p) in def.params.iter().enumerate() {
+            if let Some(prev) = def.params[..i].iter().find(|q| q == &p) {
+                let info = self
+                    .diag
+                    .add_info("parameter is bound again here", p.pos());
+                let prev = self.diag.add_info("previous binding", prev.pos());
+                let err = Error::already_bound(*p.inner(), "another parameter")
+                    .add_note(info)
+                    .add_note(prev);
+                return self.fail(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check an expression in the definition and collect the calls it makes
+    fn expr(
+        &mut self,
+        def: &ast::FnDef,
+        e: &ast::Expr,
+        calls: &mut Vec<(Id, GPosIdx)>,
+    ) -> BuildRes<()> {
+        match e {
+            ast::Expr::Concrete(_) => Ok(()),
+            ast::Expr::Abstract(p) => {
+                if def.params.contains(p) {
+                    return Ok(());
+                }
+                let info = self.diag.add_info("unknown parameter", p.pos());
+                let mut err = Error::undefined(p, "parameter").add_note(info);
+                let names = def.params.iter().map(|p| p.inner()).collect_vec();
+                if let Some(msg) =
+                    utils::did_you_mean(p.inner().as_ref(), names)
+                {
+                    err = err.add_note(self.diag.add_message(msg));
+                }
+                self.fail(err)
+            }
+            ast::Expr::ParamAccess { inst, param } => {
+                let info = self.diag.add_info(
+                    format!(
+                        "`{inst}::{param}' is not a parameter of the function"
+                    ),
+                    inst.pos(),
+                );
+                let err = Error::malformed(
+                    "functions cannot refer to the parameters of instances",
+                )
+                .add_note(info);
+                self.fail(err)
+            }
+            ast::Expr::Op { left, right, .. } => {
+                self.expr(def, left, calls)?;
+                self.expr(def, right, calls)
+            }
+            ast::Expr::App { func, args } => {
+                if let Err(err) = check_call(
+                    &mut self.diag,
+                    func,
+                    args.len(),
+                    self.defs.iter(),
+                ) {
+                    return self.fail(err);
+                }
+                if let ast::Fn::User(name) = *func.inner() {
+                    calls.push((name, func.pos()));
+                }
+                args.iter().try_for_each(|a| self.expr(def, a, calls))
+            }
+        }
+    }
+
+    /// Add the function to `order` after the functions it calls. `stack`
+    /// contains the functions whose callees are being visited.
+    fn visit(
+        &mut self,
+        name: Id,
+        calls: &HashMap<Id, Vec<(Id, GPosIdx)>>,
+        stack: &mut Vec<Id>,
+        order: &mut Vec<Id>,
+    ) -> BuildRes<()> {
+        if order.contains(&name) {
+            return Ok(());
+        }
+        stack.push(name);
+        for (callee, pos) in &calls[&name] {
+            if stack.contains(callee) {
+                let info = self
+                    .diag
+                    .add_info(format!("call to `{callee}' is recursive"), *pos);
+                let err =
+                    Error::malformed("recursive functions are not supported")
+                        .add_note(info);
+                return self.fail(err);
+            }
+            self.visit(*callee, calls, stack, order)?;
+        }
+        stack.pop();
+        order.push(name);
+        Ok(())
+    }
+}
+
+/// Check the functions defined in the program and order them so that every
+/// function is defined after the functions it calls.
+pub(super) fn check_fns(defs: Vec<ast::FnDef>) -> BuildRes<ir::Funcs> {
+    let mut check = FnCheck {
+        defs: &defs,
+        diag: utils::Diagnostics::default(),
+    };
+    let mut calls = HashMap::new();
+    for (idx, def) in defs.iter().enumerate() {
+        check.names(idx)?;
+        let mut cs = vec![];
+        check.expr(def, def.body.inner(), &mut cs)?;
+        for pre in &def.pres {
+            for e in pre.inner().exprs() {
+                check.expr(def, e, &mut cs)?;
+            }
+        }
+        calls.insert(*def.name.inner(), cs);
+    }
+
+    let mut order = vec![];
+    for def in &defs {
+        check.visit(*def.name.inner(), &calls, &mut vec![], &mut order)?;
+    }
+    let mut defs: HashMap<_, _> =
+        defs.into_iter().map(|d| (*d.name.inner(), d)).collect();
+    Ok(ir::Funcs::new(
+        order
+            .into_iter()
+            .map(|n| defs.remove(&n).unwrap())
+            .collect(),
+    ))
+}
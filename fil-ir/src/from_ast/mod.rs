@@ -1,4 +1,5 @@
 mod build_ctx;
+mod fn_defs;
 mod scope_map;
 mod sig_map;
 
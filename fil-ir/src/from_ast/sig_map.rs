@@ -3,7 +3,7 @@ use crate as ir;
 use fil_ast as ast;
 use fil_utils::{self as utils, Error, Id};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 #[derive(Clone)]
 /// The signature of component.
@@ -26,10 +26,10 @@ pub struct Sig {
     pub raw_events: Vec<ast::EventBind>,
     /// The AST representation of output ports of the signature
     pub raw_outputs: Vec<ast::PortDef>,
-    /// Constraints on input parameters
-    pub param_cons: Vec<ast::Loc<ast::OrderConstraint<ast::Expr>>>,
+    /// Constraints on input parameters including their declared ranges
+    pub param_cons: Vec<ast::Loc<ast::Formula<ast::Expr>>>,
     /// Constraints on existentially bound parameters
-    pub exist_cons: Vec<ast::Loc<ast::OrderConstraint<ast::Expr>>>,
+    pub exist_cons: Vec<ast::Loc<ast::Formula<ast::Expr>>>,
     /// Constraints on events
     pub event_cons: Vec<ast::Loc<ast::OrderConstraint<ast::Time>>>,
 }
@@ -41,7 +41,7 @@ impl Sig {
             raw_params: sig.params.iter().map(|p| p.clone().take()).collect(),
             raw_outputs: sig.outputs().map(|p| p.clone().take()).collect(),
             raw_events: sig.events.iter().map(|e| e.clone().take()).collect(),
-            param_cons: sig.param_constraints.clone(),
+            param_cons: sig.all_param_constraints(),
             exist_cons: sig
                 .sig_bindings
                 .iter()
@@ -209,6 +209,8 @@ impl Sig {
 /// Mapping from names of component to [Sig].
 pub struct SigMap {
     map: HashMap<Id, Sig>,
+    /// Library files that have not been imported along with their import
+    library: Vec<(String, PathBuf)>,
 }
 
 impl SigMap {
@@ -216,6 +218,32 @@ impl SigMap {
     pub fn get(&self, id: &Id) -> Option<&Sig> {
         self.map.get(id)
     }
+
+    /// Names of the defined signatures
+    pub fn names(&self) -> impl Iterator<Item = &Id> {
+        self.map.keys()
+    }
+
+    /// Record the library files that were not imported
+    pub fn set_library(&mut self, library: Vec<(String, PathBuf)>) {
+        self.library = library;
+    }
+
+    /// The import that defines the component if it is not defined.
+    /// The library files are only parsed when an undefined component is
+    /// reported.
+    pub fn import_for(&self, id: &Id) -> Option<&String> {
+        self.library.iter().find_map(|(import, path)| {
+            // Files that fail to parse are reported when imported
+            let ns = ast::FilamentParser::parse_file(path).ok()?;
+            let defined = ns
+                .externals()
+                .map(|(name, _)| name)
+                .chain(ns.components.iter().map(|c| *c.sig.name.inner()))
+                .any(|name| name == *id);
+            defined.then_some(import)
+        })
+    }
 }
 
 impl FromIterator<(Id, Sig)> for SigMap {
@@ -0,0 +1,76 @@
+use fil_ast as ast;
+use fil_utils::Id;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Default, Clone)]
+/// Functions over parameters defined in the program. The definitions are
+/// shared by all components and ordered so that every function is defined
+/// after the functions it calls.
+pub struct Funcs {
+    defs: Arc<Vec<ast::FnDef>>,
+}
+
+impl Funcs {
+    /// REQUIRES: Functions are defined after the functions they call.
+    pub fn new(defs: Vec<ast::FnDef>) -> Self {
+        Self {
+            defs: Arc::new(defs),
+        }
+    }
+
+    /// Get the definition of a function
+    pub fn get(&self, name: Id) -> Option<&ast::FnDef> {
+        self.defs.iter().find(|d| *d.name.inner() == name)
+    }
+
+    /// Iterate over the definitions, callees first
+    pub fn iter(&self) -> impl Iterator<Item = &ast::FnDef> + Clone {
+        self.defs.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// Evaluate the function on concrete arguments. Returns `None` if the
+    /// result is not a natural number, for example, because of an underflow
+    /// or a division by zero.
+    pub fn eval(&self, f: ast::Fn, args: Vec<u64>) -> Option<u64> {
+        let name = match (f, &args[..]) {
+            (ast::Fn::Pow2, &[n]) if n >= 64 => return None,
+            (ast::Fn::CeilDiv, &[_, 0]) => return None,
+            (ast::Fn::User(name), _) => name,
+            _ => return Some(f.eval(args)),
+        };
+        let def = self.get(name)?;
+        let bind = def.params.iter().map(|p| *p.inner()).zip(args).collect();
+        self.eval_expr(def.body.inner(), &bind)
+    }
+
+    fn eval_expr(&self, e: &ast::Expr, bind: &HashMap<Id, u64>) -> Option<u64> {
+        match e {
+            ast::Expr::Concrete(n) => Some(*n),
+            ast::Expr::Abstract(p) => bind.get(p.inner()).copied(),
+            ast::Expr::ParamAccess { .. } => None,
+            ast::Expr::Op { op, left, right } => {
+                let l = self.eval_expr(left, bind)?;
+                let r = self.eval_expr(right, bind)?;
+                match op {
+                    ast::Op::Add => l.checked_add(r),
+                    ast::Op::Sub => l.checked_sub(r),
+                    ast::Op::Mul => l.checked_mul(r),
+                    ast::Op::Div => l.checked_div(r),
+                    ast::Op::Mod => l.checked_rem(r),
+                }
+            }
+            ast::Expr::App { func, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| self.eval_expr(a, bind))
+                    .collect::<Option<Vec<_>>>()?;
+                self.eval(*func.inner(), args)
+            }
+        }
+    }
+}
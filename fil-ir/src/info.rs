@@ -20,6 +20,8 @@ pub struct Param {
     /// Surface-level name of the parameter
     pub name: ast::Id,
     pub bind_loc: GPosIdx,
+    /// Surface-level range of values declared for a signature parameter
+    pub range: Option<String>,
 }
 
 #[derive(Clone, Eq, PartialEq)]
@@ -106,7 +108,20 @@ impl Info {
     }
 
     pub fn param(name: ast::Id, bind_loc: GPosIdx) -> Self {
-        Param { name, bind_loc }.into()
+        Self::sig_param(name, bind_loc, None)
+    }
+
+    pub fn sig_param(
+        name: ast::Id,
+        bind_loc: GPosIdx,
+        range: Option<String>,
+    ) -> Self {
+        Param {
+            name,
+            bind_loc,
+            range,
+        }
+        .into()
     }
 
     pub fn event(
@@ -305,6 +320,15 @@ pub enum Reason {
         /// Location of the constraint
         constraint_loc: Option<GPosIdx>,
     },
+    /// Precondition of a function defined in the program
+    FnPrecondition {
+        /// Name of the function
+        func: ast::Id,
+        /// Location of the call
+        call_loc: GPosIdx,
+        /// Location of the precondition
+        pre_loc: GPosIdx,
+    },
 
     // ============ Constraints from type checking ==============
     /// Require that lengths of bundles match
@@ -320,6 +344,14 @@ pub enum Reason {
         dst_width: ExprIdx,
         src_width: ExprIdx,
     },
+    /// An unsized constant fits in the destination
+    ConstantFits {
+        dst_loc: GPosIdx,
+        const_loc: GPosIdx,
+        dst_width: ExprIdx,
+        /// Number of bits needed to represent the constant
+        bits: u64,
+    },
     /// An access is within bounds
     InBoundsAccess {
         // Defining location for the port
@@ -366,6 +398,21 @@ pub enum Reason {
         /// Location of the binding
         time_expr_loc: GPosIdx,
     },
+    /// Two invocations of the same instance must not overlap in time
+    SharedInstance {
+        /// Delay of the invoked component's event
+        ev_delay_loc: GPosIdx,
+        ev_delay: TimeSub,
+        /// Start of the invocation whose use of the instance is interrupted
+        first_loc: GPosIdx,
+        first: TimeIdx,
+        /// Start of the conflicting invocation
+        second_loc: GPosIdx,
+        second: TimeIdx,
+        /// Delay of the containing component's event when the conflicting
+        /// invocation belongs to the next execution of the component
+        period: Option<TimeSub>,
+    },
     // =============== Generic Constraints =======================
     /// A simple reason
     Misc { reason: String, def_loc: GPosIdx },
@@ -433,6 +480,18 @@ impl Reason {
         }
     }
 
+    pub fn fn_precondition(
+        func: ast::Id,
+        call_loc: GPosIdx,
+        pre_loc: GPosIdx,
+    ) -> Self {
+        Self::FnPrecondition {
+            func,
+            call_loc,
+            pre_loc,
+        }
+    }
+
     pub fn event_trig(
         ev_delay_loc: GPosIdx,
         ev_delay: TimeSub,
@@ -449,6 +508,24 @@ impl Reason {
         }
     }
 
+    pub fn shared_instance(
+        ev_delay_loc: GPosIdx,
+        ev_delay: TimeSub,
+        (first_loc, first): (GPosIdx, TimeIdx),
+        (second_loc, second): (GPosIdx, TimeIdx),
+        period: Option<TimeSub>,
+    ) -> Self {
+        Self::SharedInstance {
+            ev_delay_loc,
+            ev_delay,
+            first_loc,
+            first,
+            second_loc,
+            second,
+            period,
+        }
+    }
+
     pub fn in_bounds_access(
         def_loc: GPosIdx,
         access_loc: GPosIdx,
@@ -489,6 +566,20 @@ impl Reason {
         }
     }
 
+    pub fn constant_fits(
+        dst_loc: GPosIdx,
+        const_loc: GPosIdx,
+        dst_width: ExprIdx,
+        bits: u64,
+    ) -> Self {
+        Self::ConstantFits {
+            dst_loc,
+            const_loc,
+            dst_width,
+            bits,
+        }
+    }
+
     pub fn well_formed_interval(
         range_loc: GPosIdx,
         range: (TimeIdx, TimeIdx),
@@ -554,6 +645,22 @@ impl Reason {
                     .with_message("instantiation violates parameter constraint")
                     .with_labels(vec![con, inst])
             }
+            Reason::FnPrecondition {
+                func,
+                call_loc,
+                pre_loc,
+            } => {
+                let pre =
+                    pre_loc.primary().with_message("precondition was violated");
+                let call = call_loc
+                    .secondary()
+                    .with_message("function is called here");
+                Diagnostic::error()
+                    .with_message(format!(
+                        "call violates the precondition of `{func}'"
+                    ))
+                    .with_labels(vec![pre, call])
+            }
             Reason::EventConstraint {
                 bind_loc,
                 constraint_loc,
@@ -618,6 +725,25 @@ impl Reason {
                     .with_message(format!("required bundle of width `{dw}' but found bundle of width `{sw}'"))
                     .with_labels(vec![src, dst])
             }
+            Reason::ConstantFits {
+                dst_loc,
+                const_loc,
+                dst_width,
+                bits,
+            } => {
+                let dw = ctx.display(*dst_width);
+                let src = const_loc
+                    .primary()
+                    .with_message(format!("constant needs {bits} bits"));
+                let dst = dst_loc
+                    .secondary()
+                    .with_message(format!("destination has width {dw}"));
+                Diagnostic::error()
+                    .with_message(format!(
+                        "constant does not fit in a port of width `{dw}'"
+                    ))
+                    .with_labels(vec![src, dst])
+            }
             Reason::Liveness {
                 dst_loc,
                 src_loc,
@@ -700,6 +826,68 @@ impl Reason {
                     .with_message("event provided to invocation triggers more often that invocation's event's delay allows")
                     .with_labels(vec![bind, ev, comp])
             }
+            Reason::SharedInstance {
+                ev_delay_loc,
+                ev_delay,
+                first_loc,
+                first,
+                second_loc,
+                second,
+                period,
+            } => {
+                // Pluralizes the number of cycles
+                let cycles = |ts: &TimeSub| match ts {
+                    TimeSub::Unit(e) if e.is_const(ctx, 1) => {
+                        "1 cycle".to_string()
+                    }
+                    _ => format!("{} cycles", ctx.display(ts)),
+                };
+                let msg = match period {
+                    Some(period) => format!(
+                        "invocation starts at `{}' and again {} later in the next execution of the component",
+                        ctx.display(*second),
+                        cycles(period)
+                    ),
+                    None => format!(
+                        "invocation starts at `{}'",
+                        ctx.display(*second)
+                    ),
+                };
+                // Default bindings for events do not have a location
+                let mut labels = Vec::with_capacity(3);
+                let second_loc = second_loc.into_option();
+                let first_loc = first_loc.into_option();
+                if let Some(loc) = second_loc.filter(|l| Some(*l) == first_loc)
+                {
+                    // An invocation in a loop conflicts with itself in
+                    // another iteration
+                    labels.push(loc.primary().with_message(
+                        format!(
+                            "{msg}, which overlaps with another iteration starting at `{}'",
+                            ctx.display(*first)
+                        ),
+                    ));
+                } else {
+                    if let Some(loc) = second_loc {
+                        labels.push(loc.primary().with_message(msg));
+                    }
+                    if let Some(loc) = first_loc {
+                        labels.push(loc.secondary().with_message(format!(
+                            "conflicting invocation starts at `{}'",
+                            ctx.display(*first)
+                        )));
+                    }
+                }
+                labels.push(ev_delay_loc.secondary().with_message(format!(
+                    "instance is busy for {} after each invocation",
+                    cycles(ev_delay)
+                )));
+                Diagnostic::error()
+                    .with_message(
+                        "instance must be shared with sufficient delay",
+                    )
+                    .with_labels(labels)
+            }
         }
     }
 }
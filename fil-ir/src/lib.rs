@@ -5,8 +5,10 @@ mod ctx;
 mod expr;
 mod fact;
 mod from_ast;
+mod funcs;
 mod idxs;
 pub mod info;
+pub mod linear;
 mod macros;
 mod printer;
 mod source_info;
@@ -24,6 +26,7 @@ pub use ctx::{AddCtx, Ctx, MutCtx};
 pub use expr::Expr;
 pub use fact::{Cmp, CmpOp, Fact, Prop};
 pub use from_ast::astconv::transform;
+pub use funcs::Funcs;
 pub use idxs::{
     CompIdx, EventIdx, ExprIdx, InfoIdx, InstIdx, InvIdx, ParamIdx, PortIdx,
     PropIdx, TimeIdx,
@@ -33,7 +36,7 @@ pub use printer::{DisplayCtx, Printer};
 pub use source_info::InterfaceSrc;
 pub use structure::{
     Access, Direction, Event, Liveness, Param, ParamOwner, Port, PortOwner,
-    Range,
+    Range, Source,
 };
 pub use time::{Time, TimeSub};
 pub use utils::{
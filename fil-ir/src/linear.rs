@@ -0,0 +1,223 @@
+//! Linear normal form of expressions and time expressions.
+//!
+//! Expressions are normalized into sums `c + a_1*x_1 + ... + a_n*x_n` over
+//! parameters and events with constant coefficients. Sub-expressions that are
+//! not linear, such as `pow2(N)`, `N*M`, or `N/2`, are treated as opaque
+//! variables.
+use super::{
+    Cmp, CmpOp, Ctx, EventIdx, Expr, ExprIdx, ParamIdx, Prop, PropIdx, Time,
+    TimeIdx, TimeSub,
+};
+use fil_ast as ast;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+/// Variables of a linear sum
+pub enum Var {
+    Param(ParamIdx),
+    Event(EventIdx),
+    /// A non-linear sub-expression
+    Term(ExprIdx),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// The coefficients of a linear sum do not fit in an `i128`
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "coefficient overflow")
+    }
+}
+
+impl From<Overflow> for String {
+    fn from(o: Overflow) -> Self {
+        o.to_string()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// A linear sum with constant coefficients over the variables `V`. Variables
+/// with a zero coefficient are never stored so two sums are equal iff they are
+/// syntactically equal.
+pub struct Linear<V = Var> {
+    coeffs: BTreeMap<V, i128>,
+    constant: i128,
+}
+
+impl<V> Default for Linear<V> {
+    fn default() -> Self {
+        Self {
+            coeffs: BTreeMap::new(),
+            constant: 0,
+        }
+    }
+}
+
+impl<V: Copy + Ord> Linear<V> {
+    pub fn constant(n: i128) -> Self {
+        Self {
+            coeffs: BTreeMap::new(),
+            constant: n,
+        }
+    }
+
+    pub fn var(v: V) -> Self {
+        Self {
+            coeffs: BTreeMap::from([(v, 1)]),
+            constant: 0,
+        }
+    }
+
+    /// The value of the sum if it does not mention any variables
+    pub fn as_constant(&self) -> Option<i128> {
+        self.coeffs.is_empty().then_some(self.constant)
+    }
+
+    /// The constant term of the sum
+    pub fn constant_term(&self) -> i128 {
+        self.constant
+    }
+
+    /// The coefficient of a variable
+    pub fn coeff(&self, v: V) -> i128 {
+        self.coeffs.get(&v).copied().unwrap_or(0)
+    }
+
+    /// The variables of the sum along with their coefficients
+    pub fn coeffs(&self) -> impl Iterator<Item = (V, i128)> + '_ {
+        self.coeffs.iter().map(|(v, c)| (*v, *c))
+    }
+
+    /// Compute `self + other * scale`
+    fn add_scaled(&self, other: &Self, scale: i128) -> Result<Self, Overflow> {
+        let mut out = self.clone();
+        for (v, c) in &other.coeffs {
+            let entry = out.coeffs.entry(*v).or_insert(0);
+            *entry = c
+                .checked_mul(scale)
+                .and_then(|c| entry.checked_add(c))
+                .ok_or(Overflow)?;
+            if *entry == 0 {
+                out.coeffs.remove(v);
+            }
+        }
+        out.constant = other
+            .constant
+            .checked_mul(scale)
+            .and_then(|c| c.checked_add(out.constant))
+            .ok_or(Overflow)?;
+        Ok(out)
+    }
+
+    pub fn add(&self, other: &Self) -> Result<Self, Overflow> {
+        self.add_scaled(other, 1)
+    }
+
+    pub fn sub(&self, other: &Self) -> Result<Self, Overflow> {
+        self.add_scaled(other, -1)
+    }
+
+    pub fn scale(&self, n: i128) -> Result<Self, Overflow> {
+        Self::default().add_scaled(self, n)
+    }
+
+    /// Divide the coefficients by `g > 0`, which must divide all of them, and
+    /// round the constant down.
+    pub fn div_floor(mut self, g: i128) -> Self {
+        for c in self.coeffs.values_mut() {
+            debug_assert!(*c % g == 0, "{g} does not divide {c}");
+            *c /= g;
+        }
+        self.constant = self.constant.div_euclid(g);
+        self
+    }
+
+    /// Evaluate the sum given the values of its variables
+    pub fn eval(&self, val: impl Fn(V) -> i128) -> Option<i128> {
+        self.coeffs.iter().try_fold(self.constant, |acc, (v, c)| {
+            acc.checked_add(c.checked_mul(val(*v))?)
+        })
+    }
+}
+
+impl Linear {
+    /// Returns true if the sum mentions a non-linear sub-expression
+    pub fn has_terms(&self) -> bool {
+        self.coeffs.keys().any(|v| matches!(v, Var::Term(_)))
+    }
+}
+
+impl ExprIdx {
+    /// Normalize the expression into a linear sum. Returns `None` if the
+    /// coefficients overflow.
+    pub fn linear(self, ctx: &impl Ctx<Expr>) -> Option<Linear> {
+        match ctx.get(self) {
+            Expr::Param(p) => Some(Linear::var(Var::Param(*p))),
+            Expr::Concrete(n) => Some(Linear::constant(*n as i128)),
+            Expr::Bin { op, lhs, rhs } => {
+                let l = lhs.linear(ctx)?;
+                let r = rhs.linear(ctx)?;
+                match (op, l.as_constant(), r.as_constant()) {
+                    (ast::Op::Add, _, _) => l.add(&r).ok(),
+                    (ast::Op::Sub, _, _) => l.sub(&r).ok(),
+                    (ast::Op::Mul, _, Some(n)) => l.scale(n).ok(),
+                    (ast::Op::Mul, Some(n), _) => r.scale(n).ok(),
+                    _ => Some(Linear::var(Var::Term(self))),
+                }
+            }
+            Expr::Fn { .. } => Some(Linear::var(Var::Term(self))),
+        }
+    }
+}
+
+impl TimeIdx {
+    /// Normalize the time into a linear sum over its event and the parameters
+    /// of its offset.
+    pub fn linear<C>(self, ctx: &C) -> Option<Linear>
+    where
+        C: Ctx<Time> + Ctx<Expr>,
+    {
+        let Time { event, offset } = ctx.get(self);
+        Linear::var(Var::Event(*event))
+            .add(&offset.linear(ctx)?)
+            .ok()
+    }
+}
+
+impl TimeSub {
+    /// Normalize the difference into a linear sum
+    pub fn linear<C>(&self, ctx: &C) -> Option<Linear>
+    where
+        C: Ctx<Time> + Ctx<Expr>,
+    {
+        match self {
+            TimeSub::Unit(e) => e.linear(ctx),
+            TimeSub::Sym { l, r } => l.linear(ctx)?.sub(&r.linear(ctx)?).ok(),
+        }
+    }
+}
+
+impl PropIdx {
+    /// Normalize a comparison into `diff op 0`. Returns `None` if the
+    /// proposition is not a comparison or if the coefficients overflow.
+    pub fn linear<C>(self, ctx: &C) -> Option<(Cmp, Linear)>
+    where
+        C: Ctx<Prop> + Ctx<Time> + Ctx<Expr>,
+    {
+        let (op, l, r) = match ctx.get(self) {
+            Prop::Cmp(CmpOp { op, lhs, rhs }) => {
+                (op, lhs.linear(ctx)?, rhs.linear(ctx)?)
+            }
+            Prop::TimeCmp(CmpOp { op, lhs, rhs }) => {
+                (op, lhs.linear(ctx)?, rhs.linear(ctx)?)
+            }
+            Prop::TimeSubCmp(CmpOp { op, lhs, rhs }) => {
+                (op, lhs.linear(ctx)?, rhs.linear(ctx)?)
+            }
+            _ => return None,
+        };
+        Some((op.clone(), l.sub(&r).ok()?))
+    }
+}
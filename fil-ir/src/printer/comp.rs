@@ -159,23 +159,22 @@ impl<'a, 'b> Printer<'a, 'b> {
             write!(f, "comp")?;
         }
         write!(f, "[")?;
-        for pos in self
+        let params = self
             .comp
             .params()
             .iter()
             .filter(|(_, p)| p.is_sig_owned())
-            .map(|(idx, _)| idx)
-            .with_position()
-        {
-            match pos {
-                Position::First(idx) | Position::Middle(idx) => {
-                    write!(f, "{}, ", self.comp.display(idx))?
+            .map(|(idx, p)| {
+                let info: Option<&ir::info::Param> =
+                    self.comp.get(p.info).into();
+                match info.and_then(|i| i.range.as_ref()) {
+                    Some(range) => {
+                        format!("{}: {range}", self.comp.display(idx))
+                    }
+                    None => self.comp.display(idx),
                 }
-                Position::Only(idx) | Position::Last(idx) => {
-                    write!(f, "{}", self.comp.display(idx))?
-                }
-            }
-        }
+            });
+        write!(f, "{}", params.format(", "))?;
         write!(f, "]<")?;
         // All events are defined by the signature
         for pos in self.comp.events().iter().with_position() {
@@ -181,6 +181,15 @@ impl<'a> DisplayCtx<&'a ir::Access> for ir::Component {
     }
 }
 
+impl DisplayCtx<&ir::Source> for ir::Component {
+    fn write(&self, s: &ir::Source, f: &mut impl Write) -> std::fmt::Result {
+        match s {
+            ir::Source::Port(a) => self.write(a, f),
+            ir::Source::Const(c) => write!(f, "{c}"),
+        }
+    }
+}
+
 impl<'a> DisplayCtx<&'a ir::Connect> for ir::Component {
     fn write(&self, c: &ir::Connect, f: &mut impl Write) -> std::fmt::Result {
         let ir::Connect { src, dst, .. } = c;
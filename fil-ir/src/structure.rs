@@ -2,7 +2,7 @@ use super::{
     AddCtx, Bind, Component, Ctx, Expr, ExprIdx, Foldable, Foreign, InfoIdx,
     InstIdx, InvIdx, ParamIdx, PortIdx, Subst, TimeIdx, TimeSub,
 };
-use fil_ast::Op;
+use fil_ast::{self as ast, Op};
 use std::fmt;
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -270,6 +270,32 @@ impl Access {
     }
 }
 
+#[derive(PartialEq, Eq, Hash, Clone)]
+/// The value driven by a connection
+pub enum Source {
+    /// An access into a port
+    Port(Access),
+    /// A constant that is available at all times. Constants without a width
+    /// take the width of the destination.
+    Const(ast::Constant),
+}
+
+impl Source {
+    /// The access if the source is a port
+    pub fn as_port(&self) -> Option<&Access> {
+        match self {
+            Source::Port(a) => Some(a),
+            Source::Const(_) => None,
+        }
+    }
+}
+
+impl From<Access> for Source {
+    fn from(a: Access) -> Self {
+        Source::Port(a)
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone)]
 /// Construct that defines the parameter
 pub enum ParamOwner {
@@ -32,7 +32,17 @@ impl From<ir::Context> for Traversal {
             }
         }
 
-        let order: Vec<_> = ts.collect();
+        // Components without dependencies between them are ordered by index
+        // so that the traversal is deterministic.
+        let mut order = Vec::with_capacity(comps.len());
+        loop {
+            let mut layer = ts.pop_all();
+            if layer.is_empty() {
+                break;
+            }
+            layer.sort();
+            order.extend(layer);
+        }
         assert!(
             order.len() == comps.len(),
             "Ordering contains {} elements but context has {} components",
@@ -213,7 +213,9 @@ impl<'a> Validate<'a> {
     /// (2) The range of the src and dst accesses match
     fn connect(&self, connect: &ir::Connect) {
         let ir::Connect { src, dst, .. } = connect;
-        self.access(src);
+        if let Some(src) = src.as_port() {
+            self.access(src);
+        }
         self.access(dst);
     }
 
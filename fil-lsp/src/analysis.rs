@@ -0,0 +1,410 @@
+//! Answers queries about the definitions in a checked program.
+use crate::check::Checked;
+use codespan_reporting::files::Files;
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx};
+use fil_utils::{GPosIdx, GlobalPositionTable};
+use itertools::Itertools;
+use lsp_types as lsp;
+use std::path::{Path, PathBuf};
+
+/// A span in one of the files in the [GlobalPositionTable]
+#[derive(Clone, Copy)]
+pub struct Span {
+    pub file: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The span of a position. `None` if the position is unknown.
+    pub fn of(pos: GPosIdx) -> Option<Self> {
+        let pos = pos.into_option()?;
+        let data = GlobalPositionTable::as_ref().get_pos(pos.0);
+        Some(Self {
+            file: data.file.get(),
+            start: data.start,
+            end: data.end,
+        })
+    }
+
+    /// Path of the file containing the span
+    pub fn path(&self) -> PathBuf {
+        path(self.file)
+    }
+
+    /// The span as an LSP range
+    pub fn range(&self) -> lsp::Range {
+        lsp::Range::new(
+            position(self.file, self.start),
+            position(self.file, self.end),
+        )
+    }
+
+    /// The span as an LSP location
+    pub fn location(&self) -> Option<lsp::Location> {
+        let uri = lsp::Url::from_file_path(self.path()).ok()?;
+        Some(lsp::Location::new(uri, self.range()))
+    }
+}
+
+/// Canonical path of a file in the [GlobalPositionTable]
+pub fn path(file: usize) -> PathBuf {
+    let files = GlobalPositionTable::as_ref().files();
+    let name = PathBuf::from(files.name(file).unwrap());
+    name.canonicalize().unwrap_or(name)
+}
+
+/// Convert a byte offset in a file of the [GlobalPositionTable] into an LSP
+/// position.
+pub fn position(file: usize, offset: usize) -> lsp::Position {
+    let files = GlobalPositionTable::as_ref().files();
+    let src = files.source(file).unwrap();
+    let line = files.line_index(file, offset).unwrap();
+    let start = files.line_range(file, line).unwrap().start;
+    let col = src[start..offset].encode_utf16().count();
+    lsp::Position::new(line as u32, col as u32)
+}
+
+/// Convert an LSP position into a byte offset in `text`.
+pub fn offset(text: &str, pos: lsp::Position) -> usize {
+    let start = text
+        .split_inclusive('\n')
+        .take(pos.line as usize)
+        .map(|l| l.len())
+        .sum::<usize>();
+    let mut col = 0;
+    for (i, c) in text[start..].char_indices() {
+        if col >= pos.character as usize || c == '\n' {
+            return start + i;
+        }
+        col += c.len_utf16();
+    }
+    text.len()
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// How the identifier under the cursor is accessed
+enum Access<'a> {
+    /// A name in the scope of the component
+    Plain,
+    /// Port of an invocation: `inv.name`
+    Port(&'a str),
+    /// Existentially quantified parameter of an instance: `inst::name`
+    Exists(&'a str),
+}
+
+/// The identifier at `offset` in `text` along with how it is accessed.
+fn word_at(text: &str, offset: usize) -> Option<(&str, Access<'_>)> {
+    let start = text[..offset].rfind(|c| !is_ident(c)).map_or(0, |i| i + 1);
+    let end = text[offset..]
+        .find(|c| !is_ident(c))
+        .map_or(text.len(), |i| offset + i);
+    if start == end {
+        return None;
+    }
+    let before = &text[..start];
+    // The identifier that precedes the separator
+    let prefix = |sep: &str| {
+        let rest = before.strip_suffix(sep)?;
+        let s = rest.rfind(|c| !is_ident(c)).map_or(0, |i| i + 1);
+        Some(&rest[s..]).filter(|p| !p.is_empty())
+    };
+    let access = if let Some(inv) = prefix(".") {
+        Access::Port(inv)
+    } else if let Some(inst) = prefix("::") {
+        Access::Exists(inst)
+    } else {
+        Access::Plain
+    };
+    Some((&text[start..end], access))
+}
+
+/// The identifier of an invocation that precedes a `.` right before the
+/// cursor. The cursor may be in the middle of typing the port name.
+fn inv_before(text: &str, offset: usize) -> Option<&str> {
+    let before = text[..offset].trim_end_matches(is_ident);
+    let rest = before.strip_suffix('.')?;
+    let start = rest.rfind(|c| !is_ident(c)).map_or(0, |i| i + 1);
+    Some(&rest[start..]).filter(|inv| !inv.is_empty())
+}
+
+/// A definition found by [Analysis::resolve]
+pub struct Def {
+    /// Location of the definition
+    pub span: Span,
+    /// The component whose signature describes the definition: the
+    /// component itself or the component of an instance or invocation.
+    pub comp: Option<ir::CompIdx>,
+}
+
+/// Results of checking a document
+pub struct Analysis {
+    checked: Checked,
+}
+
+impl Analysis {
+    pub fn new(checked: Checked) -> Self {
+        Self { checked }
+    }
+
+    pub fn diagnostics(
+        &self,
+    ) -> &[codespan_reporting::diagnostic::Diagnostic<usize>] {
+        &self.checked.diags
+    }
+
+    /// True if the program could be transformed into the IR
+    pub fn has_ir(&self) -> bool {
+        self.checked.ctx.is_some()
+    }
+
+    /// The component whose definition contains the offset in the file
+    fn enclosing(&self, file: &Path, offset: usize) -> Option<ir::CompIdx> {
+        self.checked
+            .sigs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, sig)| {
+                let span = Span::of(sig.name.pos())?;
+                (span.path() == file && span.start <= offset)
+                    .then_some((span.start, idx))
+            })
+            .max()
+            .map(|(_, idx)| ir::CompIdx::new(idx))
+    }
+
+    /// Names defined within a component along with the location of their
+    /// binding and the component they refer to.
+    fn locals(
+        comp: &ir::Component,
+    ) -> Vec<(ast::Id, GPosIdx, Option<ir::CompIdx>)> {
+        let mut defs = vec![];
+        for (_, param) in comp.params().iter() {
+            if let Some(p) = comp.get(param.info).as_param() {
+                defs.push((p.name, p.bind_loc, None));
+            }
+        }
+        for (_, ev) in comp.events().iter() {
+            if let Some(e) = comp.get(ev.info).as_event() {
+                defs.push((e.name, e.bind_loc, None));
+            }
+        }
+        for (_, port) in comp.ports().iter() {
+            if matches!(port.owner, ir::PortOwner::Inv { .. }) {
+                continue;
+            }
+            if let Some(p) = comp.get(port.info).as_port() {
+                defs.push((p.name, p.bind_loc, None));
+            }
+        }
+        for (_, inst) in comp.instances().iter() {
+            if let Some(i) = comp.get(inst.info).as_instance() {
+                defs.push((i.name, i.bind_loc, Some(inst.comp)));
+            }
+        }
+        for (_, inv) in comp.invocations().iter() {
+            if let Some(i) = comp.get(inv.info).as_invoke() {
+                let child = comp.get(inv.inst).comp;
+                defs.push((i.name, i.bind_loc, Some(child)));
+            }
+        }
+        defs
+    }
+
+    /// The component of the invocation or instance named `name` in `comp`
+    fn child(&self, comp: ir::CompIdx, name: &str) -> Option<ir::CompIdx> {
+        let ctx = self.checked.ctx.as_ref()?;
+        Self::locals(ctx.get(comp))
+            .into_iter()
+            .find(|(n, _, c)| n == name && c.is_some())
+            .and_then(|(_, _, c)| c)
+    }
+
+    /// Find the definition of the identifier at `offset` in `text`, the
+    /// contents of `file`.
+    pub fn resolve(
+        &self,
+        file: &Path,
+        text: &str,
+        offset: usize,
+    ) -> Option<Def> {
+        let (word, access) = word_at(text, offset)?;
+        let cur = self.enclosing(file, offset);
+        let ctx = self.checked.ctx.as_ref();
+
+        // Names defined by the signature of another component
+        let foreign = |child: ir::CompIdx| {
+            let child = ctx?.get(child);
+            Self::locals(child)
+                .into_iter()
+                .find(|(n, _, _)| n == word)
+                .and_then(|(_, pos, _)| Span::of(pos))
+                .map(|span| Def { span, comp: None })
+        };
+        match access {
+            Access::Port(inv) => return foreign(self.child(cur?, inv)?),
+            Access::Exists(inst) => return foreign(self.child(cur?, inst)?),
+            Access::Plain => (),
+        }
+
+        // Prefer the closest definition before the use for names, like loop
+        // indices, that are bound multiple times.
+        if let (Some(cur), Some(ctx)) = (cur, ctx) {
+            let defs = Self::locals(ctx.get(cur))
+                .into_iter()
+                .filter(|(n, _, _)| n == word)
+                .filter_map(|(_, pos, c)| Some((Span::of(pos)?, c)))
+                .collect_vec();
+            let before = defs
+                .iter()
+                .filter(|(s, _)| s.path() == file && s.start <= offset)
+                .max_by_key(|(s, _)| s.start);
+            if let Some(&(span, comp)) = before.or_else(|| defs.first()) {
+                return Some(Def { span, comp });
+            }
+        }
+
+        // Components
+        self.checked
+            .sigs
+            .iter()
+            .enumerate()
+            .find(|(_, sig)| sig.name.inner() == word)
+            .and_then(|(idx, sig)| {
+                Some(Def {
+                    span: Span::of(sig.name.pos())?,
+                    comp: Some(ir::CompIdx::new(idx)),
+                })
+            })
+    }
+
+    /// Signature of the component
+    pub fn signature(&self, comp: ir::CompIdx) -> String {
+        signature(&self.checked.sigs[comp.get()])
+    }
+
+    /// Completions for the ports of the invocation preceding the cursor
+    pub fn complete_ports(
+        &self,
+        file: &Path,
+        text: &str,
+        offset: usize,
+    ) -> Vec<lsp::CompletionItem> {
+        let Some(child) = inv_before(text, offset)
+            .zip(self.enclosing(file, offset))
+            .and_then(|(inv, cur)| self.child(cur, inv))
+        else {
+            return vec![];
+        };
+        let sig = &self.checked.sigs[child.get()];
+        let inputs = sig.inputs().map(|p| (p, "input"));
+        let outputs = sig.outputs().map(|p| (p, "output"));
+        inputs
+            .chain(outputs)
+            .map(|(p, dir)| {
+                let (name, def) = port(p.inner());
+                lsp::CompletionItem {
+                    label: name,
+                    kind: Some(lsp::CompletionItemKind::FIELD),
+                    detail: Some(format!("{dir} {def}")),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+}
+
+fn time(t: &ast::Time) -> String {
+    match &t.offset {
+        ast::Expr::Concrete(0) => format!("'{}", t.event),
+        e => format!("'{}+{e}", t.event),
+    }
+}
+
+fn range(r: &ast::Range) -> String {
+    format!("[{}, {}]", time(&r.start), time(&r.end))
+}
+
+/// The name of a port and its definition
+fn port(p: &ast::PortDef) -> (String, String) {
+    match p {
+        ast::PortDef::Port {
+            name,
+            liveness,
+            bitwidth,
+        } => (
+            name.to_string(),
+            format!("{name}: {} {bitwidth}", range(liveness)),
+        ),
+        // Ports without a bundle index are parsed as bundles of size one
+        ast::PortDef::Bundle(ast::Bundle { name, typ })
+            if typ.idx.pos().into_option().is_none()
+                && matches!(typ.len.inner(), ast::Expr::Concrete(1)) =>
+        {
+            (
+                name.to_string(),
+                format!("{name}: {} {}", range(&typ.liveness), typ.bitwidth),
+            )
+        }
+        ast::PortDef::Bundle(ast::Bundle { name, typ }) => (
+            name.to_string(),
+            format!(
+                "{name}[{}]: for<{}> {} {}",
+                typ.len,
+                typ.idx,
+                range(&typ.liveness),
+                typ.bitwidth
+            ),
+        ),
+    }
+}
+
+/// Render a signature like its definition in the source
+fn signature(sig: &ast::Signature) -> String {
+    let params = sig.params().map(|p| p.to_string()).collect_vec();
+    let params = if params.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", params.join(", "))
+    };
+    let events = sig.events.iter().map(|eb| {
+        let delay = match eb.delay.inner() {
+            ast::TimeSub::Unit(e) => e.to_string(),
+            ast::TimeSub::Sym { l, r } => format!("{}-({})", time(l), time(r)),
+        };
+        format!("'{}: {delay}", eb.event)
+    });
+    let ports = |ports: Vec<String>| {
+        if ports.is_empty() {
+            return String::new();
+        }
+        ports
+            .into_iter()
+            .map(|p| format!("\n    {p},"))
+            .collect::<String>()
+            + "\n"
+    };
+    let inputs = sig
+        .unannotated_ports
+        .iter()
+        .map(|(name, width)| format!("{name}: {width}"))
+        .chain(
+            sig.interface_signals
+                .iter()
+                .map(|i| format!("{}: interface['{}]", i.name, i.event)),
+        )
+        .chain(sig.inputs().map(|p| port(p.inner()).1))
+        .collect_vec();
+    let outputs = sig.outputs().map(|p| port(p.inner()).1).collect_vec();
+    format!(
+        "comp {}{params}<{}>({}) -> ({})",
+        sig.name,
+        events.format(", "),
+        ports(inputs),
+        ports(outputs)
+    )
+}
@@ -0,0 +1,88 @@
+//! Runs the checking passes of the compiler on a document.
+use argh::FromArgs;
+use codespan_reporting::diagnostic::Diagnostic;
+use fil_ast as ast;
+use fil_ir as ir;
+use filament::{cmdline, pipeline, resolver::Resolver};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// Result of checking a program
+pub struct Checked {
+    /// Signatures of the components in the program in the order of their
+    /// [ir::CompIdx].
+    pub sigs: Vec<ast::Signature>,
+    /// The program after running the checking passes. `None` if the program
+    /// could not be transformed into the IR.
+    pub ctx: Option<ir::Context>,
+    /// Diagnostics reported by the compiler
+    pub diags: Vec<Diagnostic<usize>>,
+}
+
+/// Parse the compiler options for checking `input`. `flags` are the flags
+/// passed to the language server and are forwarded to the compiler.
+pub fn opts(input: &Path, flags: &[String]) -> Result<cmdline::Opts, String> {
+    let input = input.to_string_lossy();
+    let args = std::iter::once(input.as_ref())
+        .chain(flags.iter().map(|f| f.as_str()))
+        .collect::<Vec<_>>();
+    cmdline::Opts::from_args(&["filament"], &args).map_err(|e| e.output)
+}
+
+/// Describes the payload of a panic
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+/// Check the program rooted at the input file of `opts`.
+/// Internal compiler errors are reported as diagnostics instead of bringing
+/// down the language server.
+pub fn check(opts: &cmdline::Opts) -> Checked {
+    let ((sigs, ctx), diags) = fil_utils::capture(|| {
+        let mut ns = match Resolver::from(opts).parse_namespace() {
+            Ok(ns) => ns,
+            Err(e) => {
+                let diag = e.diagnostic().unwrap_or_else(|| {
+                    Diagnostic::error().with_message(e.kind)
+                });
+                fil_utils::emit(&diag, &[]);
+                return (vec![], None);
+            }
+        };
+        ns.toplevel = opts.toplevel.clone();
+
+        let sigs = ns
+            .externals()
+            .map(|(_, sig)| sig.clone())
+            .chain(ns.components.iter().map(|c| c.sig.clone()))
+            .collect();
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut ir = ir::transform(ns).ok()?;
+            // Errors have already been captured. The program before
+            // monomorphization is kept since it matches the source.
+            let _ = pipeline::check(opts, &mut ir);
+            Some(ir)
+        }));
+        match res {
+            Ok(ctx) => (sigs, ctx),
+            Err(payload) => {
+                let msg = panic_message(payload);
+                fil_utils::emit(
+                    &Diagnostic::bug().with_message(format!(
+                        "internal compiler error: {msg}"
+                    )),
+                    &[],
+                );
+                (sigs, None)
+            }
+        }
+    });
+    Checked { sigs, ctx, diags }
+}
@@ -0,0 +1,339 @@
+//! Language server for Filament. Publishes the diagnostics generated by the
+//! compiler when a document is opened or saved and answers go-to-definition,
+//! hover, and completion requests using the results of the last check.
+//!
+//! Arguments passed to the server are forwarded to the compiler, for example,
+//! `filament-lsp --library /path/to/filament --solver z3`.
+mod analysis;
+mod check;
+
+use analysis::{Analysis, Span};
+use codespan_reporting::diagnostic::{Diagnostic, LabelStyle, Severity};
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types as lsp;
+use lsp_types::notification::{self as notif, Notification as _};
+use lsp_types::request::{self as req, Request as _};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+type Res<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// State of an open document
+struct Document {
+    /// Current contents of the document
+    text: String,
+    /// Results of the last check that produced the IR
+    analysis: Option<Analysis>,
+    /// Files for which the last check published diagnostics
+    published: Vec<lsp::Url>,
+}
+
+struct Server {
+    conn: Connection,
+    /// Flags forwarded to the compiler
+    flags: Vec<String>,
+    docs: HashMap<lsp::Url, Document>,
+}
+
+/// Position of errors without a label. Parse errors mention the position in
+/// their message using pest's ` --> file:line:col` format.
+fn message_pos(msg: &str) -> lsp::Position {
+    let pos = msg.split_once(" --> ").and_then(|(_, rest)| {
+        let loc = rest.split_whitespace().next()?;
+        let mut parts = loc.rsplitn(3, ':');
+        let col = parts.next()?.parse::<u32>().ok()?;
+        let line = parts.next()?.parse::<u32>().ok()?;
+        Some(lsp::Position::new(line - 1, col - 1))
+    });
+    pos.unwrap_or_default()
+}
+
+/// Convert a compiler diagnostic into an LSP diagnostic. Returns the file
+/// that the diagnostic should be reported in if it is not the root file.
+fn convert(diag: &Diagnostic<usize>) -> (Option<lsp::Url>, lsp::Diagnostic) {
+    let severity = match diag.severity {
+        Severity::Bug | Severity::Error => lsp::DiagnosticSeverity::ERROR,
+        Severity::Warning => lsp::DiagnosticSeverity::WARNING,
+        Severity::Note => lsp::DiagnosticSeverity::INFORMATION,
+        Severity::Help => lsp::DiagnosticSeverity::HINT,
+    };
+    let span = |l: &codespan_reporting::diagnostic::Label<usize>| Span {
+        file: l.file_id,
+        start: l.range.start,
+        end: l.range.end,
+    };
+    let primary = diag.labels.iter().find(|l| l.style == LabelStyle::Primary);
+
+    let mut message = diag.message.clone();
+    if let Some(l) = primary {
+        if !l.message.is_empty() && l.message != diag.message {
+            message = format!("{message}\n{}", l.message);
+        }
+    }
+    for note in &diag.notes {
+        message = format!("{message}\n{note}");
+    }
+    let related = diag
+        .labels
+        .iter()
+        .filter(|l| l.style == LabelStyle::Secondary)
+        .filter_map(|l| {
+            Some(lsp::DiagnosticRelatedInformation {
+                location: span(l).location()?,
+                message: l.message.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let (uri, range) = match primary.map(span) {
+        Some(s) => (lsp::Url::from_file_path(s.path()).ok(), s.range()),
+        None => {
+            let pos = message_pos(&diag.message);
+            (None, lsp::Range::new(pos, pos))
+        }
+    };
+    let diag = lsp::Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some("filament".to_string()),
+        message,
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    };
+    (uri, diag)
+}
+
+impl Server {
+    fn send(&self, msg: impl Into<Message>) -> Res<()> {
+        self.conn.sender.send(msg.into())?;
+        Ok(())
+    }
+
+    fn notify<N: notif::Notification>(&self, params: N::Params) -> Res<()> {
+        self.send(Notification::new(N::METHOD.to_string(), params))
+    }
+
+    /// Check the document and publish its diagnostics
+    fn check(&mut self, uri: lsp::Url) -> Res<()> {
+        let Ok(path) = uri.to_file_path() else {
+            return Ok(());
+        };
+        let opts = check::opts(&path, &self.flags)?;
+        let analysis = Analysis::new(check::check(&opts));
+
+        let mut diags: BTreeMap<lsp::Url, Vec<lsp::Diagnostic>> =
+            BTreeMap::new();
+        diags.insert(uri.clone(), vec![]);
+        for diag in analysis.diagnostics() {
+            let (file, diag) = convert(diag);
+            diags
+                .entry(file.unwrap_or(uri.clone()))
+                .or_default()
+                .push(diag);
+        }
+
+        let Some(doc) = self.docs.get_mut(&uri) else {
+            return Ok(());
+        };
+        // Keep answering queries using the last program that could be
+        // transformed into the IR while the document is being edited.
+        if analysis.has_ir() || doc.analysis.is_none() {
+            doc.analysis = Some(analysis);
+        }
+        // Clear the diagnostics of files that no longer have errors
+        let stale = std::mem::take(&mut doc.published);
+        for file in stale {
+            diags.entry(file).or_default();
+        }
+        doc.published = diags.keys().cloned().collect();
+        for (uri, diagnostics) in diags {
+            self.notify::<notif::PublishDiagnostics>(
+                lsp::PublishDiagnosticsParams::new(uri, diagnostics, None),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The analysis, path, contents, and cursor offset for a position in a
+    /// document.
+    fn at(
+        &self,
+        pos: &lsp::TextDocumentPositionParams,
+    ) -> Option<(&Analysis, PathBuf, &str, usize)> {
+        let uri = &pos.text_document.uri;
+        let doc = self.docs.get(uri)?;
+        let path = uri.to_file_path().ok()?;
+        let path = path.canonicalize().unwrap_or(path);
+        let offset = analysis::offset(&doc.text, pos.position);
+        Some((doc.analysis.as_ref()?, path, &doc.text, offset))
+    }
+
+    fn definition(
+        &self,
+        params: lsp::GotoDefinitionParams,
+    ) -> Option<lsp::GotoDefinitionResponse> {
+        let (analysis, path, text, offset) =
+            self.at(&params.text_document_position_params)?;
+        let def = analysis.resolve(&path, text, offset)?;
+        Some(lsp::GotoDefinitionResponse::Scalar(def.span.location()?))
+    }
+
+    fn hover(&self, params: lsp::HoverParams) -> Option<lsp::Hover> {
+        let (analysis, path, text, offset) =
+            self.at(&params.text_document_position_params)?;
+        let comp = analysis.resolve(&path, text, offset)?.comp?;
+        Some(lsp::Hover {
+            contents: lsp::HoverContents::Markup(lsp::MarkupContent {
+                kind: lsp::MarkupKind::Markdown,
+                value: format!(
+                    "```filament\n{}\n```",
+                    analysis.signature(comp)
+                ),
+            }),
+            range: None,
+        })
+    }
+
+    fn completion(
+        &self,
+        params: lsp::CompletionParams,
+    ) -> Option<lsp::CompletionResponse> {
+        let (analysis, path, text, offset) =
+            self.at(&params.text_document_position)?;
+        let items = analysis.complete_ports(&path, text, offset);
+        Some(lsp::CompletionResponse::Array(items))
+    }
+
+    fn request(&mut self, req: Request) -> Res<()> {
+        fn params<R: req::Request>(req: Request) -> Res<R::Params> {
+            Ok(serde_json::from_value(req.params)?)
+        }
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            req::GotoDefinition::METHOD => serde_json::to_value(
+                self.definition(params::<req::GotoDefinition>(req)?),
+            )?,
+            req::HoverRequest::METHOD => serde_json::to_value(
+                self.hover(params::<req::HoverRequest>(req)?),
+            )?,
+            req::Completion::METHOD => serde_json::to_value(
+                self.completion(params::<req::Completion>(req)?),
+            )?,
+            method => {
+                return self.send(Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request: {method}"),
+                ))
+            }
+        };
+        self.send(Response {
+            id,
+            result: Some(result),
+            error: None,
+        })
+    }
+
+    fn notification(&mut self, not: Notification) -> Res<()> {
+        fn params<N: notif::Notification>(not: Notification) -> Res<N::Params> {
+            Ok(serde_json::from_value(not.params)?)
+        }
+        match not.method.as_str() {
+            notif::DidOpenTextDocument::METHOD => {
+                let params = params::<notif::DidOpenTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                self.docs.insert(
+                    uri.clone(),
+                    Document {
+                        text: params.text_document.text,
+                        analysis: None,
+                        published: vec![],
+                    },
+                );
+                self.check(uri)?;
+            }
+            notif::DidChangeTextDocument::METHOD => {
+                let params = params::<notif::DidChangeTextDocument>(not)?;
+                let uri = params.text_document.uri;
+                if let (Some(doc), Some(change)) =
+                    (self.docs.get_mut(&uri), params.content_changes.last())
+                {
+                    doc.text = change.text.clone();
+                }
+            }
+            notif::DidSaveTextDocument::METHOD => {
+                let params = params::<notif::DidSaveTextDocument>(not)?;
+                self.check(params.text_document.uri)?;
+            }
+            notif::DidCloseTextDocument::METHOD => {
+                let params = params::<notif::DidCloseTextDocument>(not)?;
+                if let Some(doc) = self.docs.remove(&params.text_document.uri) {
+                    for uri in doc.published {
+                        self.notify::<notif::PublishDiagnostics>(
+                            lsp::PublishDiagnosticsParams::new(
+                                uri,
+                                vec![],
+                                None,
+                            ),
+                        )?;
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn run(&mut self) -> Res<()> {
+        while let Ok(msg) = self.conn.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.conn.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.request(req)?;
+                }
+                Message::Notification(not) => self.notification(not)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn main() -> Res<()> {
+    let flags = std::env::args().skip(1).collect::<Vec<_>>();
+    // Report invalid flags before starting the server
+    check::opts(&PathBuf::from("main.fil"), &flags)?;
+
+    let (conn, io_threads) = Connection::stdio();
+    let capabilities = lsp::ServerCapabilities {
+        text_document_sync: Some(lsp::TextDocumentSyncCapability::Options(
+            lsp::TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(lsp::TextDocumentSyncKind::FULL),
+                save: Some(lsp::TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        definition_provider: Some(lsp::OneOf::Left(true)),
+        hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+        completion_provider: Some(lsp::CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    conn.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        conn,
+        flags,
+        docs: HashMap::new(),
+    };
+    server.run()?;
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
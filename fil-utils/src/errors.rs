@@ -1,11 +1,17 @@
 //! Errors generated by the compiler.
-use super::{Id, InfoIdx};
+use super::{GPosIdx, Id, InfoIdx};
+use codespan_reporting::diagnostic::Diagnostic;
 use itertools::Itertools;
 
 #[derive(PartialEq, Eq, Hash)]
 pub struct Error {
     pub kind: String,
     pub notes: Vec<InfoIdx>,
+    /// Location of an error found before the program is converted into the
+    /// IR along with the message of its label
+    pub pos: Option<(GPosIdx, String)>,
+    /// Hints shown along with the error
+    pub help: Vec<String>,
 }
 
 impl std::fmt::Debug for Error {
@@ -33,6 +39,8 @@ impl Error {
         Self {
             kind: format!("invalid file: {}", f),
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
 
@@ -40,6 +48,8 @@ impl Error {
         Self {
             kind: format!("failed to write output: {}", e),
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
 
@@ -47,6 +57,8 @@ impl Error {
         Self {
             kind: msg.to_string(),
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
 
@@ -58,6 +70,8 @@ impl Error {
                 name.to_string(),
             ),
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
 
@@ -68,6 +82,8 @@ impl Error {
                 kind.to_string()
             ),
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
 
@@ -75,8 +91,36 @@ impl Error {
         Self {
             kind: msg,
             notes: vec![],
+            pos: None,
+            help: vec![],
         }
     }
+
+    /// Malformed program text at the given location
+    pub fn syntax<S: ToString, L: ToString>(
+        msg: S,
+        pos: GPosIdx,
+        label: L,
+        help: Vec<String>,
+    ) -> Self {
+        Self {
+            kind: msg.to_string(),
+            notes: vec![],
+            pos: Some((pos, label.to_string())),
+            help,
+        }
+    }
+
+    /// Diagnostic pointing at the location of the error if it is known
+    pub fn diagnostic(&self) -> Option<Diagnostic<usize>> {
+        let (pos, label) = self.pos.as_ref()?;
+        Some(
+            Diagnostic::error()
+                .with_message(&self.kind)
+                .with_labels(vec![pos.primary().with_message(label)])
+                .with_notes(self.help.clone()),
+        )
+    }
 }
 
 /// Convience wrapper to represent success or meaningul compiler error.
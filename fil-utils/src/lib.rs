@@ -4,9 +4,11 @@ mod gsym;
 mod id;
 mod position;
 mod reporter;
+mod suggest;
 
 pub use errors::{Error, FilamentResult};
 pub use gsym::GSym;
 pub use id::Id;
 pub use position::{FileIdx, GPosIdx, GlobalPositionTable, PosData};
-pub use reporter::{Diagnostics, InfoIdx};
+pub use reporter::{capture, emit, Diagnostics, ErrorFormat, InfoIdx};
+pub use suggest::did_you_mean;
@@ -1,10 +1,148 @@
 use crate::{Error, GPosIdx, GlobalPositionTable};
 use codespan_reporting::term::termcolor::ColorChoice;
 use codespan_reporting::{
-    diagnostic::{Diagnostic, Label, LabelStyle},
+    diagnostic::{Diagnostic, Label, LabelStyle, Severity},
+    files::Files,
     term::{self, termcolor::StandardStream},
 };
+use serde_json::json;
 use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Whether diagnostics are emitted as JSON. Set once by the driver.
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Diagnostics collected by [capture] instead of being emitted.
+static CAPTURED: Mutex<Option<Vec<Diagnostic<usize>>>> = Mutex::new(None);
+
+/// Run `f` and return the diagnostics emitted while running it instead of
+/// printing them. Used by tools that present the diagnostics themselves.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Diagnostic<usize>>) {
+    *CAPTURED.lock().unwrap() = Some(vec![]);
+    let out = f();
+    let diags = CAPTURED.lock().unwrap().take().unwrap_or_default();
+    (out, diags)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Format used to emit diagnostics
+pub enum ErrorFormat {
+    #[default]
+    /// Human readable diagnostics with source snippets
+    Human,
+    /// One JSON object per diagnostic on each line
+    Json,
+}
+
+impl ErrorFormat {
+    /// Use this format for all diagnostics emitted by the compiler.
+    pub fn set_global(self) {
+        JSON_ERRORS.store(self == ErrorFormat::Json, Ordering::Relaxed);
+    }
+
+    /// The format used for diagnostics emitted by the compiler.
+    pub fn global() -> Self {
+        if JSON_ERRORS.load(Ordering::Relaxed) {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Human
+        }
+    }
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "unknown error format: {s}. Known formats are: human, json"
+            )),
+        }
+    }
+}
+
+/// Emit a diagnostic to stderr using the global [ErrorFormat] unless it is
+/// being captured by [capture].
+/// `model` is an assignment to parameters that falsifies the constraint
+/// reported by the diagnostic. It is only reported in the JSON format since
+/// the human readable format already mentions it in the notes.
+pub fn emit(diag: &Diagnostic<usize>, model: &[(String, String)]) {
+    if let Some(diags) = CAPTURED.lock().unwrap().as_mut() {
+        diags.push(diag.clone());
+        return;
+    }
+    let table = GlobalPositionTable::as_ref();
+    match ErrorFormat::global() {
+        ErrorFormat::Human => {
+            let is_tty = atty::is(atty::Stream::Stderr);
+            let writer = StandardStream::stderr(if is_tty {
+                ColorChoice::Always
+            } else {
+                ColorChoice::Never
+            });
+            term::emit(
+                &mut writer.lock(),
+                &term::Config::default(),
+                table.files(),
+                diag,
+            )
+            .unwrap();
+        }
+        ErrorFormat::Json => {
+            eprintln!("{}", to_json(diag, model))
+        }
+    }
+}
+
+/// Convert the diagnostic into a JSON object.
+fn to_json(diag: &Diagnostic<usize>, model: &[(String, String)]) -> String {
+    let files = GlobalPositionTable::as_ref().files();
+    let severity = match diag.severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    };
+    let labels = diag
+        .labels
+        .iter()
+        .map(|l| {
+            let start = files.location(l.file_id, l.range.start).unwrap();
+            let end = files.location(l.file_id, l.range.end).unwrap();
+            json!({
+                "style": match l.style {
+                    LabelStyle::Primary => "primary",
+                    LabelStyle::Secondary => "secondary",
+                },
+                "message": l.message,
+                "file": files.name(l.file_id).unwrap(),
+                "byte_start": l.range.start,
+                "byte_end": l.range.end,
+                "line_start": start.line_number,
+                "column_start": start.column_number,
+                "line_end": end.line_number,
+                "column_end": end.column_number,
+            })
+        })
+        .collect::<Vec<_>>();
+    let model = model
+        .iter()
+        .map(|(param, value)| json!({ "param": param, "value": value }))
+        .collect::<Vec<_>>();
+    json!({
+        "severity": severity,
+        "message": diag.message,
+        "labels": labels,
+        "notes": diag.notes,
+        "counterexample": model,
+    })
+    .to_string()
+}
 
 #[derive(PartialOrd, Ord, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 /// Index for information associated with a [Diagnostic] instance.
@@ -82,12 +220,6 @@ impl Diagnostics {
     /// Report all errors and return the number of errors.
     /// Returns None if there are no errors.
     pub fn report_all(&mut self) -> Option<u64> {
-        let is_tty = atty::is(atty::Stream::Stderr);
-        let writer = StandardStream::stderr(if is_tty {
-            ColorChoice::Always
-        } else {
-            ColorChoice::Never
-        });
         if self.errors.is_empty() {
             return None;
         }
@@ -141,16 +273,13 @@ impl Diagnostics {
             };
 
             total += 1;
-            term::emit(
-                &mut writer.lock(),
-                &term::Config::default(),
-                table.files(),
+            emit(
                 &Diagnostic::error()
                     .with_message(msg)
                     .with_labels(labels)
                     .with_notes(notes),
-            )
-            .unwrap();
+                &[],
+            );
         }
 
         Some(total)
@@ -0,0 +1,72 @@
+//! Suggestions for misspelled names.
+use itertools::Itertools;
+
+/// Maximum number of names suggested at once
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Edit distance between two strings counting insertions, deletions,
+/// substitutions, and transpositions of adjacent characters.
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect_vec();
+    let b = b.chars().collect_vec();
+    // `d[i][j]` is the distance between the first `i` characters of `a` and
+    // the first `j` characters of `b`
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let sub = d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = sub.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Names among the candidates that are close to `name`, closest first. Names
+/// that only differ in case are always considered close while other names
+/// must share at least one character with `name`.
+fn similar<S: AsRef<str>>(
+    name: &str,
+    candidates: impl IntoIterator<Item = S>,
+) -> Vec<String> {
+    let len = name.chars().count();
+    let max = (len / 3).max(1).min(len.saturating_sub(1));
+    candidates
+        .into_iter()
+        .map(|c| c.as_ref().to_string())
+        .filter(|c| c != name)
+        .unique()
+        .filter_map(|c| {
+            let d = if c.eq_ignore_ascii_case(name) {
+                0
+            } else {
+                distance(name, &c)
+            };
+            (d <= max).then_some((d, c))
+        })
+        .sorted()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// A help message suggesting the candidates that are close to `name`
+pub fn did_you_mean<S: AsRef<str>>(
+    name: &str,
+    candidates: impl IntoIterator<Item = S>,
+) -> Option<String> {
+    match &similar(name, candidates)[..] {
+        [] => None,
+        [c] => Some(format!("help: did you mean `{c}'?")),
+        cs => Some(format!(
+            "help: did you mean one of {}?",
+            cs.iter().map(|c| format!("`{c}'")).join(", ")
+        )),
+    }
+}
@@ -1,4 +1,5 @@
 use argh::FromArgs;
+use fil_utils::ErrorFormat;
 use std::{path::PathBuf, str::FromStr};
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -7,6 +8,10 @@ pub enum Solver {
     #[default]
     CVC5,
     Z3,
+    /// In-process decision procedure for linear arithmetic
+    Builtin,
+    /// Race z3 and cvc5 and use the first definitive answer
+    Portfolio,
 }
 
 impl FromStr for Solver {
@@ -15,18 +20,33 @@ impl FromStr for Solver {
         match s {
             "z3" => Ok(Solver::Z3),
             "cvc5" => Ok(Solver::CVC5),
-            _ => {
-                Err(format!("unknown solver: {s}. Known solvers are: z3, cvc5"))
-            }
+            "builtin" => Ok(Solver::Builtin),
+            "portfolio" => Ok(Solver::Portfolio),
+            _ => Err(format!(
+                "unknown solver: {s}. Known solvers are: z3, cvc5, builtin, portfolio"
+            )),
         }
     }
 }
 
+impl std::fmt::Display for Solver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Solver::CVC5 => "cvc5",
+            Solver::Z3 => "z3",
+            Solver::Builtin => "builtin",
+            Solver::Portfolio => "portfolio",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Debug, Default)]
 pub enum Backend {
     #[default]
     Verilog,
     Calyx,
+    Sv,
 }
 
 impl FromStr for Backend {
@@ -35,8 +55,9 @@ impl FromStr for Backend {
         match s {
             "calyx" => Ok(Backend::Calyx),
             "verilog" => Ok(Backend::Verilog),
+            "sv" => Ok(Backend::Sv),
             _ => Err(format!(
-                "unknown backend: {s}. Known backends are: calyx, verilog"
+                "unknown backend: {s}. Known backends are: calyx, verilog, sv"
             )),
         }
     }
@@ -47,7 +68,7 @@ impl FromStr for Backend {
 pub struct Opts {
     /// path to the primitives library
     #[argh(positional)]
-    pub input: PathBuf,
+    pub input: Option<PathBuf>,
 
     /// print out the IR
     #[argh(option, long = "dump-after")]
@@ -57,6 +78,18 @@ pub struct Opts {
     #[argh(switch, long = "show-models")]
     pub show_models: bool,
 
+    /// label the assumptions that contradict failing constraints
+    #[argh(switch, long = "explain")]
+    pub explain: bool,
+
+    /// report a minimal set of assumptions used to prove each constraint
+    #[argh(switch, long = "explain-proofs")]
+    pub explain_proofs: bool,
+
+    /// format of the reported errors (default: human): human, json
+    #[argh(option, long = "error-format", default = "ErrorFormat::Human")]
+    pub error_format: ErrorFormat,
+
     /// path to search for imports
     #[argh(option, long = "library", short = 'l', default = "\".\".into()")]
     pub library: PathBuf,
@@ -69,6 +102,10 @@ pub struct Opts {
     #[argh(switch, long = "dump-interface")]
     pub dump_interface: bool,
 
+    /// interpret the program using the transactions in the data file instead of generating hardware
+    #[argh(option, long = "interp")]
+    pub interp: Option<PathBuf>,
+
     /// set log level
     #[argh(option, long = "log", default = "log::LevelFilter::Warn")]
     pub log_level: log::LevelFilter,
@@ -81,15 +118,52 @@ pub struct Opts {
     #[argh(switch, long = "unsafe-skip-discharge")]
     pub unsafe_skip_discharge: bool,
 
-    /// backend to use (default: verilog): calyx, verilog
+    /// check the constraints on the monomorphized components instead of for
+    /// all parameter values
+    #[argh(switch, long = "verify-mono")]
+    pub verify_mono: bool,
+
+    /// backend to use (default: verilog): calyx, verilog, sv
     #[argh(option, long = "backend", default = "Backend::Verilog")]
     pub backend: Backend,
 
     // Solver specific configuration
-    /// solver to use (default: cvc5): cvc5, z3
+    /// solver to use (default: cvc5): cvc5, z3, builtin, portfolio
     #[argh(option, long = "solver", default = "Solver::CVC5")]
     pub solver: Solver,
 
+    /// time limit in milliseconds for each solver query
+    #[argh(option, long = "solver-timeout")]
+    pub solver_timeout: Option<u64>,
+
+    /// time limit in milliseconds for checking the constraints of each
+    /// component
+    #[argh(option, long = "component-timeout")]
+    pub component_timeout: Option<u64>,
+
+    /// report constraints that the solver cannot decide as warnings
+    #[argh(switch, long = "warn-unknown")]
+    pub warn_unknown: bool,
+
+    /// encode parameters, events, and times as bit-vectors of the given width
+    /// instead of unbounded integers. Requires z3 or cvc5.
+    #[argh(option, long = "bv-width")]
+    pub bv_width: Option<u32>,
+
+    /// cache the results of the solver in the given directory to skip solving
+    /// unchanged components in later runs
+    #[argh(option, long = "solver-cache")]
+    pub solver_cache: Option<PathBuf>,
+
+    /// write the proof obligations of each component to an SMT-LIB file in
+    /// the given directory
+    #[argh(option, long = "emit-smt")]
+    pub emit_smt: Option<PathBuf>,
+
+    /// number of solvers used to check components in parallel (default: 1)
+    #[argh(option, short = 'j', long = "jobs", default = "1")]
+    pub jobs: usize,
+
     /// dump interactions with the solver in the given file
     #[argh(option, long = "dump-solver-log")]
     pub solver_replay_file: Option<String>,
@@ -101,4 +175,32 @@ pub struct Opts {
     /// preserves original port names during compilation.
     #[argh(switch, long = "preserve-names")]
     pub preserve_names: bool,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+/// Commands that do not compile the input program
+pub enum Command {
+    Fmt(FmtOpts),
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "fmt")]
+/// Format Filament programs. Prints the formatted programs unless `--check`
+/// or `--write` is given.
+pub struct FmtOpts {
+    /// files to format
+    #[argh(positional)]
+    pub files: Vec<PathBuf>,
+
+    /// exit with an error if any file is not formatted
+    #[argh(switch, long = "check")]
+    pub check: bool,
+
+    /// write the formatted programs back to the files
+    #[argh(switch, short = 'w', long = "write")]
+    pub write: bool,
 }
@@ -0,0 +1,215 @@
+mod netlist;
+mod prims;
+
+use crate::ir_passes::max_states;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use itertools::Itertools;
+use netlist::{Netlist, Sig};
+use std::collections::HashMap;
+
+/// A port of the main component that is live during `start..end`.
+struct IoPort {
+    name: String,
+    sig: Sig,
+    start: u64,
+    end: u64,
+}
+
+/// Cycle-accurate interpreter for monomorphized programs.
+/// Mirrors the cocotb harness in `fud/harness`: a new transaction starts
+/// every `delay` cycles of the main component's event and each transaction
+/// provides the `i`th value of each input in the data file. The outputs of
+/// the transactions are reported in the same JSON format as the harness.
+pub struct Interpreter;
+
+impl Interpreter {
+    /// Parses a value from the data file. Strings may use the `0b` or `0x`
+    /// prefixes.
+    fn parse_value(v: &serde_json::Value) -> Option<u128> {
+        match v {
+            serde_json::Value::Number(n) => n.as_u64().map(u128::from),
+            serde_json::Value::String(s) => {
+                if let Some(b) = s.strip_prefix("0b") {
+                    u128::from_str_radix(b, 2).ok()
+                } else if let Some(h) = s.strip_prefix("0x") {
+                    u128::from_str_radix(h, 16).ok()
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses the data file into a mapping from input names to the value
+    /// provided by each transaction.
+    fn parse_data(data: &str) -> Result<HashMap<String, Vec<u128>>, String> {
+        let json: serde_json::Value = serde_json::from_str(data)
+            .map_err(|e| format!("failed to parse data: {e}"))?;
+        let serde_json::Value::Object(map) = json else {
+            return Err(
+                "data must be an object mapping inputs to values".into()
+            );
+        };
+        map.into_iter()
+            .map(|(name, vals)| {
+                let serde_json::Value::Array(vals) = vals else {
+                    return Err(format!("data for `{name}' must be an array"));
+                };
+                let vals = vals
+                    .iter()
+                    .map(|v| {
+                        Self::parse_value(v).ok_or_else(|| {
+                            format!("invalid value `{v}' for `{name}'")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((name, vals))
+            })
+            .collect()
+    }
+
+    /// Runs the transactions described by `data` on the entrypoint and
+    /// returns the outputs of each transaction as JSON.
+    pub fn run(ctx: &ir::Context, data: &str) -> Result<String, String> {
+        let data = Self::parse_data(data)?;
+        let Some(txns) = data.values().map(|v| v.len()).next() else {
+            return Err("data does not contain any inputs".into());
+        };
+        if data.values().any(|v| v.len() != txns) {
+            return Err("mismatched number of values for inputs".into());
+        }
+
+        let entrypoint = ctx
+            .entrypoint
+            .unwrap_or_else(|| panic!("No entrypoint found."));
+        let main = ctx.get(entrypoint);
+        let src_info = main
+            .src_info
+            .as_ref()
+            .unwrap_or_else(|| panic!("No source info found for main."));
+
+        let events = main.events().idx_iter().collect_vec();
+        let [event] = events[..] else {
+            return Err(
+                "interpreter only supports components with exactly one event"
+                    .into(),
+            );
+        };
+        let ev = main.get(event);
+        let ir::TimeSub::Unit(delay) = ev.delay else {
+            panic!("Event `{}` has a non-simple delay.", main.display(event));
+        };
+        let delay = delay.concrete(main);
+        let states = max_states(main)[&event];
+
+        let mut net = Netlist::default();
+        let mut ports = HashMap::new();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for (idx, p) in main.ports().iter() {
+            if !p.is_sig_in() && !p.is_sig_out() {
+                continue;
+            }
+            let sig = net.add_signal(p.width.concrete(main))?;
+            ports.insert(idx, sig);
+            let range = &p.live.range;
+            let port = IoPort {
+                name: src_info.ports.get(idx).to_string(),
+                sig,
+                start: main.get(range.start).offset.concrete(main),
+                end: main.get(range.end).offset.concrete(main),
+            };
+            if p.is_sig_in() {
+                inputs.push(port);
+            } else {
+                outputs.push(port);
+            }
+        }
+        let mut go = HashMap::new();
+        if ev.has_interface {
+            go.insert(event, net.add_signal(1)?);
+        }
+        net.add_comp(ctx, entrypoint, &ports, &go)?;
+
+        for inp in &inputs {
+            let Some(vals) = data.get(&inp.name) else {
+                return Err(format!(
+                    "no data provided for input `{}'",
+                    inp.name
+                ));
+            };
+            let width = net.width(inp.sig);
+            if let Some(v) =
+                vals.iter().find(|&&v| netlist::mask(v, width) != v)
+            {
+                return Err(format!(
+                    "value {v} for `{}' is not representable in {width} bits",
+                    inp.name
+                ));
+            }
+        }
+
+        // Transaction executing the given state in the cycle
+        let txn_at = |cycle: u64, start: u64, end: u64| {
+            (0..txns).filter(move |&i| {
+                let st = cycle.checked_sub(i as u64 * delay);
+                st.map_or(false, |st| st >= start && st < end)
+            })
+        };
+
+        // Like the harness, wait for one cycle with undefined inputs after
+        // the reset before starting the first transaction.
+        for inp in &inputs {
+            net.set(inp.sig, None);
+        }
+        if let Some(&go) = go.get(&event) {
+            net.set(go, Some(0));
+        }
+        net.settle()?;
+        net.tick();
+
+        let last = (txns as u64 - 1) * delay + states;
+        let mut results = vec![vec![vec![]; txns]; outputs.len()];
+        for cycle in 0..last {
+            if let Some(&go) = go.get(&event) {
+                let start = cycle % delay == 0 && cycle / delay < txns as u64;
+                net.set(go, Some(start as u128));
+            }
+            for inp in &inputs {
+                let v = txn_at(cycle, inp.start, inp.end)
+                    .next_back()
+                    .map(|i| data[&inp.name][i]);
+                net.set(inp.sig, v);
+            }
+            net.settle()?;
+            for (out, res) in outputs.iter().zip(results.iter_mut()) {
+                for i in txn_at(cycle, out.start, out.end) {
+                    res[i].push(net.get(out.sig));
+                }
+            }
+            net.tick();
+        }
+
+        // The harness waits for `delay` cycles after starting each transaction
+        let cycles = last.max(txns as u64 * delay);
+
+        // Same format as `json.dumps` in the cocotb harness
+        let outs = outputs.iter().zip(results).map(|(out, res)| {
+            let width = net.width(out.sig) as usize;
+            let txns = res.into_iter().enumerate().map(|(i, vals)| {
+                let vals = vals.into_iter().map(|v| match v {
+                    Some(v) => v.to_string(),
+                    None => format!("\"{}\"", "x".repeat(width)),
+                });
+                format!("\"{i}\": [{}]", vals.format(", "))
+            });
+            format!("\"{}\": {{{}}}", out.name, txns.format(", "))
+        });
+        Ok(format!(
+            "{{{}}}",
+            outs.chain(std::iter::once(format!("\"cycles\": {cycles}")))
+                .format(", ")
+        ))
+    }
+}
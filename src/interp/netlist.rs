@@ -0,0 +1,331 @@
+use super::prims::Prim;
+use crate::ir_passes::max_states;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use std::collections::HashMap;
+
+/// Index of a signal in the [Netlist]
+pub type Sig = usize;
+/// Value of a signal. `None` represents an undefined value (`'x`).
+pub type Value = Option<u128>;
+
+/// Truncates the value to `width` bits.
+pub fn mask(v: u128, width: u64) -> u128 {
+    if width >= 128 {
+        v
+    } else {
+        v & ((1 << width) - 1)
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Active when the FSM is in one of the states in `start..end`.
+struct Guard {
+    fsm: usize,
+    start: u64,
+    end: u64,
+}
+
+/// Reifies an event with an interface port. Like the simple FSMs generated
+/// by the compiler, `hist[k]` is true if the interface port was high `k`
+/// cycles ago.
+struct Fsm {
+    go: Sig,
+    hist: Vec<bool>,
+}
+
+/// Computes the value of a signal from other signals
+enum Driver {
+    /// The first source with an active guard. Zero if no guard is active.
+    Mux(Vec<(Option<Guard>, Sig)>),
+    /// High when any guard is active. Used for the interface ports of
+    /// instances.
+    Trigger(Vec<Guard>),
+}
+
+/// Flattened representation of a component and all its instances.
+#[derive(Default)]
+pub struct Netlist {
+    values: Vec<Value>,
+    widths: Vec<u64>,
+    drivers: Vec<Option<Driver>>,
+    fsms: Vec<Fsm>,
+    prims: Vec<Prim>,
+}
+
+impl Netlist {
+    /// Adds a new signal with an undefined value.
+    pub fn add_signal(&mut self, width: u64) -> Result<Sig, String> {
+        if width > 128 {
+            return Err(format!(
+                "interpreter does not support signals wider than 128 bits (found {width} bits)"
+            ));
+        }
+        self.values.push(None);
+        self.widths.push(width);
+        self.drivers.push(None);
+        Ok(self.values.len() - 1)
+    }
+
+    /// Adds a signal that always has the value of the constant.
+    fn add_constant(
+        &mut self,
+        c: &fil_ast::Constant,
+        width: u64,
+    ) -> Result<Sig, String> {
+        let sig = self.add_signal(width)?;
+        let lo = c.extract(0, width.min(64)) as u128;
+        let hi = if width > 64 {
+            c.extract(64, width - 64) as u128
+        } else {
+            0
+        };
+        self.values[sig] = Some(hi << 64 | lo);
+        Ok(sig)
+    }
+
+    pub fn get(&self, sig: Sig) -> Value {
+        self.values[sig]
+    }
+
+    pub fn set(&mut self, sig: Sig, v: Value) {
+        self.values[sig] = v.map(|v| mask(v, self.widths[sig]));
+    }
+
+    pub fn width(&self, sig: Sig) -> u64 {
+        self.widths[sig]
+    }
+
+    /// Adds the component to the netlist. The signature ports of the
+    /// component are represented using `ports` while the interface ports of
+    /// its events are represented using `events`.
+    pub fn add_comp(
+        &mut self,
+        ctx: &ir::Context,
+        idx: ir::CompIdx,
+        ports: &HashMap<ir::PortIdx, Sig>,
+        events: &HashMap<ir::EventIdx, Sig>,
+    ) -> Result<(), String> {
+        let comp = ctx.get(idx);
+
+        let mut fsms = HashMap::new();
+        for (ev, states) in max_states(comp) {
+            if let Some(&go) = events.get(&ev) {
+                fsms.insert(ev, self.fsms.len());
+                self.fsms.push(Fsm {
+                    go,
+                    hist: vec![false; states as usize],
+                });
+            }
+        }
+
+        // Widths of the ports used by the invocations of each instance
+        let mut widths = HashMap::new();
+        for (_, port) in comp.ports().iter() {
+            if let ir::PortOwner::Inv { inv, base, .. } = &port.owner {
+                let inst = comp.get(*inv).inst;
+                widths.insert((inst, base.key()), port.width.concrete(comp));
+            }
+        }
+
+        let mut inst_ports = HashMap::new();
+        let mut inst_events = HashMap::new();
+        for (inst_idx, inst) in comp.instances().iter() {
+            let child = ctx.get(inst.comp);
+            let mut c_ports = HashMap::new();
+            for (p, port) in child.ports().iter() {
+                if port.is_sig_in() || port.is_sig_out() {
+                    // Ports unused by the parent keep their undefined value
+                    let width = widths
+                        .get(&(inst_idx, p))
+                        .copied()
+                        .or_else(|| port.width.as_concrete(child))
+                        .unwrap_or(64);
+                    c_ports.insert(p, self.add_signal(width)?);
+                }
+            }
+            let mut c_events = HashMap::new();
+            for (ev, event) in child.events().iter() {
+                if event.has_interface {
+                    c_events.insert(ev, self.add_signal(1)?);
+                }
+            }
+
+            if child.is_ext {
+                self.add_prim(child, inst, comp, &c_ports, &c_events)?;
+            } else {
+                self.add_comp(ctx, inst.comp, &c_ports, &c_events)?;
+            }
+            inst_ports.insert(inst_idx, c_ports);
+            inst_events.insert(inst_idx, c_events);
+        }
+
+        // Invocations trigger the interface ports of their instance
+        for (_, inv) in comp.invocations().iter() {
+            for eb in &inv.events {
+                let Some(&go) = inst_events[&inv.inst].get(&eb.base.key())
+                else {
+                    continue;
+                };
+                let time = comp.get(eb.arg);
+                let Some(&fsm) = fsms.get(&time.event) else {
+                    comp.internal_error(format!(
+                        "event `{}' used to trigger an interface port does not have an interface",
+                        comp.display(time.event)
+                    ))
+                };
+                let start = time.offset.concrete(comp);
+                let guard = Guard {
+                    fsm,
+                    start,
+                    end: start + 1,
+                };
+                match self.drivers[go].get_or_insert(Driver::Trigger(vec![])) {
+                    Driver::Trigger(guards) => guards.push(guard),
+                    Driver::Mux(_) => {
+                        unreachable!("interface port driven by a connection")
+                    }
+                }
+            }
+        }
+
+        let signal = |p: ir::PortIdx| match &comp.get(p).owner {
+            ir::PortOwner::Sig { .. } => ports[&p],
+            ir::PortOwner::Inv { inv, base, .. } => {
+                inst_ports[&comp.get(*inv).inst][&base.key()]
+            }
+            ir::PortOwner::Local => {
+                unreachable!("Local ports should have been eliminated.")
+            }
+        };
+        for cmd in &comp.cmds {
+            let ir::Command::Connect(ir::Connect { dst, src, .. }) = cmd else {
+                continue;
+            };
+            assert!(
+                dst.is_port(comp),
+                "Bundles should have been compiled away."
+            );
+            let (range, src) = match src {
+                ir::Source::Port(src) => {
+                    assert!(
+                        src.is_port(comp),
+                        "Bundles should have been compiled away."
+                    );
+                    (&comp.get(src.port).live.range, signal(src.port))
+                }
+                // Constants are driven whenever the destination requires them
+                ir::Source::Const(c) => {
+                    let port = comp.get(dst.port);
+                    let width = port.width.concrete(comp);
+                    (&port.live.range, self.add_constant(c, width)?)
+                }
+            };
+            let start = comp.get(range.start);
+            let end = comp.get(range.end);
+            let guard = fsms.get(&start.event).map(|&fsm| Guard {
+                fsm,
+                start: start.offset.concrete(comp),
+                end: end.offset.concrete(comp),
+            });
+            let dst = signal(dst.port);
+            match self.drivers[dst].get_or_insert(Driver::Mux(vec![])) {
+                Driver::Mux(srcs) => srcs.push((guard, src)),
+                Driver::Trigger(_) => {
+                    unreachable!("interface port driven by a connection")
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the model of an external component instance
+    fn add_prim(
+        &mut self,
+        child: &ir::Component,
+        inst: &ir::Instance,
+        parent: &ir::Component,
+        ports: &HashMap<ir::PortIdx, Sig>,
+        events: &HashMap<ir::EventIdx, Sig>,
+    ) -> Result<(), String> {
+        let src = child.src_info.as_ref().unwrap();
+        let params = child
+            .sig_params()
+            .map(|p| src.params.get(p).to_string())
+            .zip(inst.args.iter().map(|e| e.concrete(parent)))
+            .collect();
+        let named = ports
+            .iter()
+            .map(|(p, sig)| (src.ports.get(*p).to_string(), *sig))
+            .chain(events.iter().map(|(ev, sig)| {
+                (src.interface_ports.get(*ev).to_string(), *sig)
+            }))
+            .collect();
+        let Some(prim) = Prim::new(src.name.as_ref(), params, named) else {
+            return Err(format!(
+                "interpreter does not support external component `{}'",
+                src.name
+            ));
+        };
+        self.prims.push(prim);
+        Ok(())
+    }
+
+    /// Returns true if the guard is active in the current cycle
+    fn active(&self, g: &Guard) -> bool {
+        let fsm = &self.fsms[g.fsm];
+        (g.start..g.end).any(|st| {
+            if st == 0 {
+                self.values[fsm.go] == Some(1)
+            } else {
+                fsm.hist[st as usize]
+            }
+        })
+    }
+
+    /// Computes the value of every signal from the inputs and the current
+    /// state of the design.
+    pub fn settle(&mut self) -> Result<(), String> {
+        // Each iteration defines at least one more signal unless there is a
+        // combinational loop.
+        for _ in 0..=self.values.len() {
+            let mut changed = false;
+            for sig in 0..self.values.len() {
+                let v = match &self.drivers[sig] {
+                    None => continue,
+                    Some(Driver::Mux(srcs)) => srcs
+                        .iter()
+                        .find(|(g, _)| {
+                            g.as_ref().map_or(true, |g| self.active(g))
+                        })
+                        .map_or(Some(0), |(_, src)| self.values[*src]),
+                    Some(Driver::Trigger(guards)) => {
+                        Some(guards.iter().any(|g| self.active(g)) as u128)
+                    }
+                };
+                let v = v.map(|v| mask(v, self.widths[sig]));
+                changed |= self.values[sig] != v;
+                self.values[sig] = v;
+            }
+            for prim in &self.prims {
+                changed |= prim.eval(&mut self.values);
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+        Err("design contains a combinational loop".to_string())
+    }
+
+    /// Advances the design by one clock cycle.
+    pub fn tick(&mut self) {
+        for prim in &mut self.prims {
+            prim.tick(&self.values);
+        }
+        for fsm in &mut self.fsms {
+            if fsm.hist.len() > 1 {
+                fsm.hist.rotate_right(1);
+                fsm.hist[1] = self.values[fsm.go] == Some(1);
+            }
+        }
+    }
+}
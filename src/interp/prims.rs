@@ -0,0 +1,312 @@
+use super::netlist::{mask, Sig, Value};
+use std::collections::HashMap;
+
+/// External components with a Rust model
+#[derive(Clone, Copy)]
+enum Kind {
+    // Combinational primitives from `primitives/comb.fil`
+    Const,
+    Add,
+    Sub,
+    MultComb,
+    And,
+    Or,
+    Xor,
+    Not,
+    Eq,
+    Neq,
+    Gt,
+    Lt,
+    Lte,
+    Gte,
+    SignExtend,
+    ZeroExtend,
+    Extend,
+    Concat,
+    Select,
+    Slice,
+    ReduceAnd,
+    ReduceOr,
+    ShiftLeft,
+    ShiftRight,
+    ArithShiftRight,
+    Mux,
+    // Signed primitives from `primitives/signed.fil`
+    NegConst,
+    SAdd,
+    // Stateful primitives from `primitives/state.fil`
+    Register,
+    Delay,
+    Prev,
+    ContPrev,
+}
+
+impl Kind {
+    fn from_name(name: &str) -> Option<Self> {
+        let kind = match name {
+            "Const" => Kind::Const,
+            "Add" => Kind::Add,
+            "Sub" => Kind::Sub,
+            "MultComb" => Kind::MultComb,
+            "And" => Kind::And,
+            "Or" => Kind::Or,
+            "Xor" => Kind::Xor,
+            "Not" => Kind::Not,
+            "Eq" => Kind::Eq,
+            "Neq" => Kind::Neq,
+            "Gt" => Kind::Gt,
+            "Lt" => Kind::Lt,
+            "Lte" => Kind::Lte,
+            "Gte" => Kind::Gte,
+            "SignExtend" => Kind::SignExtend,
+            "ZeroExtend" => Kind::ZeroExtend,
+            "Extend" => Kind::Extend,
+            "Concat" => Kind::Concat,
+            "Select" => Kind::Select,
+            "Slice" => Kind::Slice,
+            "ReduceAnd" => Kind::ReduceAnd,
+            "ReduceOr" => Kind::ReduceOr,
+            "ShiftLeft" => Kind::ShiftLeft,
+            "ShiftRight" => Kind::ShiftRight,
+            "ArithShiftRight" => Kind::ArithShiftRight,
+            "Mux" => Kind::Mux,
+            "NegConst" => Kind::NegConst,
+            "SAdd" => Kind::SAdd,
+            "Register" => Kind::Register,
+            "Delay" => Kind::Delay,
+            "Prev" => Kind::Prev,
+            "ContPrev" => Kind::ContPrev,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// Sign extends a `width` bit value to 128 bits.
+fn sign_extend(v: u128, width: u64) -> i128 {
+    if width == 0 || width >= 128 {
+        return v as i128;
+    }
+    let shift = 128 - width;
+    ((v << shift) as i128) >> shift
+}
+
+/// Model of an instance of an external component. Follows the Verilog
+/// implementations in `primitives/`.
+pub struct Prim {
+    kind: Kind,
+    params: HashMap<String, u64>,
+    ports: HashMap<String, Sig>,
+    /// Value stored by stateful primitives
+    state: Value,
+}
+
+impl Prim {
+    /// Constructs a model for the external component `name`. Returns `None`
+    /// if the component does not have a model.
+    pub fn new(
+        name: &str,
+        params: HashMap<String, u64>,
+        ports: HashMap<String, Sig>,
+    ) -> Option<Self> {
+        let mut prim = Self {
+            kind: Kind::from_name(name)?,
+            params,
+            ports,
+            state: None,
+        };
+        prim.reset();
+        Some(prim)
+    }
+
+    fn param(&self, name: &str) -> u64 {
+        self.params[name]
+    }
+
+    fn get(&self, values: &[Value], port: &str) -> Value {
+        values[self.ports[port]]
+    }
+
+    /// Updates the value of the output port and returns true if it changed.
+    fn set(&self, values: &mut [Value], port: &str, v: Value) -> bool {
+        let sig = self.ports[port];
+        let changed = values[sig] != v;
+        values[sig] = v;
+        changed
+    }
+
+    /// Binary operation over the `left` and `right` ports
+    fn binop(&self, values: &[Value], f: impl Fn(u128, u128) -> u128) -> Value {
+        let l = self.get(values, "left")?;
+        let r = self.get(values, "right")?;
+        Some(f(l, r))
+    }
+
+    /// Unary operation over the `in` port
+    fn unop(&self, values: &[Value], f: impl Fn(u128) -> u128) -> Value {
+        self.get(values, "in").map(f)
+    }
+
+    /// Shift operation over the `in` and `shift` ports
+    fn shift(&self, values: &[Value], f: impl Fn(u128, u128) -> u128) -> Value {
+        let v = self.get(values, "in")?;
+        let s = self.get(values, "shift")?;
+        Some(f(v, s))
+    }
+
+    /// Resets the state of stateful primitives
+    pub fn reset(&mut self) {
+        self.state = match self.kind {
+            Kind::Prev | Kind::ContPrev if self.param("SAFE") == 0 => None,
+            _ => Some(0),
+        }
+    }
+
+    /// Computes the outputs of the primitive from its inputs and state.
+    /// Returns true if any output changed.
+    pub fn eval(&self, values: &mut [Value]) -> bool {
+        let w = |name| self.param(name);
+        let (port, v) = match self.kind {
+            Kind::Const => ("out", Some(mask(w("VALUE") as u128, w("WIDTH")))),
+            Kind::NegConst => (
+                "out",
+                Some(mask((w("VALUE") as u128).wrapping_neg(), w("WIDTH"))),
+            ),
+            Kind::Add => (
+                "out",
+                self.binop(values, |l, r| {
+                    mask(l.wrapping_add(r), w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::SAdd => (
+                "out",
+                self.binop(values, |l, r| mask(l.wrapping_add(r), w("WIDTH"))),
+            ),
+            Kind::Sub => (
+                "out",
+                self.binop(values, |l, r| {
+                    mask(l.wrapping_sub(r), w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::MultComb => (
+                "out",
+                self.binop(values, |l, r| {
+                    mask(l.wrapping_mul(r), w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::And => ("out", self.binop(values, |l, r| l & r)),
+            Kind::Or => ("out", self.binop(values, |l, r| l | r)),
+            Kind::Xor => ("out", self.binop(values, |l, r| l ^ r)),
+            Kind::Not => ("out", self.unop(values, |v| mask(!v, w("WIDTH")))),
+            Kind::Eq => ("out", self.binop(values, |l, r| (l == r) as u128)),
+            Kind::Neq => ("out", self.binop(values, |l, r| (l != r) as u128)),
+            Kind::Gt => ("out", self.binop(values, |l, r| (l > r) as u128)),
+            Kind::Lt => ("out", self.binop(values, |l, r| (l < r) as u128)),
+            Kind::Lte => ("out", self.binop(values, |l, r| (l <= r) as u128)),
+            Kind::Gte => ("out", self.binop(values, |l, r| (l >= r) as u128)),
+            Kind::SignExtend => (
+                "out",
+                self.unop(values, |v| {
+                    mask(sign_extend(v, w("IN_WIDTH")) as u128, w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::ZeroExtend => ("out", self.get(values, "in")),
+            Kind::Extend => (
+                "out",
+                self.unop(values, |v| {
+                    // Replicate the input till it fills the output
+                    let (iw, ow) = (w("IN_WIDTH"), w("OUT_WIDTH"));
+                    let mut out = 0u128;
+                    for _ in 0..(ow + iw - 1) / iw {
+                        out = out.checked_shl(iw as u32).unwrap_or(0) | v;
+                    }
+                    mask(out, ow)
+                }),
+            ),
+            Kind::Concat => (
+                "out",
+                self.binop(values, |l, r| {
+                    mask(
+                        l.checked_shl(w("RIGHT") as u32).unwrap_or(0) | r,
+                        w("OUT"),
+                    )
+                }),
+            ),
+            Kind::Select => (
+                "out",
+                self.unop(values, |v| {
+                    v.checked_shr(w("POS") as u32).unwrap_or(0) & 1
+                }),
+            ),
+            Kind::Slice => (
+                "out",
+                self.unop(values, |v| {
+                    mask(
+                        v.checked_shr(w("LSB") as u32).unwrap_or(0),
+                        w("OUT_WIDTH"),
+                    )
+                }),
+            ),
+            Kind::ReduceAnd => (
+                "out",
+                self.unop(values, |v| {
+                    (v == mask(u128::MAX, w("WIDTH"))) as u128
+                }),
+            ),
+            Kind::ReduceOr => ("out", self.unop(values, |v| (v != 0) as u128)),
+            Kind::ShiftLeft => (
+                "out",
+                self.shift(values, |v, s| {
+                    let v = u32::try_from(s)
+                        .ok()
+                        .and_then(|s| v.checked_shl(s))
+                        .unwrap_or(0);
+                    mask(v, w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::ShiftRight => (
+                "out",
+                self.shift(values, |v, s| {
+                    let v = u32::try_from(s)
+                        .ok()
+                        .and_then(|s| v.checked_shr(s))
+                        .unwrap_or(0);
+                    mask(v, w("OUT_WIDTH"))
+                }),
+            ),
+            Kind::ArithShiftRight => (
+                "out",
+                self.shift(values, |v, s| {
+                    let v = sign_extend(v, w("WIDTH")) >> s.min(127);
+                    mask(v as u128, w("WIDTH"))
+                }),
+            ),
+            Kind::Mux => {
+                let v = match self.get(values, "sel") {
+                    Some(1) => self.get(values, "in0"),
+                    Some(_) => self.get(values, "in1"),
+                    None => None,
+                };
+                ("out", v)
+            }
+            Kind::Register | Kind::Delay => ("out", self.state),
+            Kind::Prev | Kind::ContPrev => ("prev", self.state),
+        };
+        self.set(values, port, v)
+    }
+
+    /// Updates the state of the primitive at the end of a cycle
+    pub fn tick(&mut self, values: &[Value]) {
+        match self.kind {
+            Kind::Register | Kind::Prev => match self.get(values, "write_en") {
+                Some(0) => (),
+                Some(_) => self.state = self.get(values, "in"),
+                None => self.state = None,
+            },
+            Kind::Delay | Kind::ContPrev => {
+                self.state = self.get(values, "in");
+            }
+            _ => (),
+        }
+    }
+}
@@ -7,20 +7,20 @@ use fil_ir::{self as ir, AddCtx, Ctx, ExprIdx, PropIdx};
 pub struct Assume;
 
 impl Assume {
-    /// Adds the assumptions associated with a proposition of the form `#l = f(#r)` to the component.
+    /// Adds the assumptions associated with a proposition of the form `#l = f(#args)` to the component.
     fn add_assumptions(
         ctx: &mut ir::Component,
         f: ast::Fn,
         lhs: ExprIdx,
-        rhs: ExprIdx,
+        args: &[ExprIdx],
     ) -> Vec<PropIdx> {
         // Define constant expressions used
         let zero = ctx.add(ir::Expr::Concrete(0));
         let one = ctx.add(ir::Expr::Concrete(1));
         let two = ctx.add(ir::Expr::Concrete(2));
 
-        match f {
-            ast::Fn::Pow2 => vec![
+        match (f, args) {
+            (ast::Fn::Pow2, &[rhs]) => vec![
                 // #l * 2 = pow2(#r + 1)
                 lhs.mul(two, ctx).equal(rhs.add(one, ctx).pow2(ctx), ctx),
                 // #r >= 1 => #l = pow2(#r - 1)*2
@@ -33,7 +33,7 @@ impl Assume {
                 // #r = 0 => #l = 1
                 rhs.equal(zero, ctx).implies(lhs.equal(one, ctx), ctx),
             ],
-            ast::Fn::Log2 => vec![
+            (ast::Fn::Log2, &[rhs]) => vec![
                 // #l + 1 = log2(#r * 2)
                 lhs.add(one, ctx).equal(rhs.mul(two, ctx).log2(ctx), ctx),
                 // #l >= 1 => (#l - 1 = log2(#r / 2)) & ((#r / 2) * 2 = #r)
@@ -51,7 +51,33 @@ impl Assume {
                 // #r = 1 => #l = 0
                 rhs.equal(one, ctx).implies(lhs.equal(zero, ctx), ctx),
             ],
-            ast::Fn::SinB | ast::Fn::CosB => vec![], // can't make any assumptions on the output value here
+            (ast::Fn::Clog2, &[rhs]) => vec![
+                // #l >= 0
+                lhs.gte(zero, ctx),
+                // #r <= pow2(#l)
+                lhs.pow2(ctx).gte(rhs, ctx),
+                // #l >= 1 => #r > pow2(#l - 1)
+                lhs.gte(one, ctx)
+                    .implies(rhs.gt(lhs.sub(one, ctx).pow2(ctx), ctx), ctx),
+                // #l = 0 => #r <= 1
+                lhs.equal(zero, ctx).implies(one.gte(rhs, ctx), ctx),
+                // #r <= 1 => #l = 0
+                one.gte(rhs, ctx).implies(lhs.equal(zero, ctx), ctx),
+            ],
+            // the solver knows the definitions of these functions
+            (
+                ast::Fn::Min
+                | ast::Fn::Max
+                | ast::Fn::CeilDiv
+                | ast::Fn::AbsDiff
+                | ast::Fn::User(_),
+                _,
+            ) => vec![],
+            (ast::Fn::SinB | ast::Fn::CosB, _) => vec![], // can't make any assumptions on the output value here
+            _ => unreachable!(
+                "Function {f} did not expect {} arguments.",
+                args.len()
+            ),
         }
     }
 }
@@ -59,7 +85,6 @@ impl Assume {
 impl Assume {
     /// Checks a proposition for whether it matches the form `#l = f(#r)` for some custom function `f`. Additionally recurses on `&` chains.
     /// Generates the assumptions associated with each [ast::Fn] and returns a list of [ir::Prop]s for each.
-    /// TODO: Implement assumption generation for functions taking more than one argument.
     fn prop(p: ir::PropIdx, comp: &mut ir::Component) -> Vec<PropIdx> {
         let p = comp.get(p);
         match p {
@@ -69,31 +94,20 @@ impl Assume {
                 rhs,
             }) => {
                 // Matches over the cases `op(args) = rhs` and `lhs = op(args)` to
-                // define the `op`, `left`, and `right` for the equivalent equation `left = op(right)`
-                if let Some((op, lhs, rhs)) = match (
-                    comp.get(*lhs),
-                    comp.get(*rhs),
-                ) {
-                    (ir::Expr::Fn { op, args }, _) => {
-                        assert!(
-                            args.len() == 1,
-                            "Currently Unimplemented: {} requires {} arguments, automatic assumptions only implemented for single argument functions.",
-                            op, args.len()
-                        );
-                        Some((*op, *rhs, args[0]))
-                    }
-                    (_, ir::Expr::Fn { op, args }) => {
-                        assert!(
-                            args.len() == 1,
-                            "Currently Unimplemented: {} requires {} arguments, automatic assumptions only implemented for single argument functions.",
-                            op, args.len()
-                        );
-                        Some((*op, *lhs, args[0]))
+                // define the `op`, `left`, and `args` for the equivalent equation `left = op(args)`
+                if let Some((op, lhs, args)) =
+                    match (comp.get(*lhs), comp.get(*rhs)) {
+                        (ir::Expr::Fn { op, args }, _) => {
+                            Some((*op, *rhs, args.clone()))
+                        }
+                        (_, ir::Expr::Fn { op, args }) => {
+                            Some((*op, *lhs, args.clone()))
+                        }
+                        _ => None,
                     }
-                    _ => None,
-                } {
+                {
                     log::debug!("Generating default assumptions for {p}");
-                    Self::add_assumptions(comp, op, lhs, rhs)
+                    Self::add_assumptions(comp, op, lhs, &args)
                 } else {
                     vec![]
                 }
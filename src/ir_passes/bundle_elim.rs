@@ -2,6 +2,7 @@ use crate::{
     cmdline,
     ir_visitor::{Action, Construct, Visitor, VisitorData},
 };
+use fil_ast as ast;
 use fil_ir::{
     self as ir, Access, AddCtx, Bind, Command, Component, Connect, Context,
     Ctx, DenseIndexInfo, DisplayCtx, Expr, Foreign, Info, InvIdx, Invoke,
@@ -10,34 +11,79 @@ use fil_ir::{
 use itertools::Itertools;
 use std::collections::HashMap;
 
+/// The value driving a single element of a local port
+#[derive(Clone)]
+enum Driver {
+    /// An element of a bundle port
+    Port(PortIdx, usize),
+    /// A constant
+    Const(ast::Constant),
+}
+
 // Eliminates bundle ports by breaking them into multiple len-1 ports, and eliminates local ports altogether.
 pub struct BundleElim {
     context: DenseIndexInfo<Component, HashMap<PortIdx, Vec<PortIdx>>>,
-    local_map: HashMap<(PortIdx, usize), (PortIdx, usize)>,
+    local_map: HashMap<(PortIdx, usize), Driver>,
 }
 
 impl BundleElim {
-    /// Gets corresponding ports from the context given a component and a port access.
-    fn get(&self, access: &Access, data: &mut VisitorData) -> Vec<PortIdx> {
+    /// Gets the values driving each element of a port access, following local ports to their sources.
+    fn drivers(&self, access: &Access, comp: &Component) -> Vec<Driver> {
         let Access { port, start, end } = access;
-        let start = start.concrete(&data.comp) as usize;
-        let end = end.concrete(&data.comp) as usize;
+        let start = start.concrete(comp) as usize;
+        let end = end.concrete(comp) as usize;
 
-        let mut ports = Vec::with_capacity(end - start);
+        let mut drivers = Vec::with_capacity(end - start);
 
         for idx in start..end {
-            let mut group = (*port, idx);
+            let mut driver = Driver::Port(*port, idx);
             // loops until the non-local source of this port is found
-            let (port, idx) = loop {
-                match self.local_map.get(&group) {
-                    Some(&g) => group = g,
-                    None => break group,
+            while let Driver::Port(port, idx) = driver {
+                match self.local_map.get(&(port, idx)) {
+                    Some(d) => driver = d.clone(),
+                    None => break,
                 }
-            };
-            ports.push(self.context[data.idx][&port][idx]);
+            }
+            drivers.push(driver);
         }
 
-        ports
+        drivers
+    }
+
+    /// Gets corresponding ports from the context given a component and a port access.
+    fn get(&self, access: &Access, data: &VisitorData) -> Vec<PortIdx> {
+        self.drivers(access, &data.comp)
+            .into_iter()
+            .map(|d| match d {
+                Driver::Port(port, idx) => self.context[data.idx][&port][idx],
+                Driver::Const(c) => data.comp.internal_error(format!(
+                    "Access `{}` is driven by the constant `{c}`",
+                    data.comp.display(access)
+                )),
+            })
+            .collect()
+    }
+
+    /// Gets the sources for each element of the source of a connect.
+    fn sources(
+        &self,
+        src: &ir::Source,
+        data: &mut VisitorData,
+    ) -> Vec<ir::Source> {
+        let access = match src {
+            ir::Source::Port(access) => access,
+            ir::Source::Const(_) => return vec![src.clone()],
+        };
+        self.drivers(access, &data.comp)
+            .into_iter()
+            .map(|d| match d {
+                Driver::Port(port, idx) => {
+                    let port = self.context[data.idx][&port][idx];
+                    Access::port(port, &mut data.comp).into()
+                }
+                Driver::Const(c) => ir::Source::Const(c),
+            })
+            .collect()
     }
 
     /// Compiles a port by breaking it into multiple len-1 ports.
@@ -209,7 +255,7 @@ impl Visitor for BundleElim {
         }
 
         // get the list of ports associated with each access in the connect.
-        let src = self.get(src, data);
+        let src = self.sources(src, data);
         let dst = self.get(dst, data);
 
         if src.len() != dst.len() {
@@ -227,7 +273,7 @@ impl Visitor for BundleElim {
                 .zip(dst)
                 .map(|(src, dst)| {
                     Command::Connect(Connect {
-                        src: Access::port(src, &mut data.comp),
+                        src,
                         dst: Access::port(dst, &mut data.comp),
                         info: data.comp.add(Info::empty()),
                     })
@@ -270,6 +316,16 @@ impl Visitor for BundleElim {
                 {
                     let dst_start = dst.start.concrete(comp) as usize;
                     let dst_end = dst.end.concrete(comp) as usize;
+                    let dsts = (dst_start..dst_end).map(|d| (dst.port, d));
+                    let src = match src {
+                        ir::Source::Port(src) => src,
+                        ir::Source::Const(c) => {
+                            return Some(
+                                dsts.map(|d| (d, Driver::Const(c.clone())))
+                                    .collect_vec(),
+                            );
+                        }
+                    };
                     let src_start = src.start.concrete(comp) as usize;
                     let src_end = src.end.concrete(comp) as usize;
                     assert!(
@@ -279,9 +335,9 @@ impl Visitor for BundleElim {
                     );
 
                     Some(
-                        (dst_start..dst_end)
-                            .zip(src_start..src_end)
-                            .map(|(d, s)| ((dst.port, d), (src.port, s))),
+                        dsts.zip(src_start..src_end)
+                            .map(|(d, s)| (d, Driver::Port(src.port, s)))
+                            .collect_vec(),
                     )
                 } else {
                     None
@@ -0,0 +1,332 @@
+//! Difference-bound reasoning used to decide simple proof obligations without
+//! a solver.
+//!
+//! Comparisons in an obligation are normalized into linear sums. The
+//! assumptions of the form `x - y <= c`, `x <= c`, and `x >= c` are closed
+//! under transitivity and every way of falsifying the consequent is checked
+//! against the resulting bounds. The obligation is refuted only if every
+//! assumption was captured exactly, in which case the bounds also provide a
+//! witness.
+use fil_ir::linear::{Linear, Overflow, Var};
+use fil_ir::{self as ir, Ctx};
+use std::collections::HashMap;
+
+/// Maximum number of variables tracked for a single obligation
+const MAX_VARS: usize = 32;
+/// Maximum number of alternatives in the normal form of a proposition
+const MAX_ALTS: usize = 16;
+
+/// Result of deciding an obligation
+pub enum Decision {
+    Valid,
+    /// The obligation is falsified by the given values of the variables
+    Invalid(HashMap<Var, i128>),
+}
+
+/// A constraint `l >= 0` in terms of the tracked variables
+enum Bound {
+    /// Always true
+    Trivial,
+    /// Always false
+    Contradiction,
+    /// `x_to - x_from <= w`
+    Edge { from: usize, to: usize, w: i128 },
+}
+
+#[derive(Clone)]
+/// Difference-bound matrix over the variables of an obligation. The variable
+/// at index 0 is the constant zero and is used to bound single variables.
+struct Dbm {
+    vars: Vec<Var>,
+    idx: HashMap<Var, usize>,
+    /// `dist[i][j]` is the tightest known upper bound on `x_j - x_i`
+    dist: Vec<Vec<Option<i128>>>,
+}
+
+impl Default for Dbm {
+    fn default() -> Self {
+        Self {
+            vars: vec![],
+            idx: HashMap::new(),
+            dist: vec![vec![Some(0)]],
+        }
+    }
+}
+
+impl Dbm {
+    /// Index of the variable, adding it if it is not tracked yet
+    fn index(&mut self, v: Var) -> Option<usize> {
+        if let Some(&i) = self.idx.get(&v) {
+            return Some(i);
+        }
+        if self.vars.len() >= MAX_VARS {
+            return None;
+        }
+        self.vars.push(v);
+        let i = self.vars.len();
+        self.idx.insert(v, i);
+        self.dist.iter_mut().for_each(|row| row.push(None));
+        let mut row = vec![None; i + 1];
+        row[i] = Some(0);
+        self.dist.push(row);
+        Some(i)
+    }
+
+    /// Convert the constraint `l >= 0` into a bound. Returns `None` if it is
+    /// not a difference constraint.
+    fn bound(&mut self, l: &Linear) -> Option<Bound> {
+        let c = l.constant_term();
+        let coeffs = l.coeffs().collect::<Vec<_>>();
+        Some(match coeffs[..] {
+            [] if c >= 0 => Bound::Trivial,
+            [] => Bound::Contradiction,
+            // a*x + c >= 0 => 0 - x <= floor(c/a)
+            [(x, a)] if a > 0 => Bound::Edge {
+                from: self.index(x)?,
+                to: 0,
+                w: c.div_euclid(a),
+            },
+            // -a*x + c >= 0 => x - 0 <= floor(c/a)
+            [(x, a)] => Bound::Edge {
+                from: 0,
+                to: self.index(x)?,
+                w: c.div_euclid(-a),
+            },
+            // a*x - a*y + c >= 0 => y - x <= floor(c/a)
+            [(x, a), (y, b)] if a == -b => {
+                let (x, y, a) = if a > 0 { (x, y, a) } else { (y, x, b) };
+                Bound::Edge {
+                    from: self.index(x)?,
+                    to: self.index(y)?,
+                    w: c.div_euclid(a),
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Add the edge and tighten the bounds that go through it
+    fn add(&mut self, from: usize, to: usize, w: i128) {
+        let n = self.dist.len();
+        for i in 0..n {
+            let Some(a) = self.dist[i][from] else {
+                continue;
+            };
+            for j in 0..n {
+                let Some(b) = self.dist[to][j] else {
+                    continue;
+                };
+                let d = a.saturating_add(w).saturating_add(b);
+                if self.dist[i][j].map_or(true, |old| d < old) {
+                    self.dist[i][j] = Some(d);
+                }
+            }
+        }
+    }
+
+    /// Returns false if the constraints have no solution
+    fn consistent(&self) -> bool {
+        (0..self.dist.len()).all(|i| self.dist[i][i].map_or(true, |d| d >= 0))
+    }
+
+    /// Values of the variables satisfying the constraints. Each variable is
+    /// assigned the value closest to zero allowed by the earlier choices.
+    fn witness(mut self) -> HashMap<Var, i128> {
+        let mut out = HashMap::new();
+        for i in 1..self.dist.len() {
+            let lo = self.dist[i][0].map(|d| -d);
+            let hi = self.dist[0][i];
+            let v = match (lo, hi) {
+                (Some(lo), _) if lo > 0 => lo,
+                (_, Some(hi)) if hi < 0 => hi,
+                _ => 0,
+            };
+            self.add(0, i, v);
+            self.add(i, 0, -v);
+            out.insert(self.vars[i - 1], v);
+        }
+        out
+    }
+}
+
+/// Disjunctive normal form of the proposition, or of its negation if
+/// `positive` is false, where each alternative is a conjunction of constraints
+/// `l >= 0`. Returns `None` if the proposition mentions something other than
+/// comparisons or has too many alternatives.
+fn dnf(
+    prop: ir::PropIdx,
+    positive: bool,
+    comp: &ir::Component,
+) -> Option<Vec<Vec<Linear>>> {
+    let one = Linear::constant(1);
+    let out = match (comp.get(prop), positive) {
+        (ir::Prop::True, true) | (ir::Prop::False, false) => vec![vec![]],
+        (ir::Prop::True, false) | (ir::Prop::False, true) => vec![],
+        (ir::Prop::Not(p), _) => dnf(*p, !positive, comp)?,
+        (ir::Prop::And(l, r), true) | (ir::Prop::Or(l, r), false) => {
+            product(dnf(*l, positive, comp)?, dnf(*r, positive, comp)?)?
+        }
+        (ir::Prop::Or(l, r), true) | (ir::Prop::And(l, r), false) => {
+            let mut alts = dnf(*l, positive, comp)?;
+            alts.extend(dnf(*r, positive, comp)?);
+            alts
+        }
+        (ir::Prop::Implies(l, r), true) => {
+            let mut alts = dnf(*l, false, comp)?;
+            alts.extend(dnf(*r, true, comp)?);
+            alts
+        }
+        (ir::Prop::Implies(l, r), false) => {
+            product(dnf(*l, true, comp)?, dnf(*r, false, comp)?)?
+        }
+        _ => {
+            let (op, d) = prop.linear(comp)?;
+            let alts = move || -> Result<_, Overflow> {
+                Ok(match (op, positive) {
+                    (ir::Cmp::Gt, true) => vec![vec![d.sub(&one)?]],
+                    (ir::Cmp::Gte, true) => vec![vec![d]],
+                    (ir::Cmp::Eq, true) => vec![vec![d.scale(-1)?, d]],
+                    (ir::Cmp::Gt, false) => vec![vec![d.scale(-1)?]],
+                    (ir::Cmp::Gte, false) => {
+                        vec![vec![d.scale(-1)?.sub(&one)?]]
+                    }
+                    (ir::Cmp::Eq, false) => {
+                        vec![vec![d.sub(&one)?], vec![d.scale(-1)?.sub(&one)?]]
+                    }
+                })
+            };
+            alts().ok()?
+        }
+    };
+    (out.len() <= MAX_ALTS).then_some(out)
+}
+
+/// Alternatives of the conjunction of two propositions in DNF
+fn product(
+    l: Vec<Vec<Linear>>,
+    r: Vec<Vec<Linear>>,
+) -> Option<Vec<Vec<Linear>>> {
+    if l.len() * r.len() > MAX_ALTS {
+        return None;
+    }
+    Some(
+        l.iter()
+            .flat_map(|a| {
+                r.iter().map(move |b| [a.clone(), b.clone()].concat())
+            })
+            .collect(),
+    )
+}
+
+/// Conjuncts in the antecedents of the obligation along with its consequent
+fn split(
+    prop: ir::PropIdx,
+    comp: &ir::Component,
+    assumes: &mut Vec<ir::PropIdx>,
+) -> ir::PropIdx {
+    match comp.get(prop) {
+        ir::Prop::Implies(ante, cons) => {
+            conjuncts(*ante, comp, assumes);
+            split(*cons, comp, assumes)
+        }
+        _ => prop,
+    }
+}
+
+fn conjuncts(
+    prop: ir::PropIdx,
+    comp: &ir::Component,
+    acc: &mut Vec<ir::PropIdx>,
+) {
+    match comp.get(prop) {
+        ir::Prop::And(l, r) => {
+            conjuncts(*l, comp, acc);
+            conjuncts(*r, comp, acc);
+        }
+        _ => acc.push(prop),
+    }
+}
+
+/// Difference bounds implied by the assumptions along with whether every
+/// assumption was captured exactly. Returns `None` if the assumptions are
+/// contradictory.
+fn assume(
+    assumes: impl IntoIterator<Item = ir::PropIdx>,
+    comp: &ir::Component,
+) -> Option<(Dbm, bool)> {
+    // Assumptions that cannot be captured are dropped. This only weakens the
+    // obligation but a counterexample might then violate them.
+    let mut dbm = Dbm::default();
+    let mut exact = true;
+    for a in assumes {
+        let alt = match dnf(a, true, comp).as_deref() {
+            // The assumption is false
+            Some([]) => return None,
+            Some([alt]) => alt.to_vec(),
+            _ => {
+                exact = false;
+                continue;
+            }
+        };
+        for l in &alt {
+            exact &= !l.has_terms();
+            match dbm.bound(l) {
+                Some(Bound::Trivial) => (),
+                Some(Bound::Contradiction) => return None,
+                Some(Bound::Edge { from, to, w }) => dbm.add(from, to, w),
+                None => exact = false,
+            }
+        }
+    }
+    dbm.consistent().then_some((dbm, exact))
+}
+
+/// Decide whether the conjunction of the assumptions is satisfiable using
+/// difference bounds. Returns `None` if the assumptions need a solver.
+pub fn satisfiable(
+    assumes: &[ir::PropIdx],
+    comp: &ir::Component,
+) -> Option<bool> {
+    match assume(assumes.iter().copied(), comp) {
+        None => Some(false),
+        Some((_, true)) => Some(true),
+        Some((_, false)) => None,
+    }
+}
+
+/// Decide the validity of the obligation using difference bounds. Returns
+/// `None` if the obligation needs a solver.
+pub fn decide(prop: ir::PropIdx, comp: &ir::Component) -> Option<Decision> {
+    let mut assumes = vec![];
+    let cons = split(prop, comp, &mut assumes);
+    let Some((dbm, exact)) = assume(assumes, comp) else {
+        return Some(Decision::Valid);
+    };
+
+    // The obligation is valid if every way of falsifying the consequent
+    // contradicts the assumptions.
+    let mut undecided = false;
+    for alt in dnf(cons, false, comp)? {
+        let mut neg = dbm.clone();
+        let mut complete = exact;
+        for l in &alt {
+            complete &= !l.has_terms();
+            match neg.bound(l) {
+                Some(Bound::Trivial) => (),
+                Some(Bound::Contradiction) => {
+                    neg.dist[0][0] = Some(-1);
+                    break;
+                }
+                Some(Bound::Edge { from, to, w }) => neg.add(from, to, w),
+                None => complete = false,
+            }
+        }
+        if neg.consistent() {
+            if complete {
+                return Some(Decision::Invalid(neg.witness()));
+            }
+            undecided = true;
+        }
+    }
+    (!undecided).then_some(Decision::Valid)
+}
@@ -0,0 +1,218 @@
+//! On-disk cache for the results of [super::Discharge].
+//!
+//! A component is stored in the cache once all of its proof obligations have
+//! been proven. The entry is keyed on a canonical form of the propositions
+//! which does not depend on the names or the indices of the parameters,
+//! events, and expressions in the component. Later runs skip solving for
+//! components whose obligations are unchanged.
+use fil_ir::{self as ir, Ctx};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// Terms that occur in the proof obligations
+enum Term {
+    Param(ir::ParamIdx),
+    Event(ir::EventIdx),
+    Expr(ir::ExprIdx),
+    Time(ir::TimeIdx),
+    Prop(ir::PropIdx),
+}
+
+/// Builds the canonical form of the proof obligations of a component. Each
+/// term is defined on its own line and referred to by the order in which it
+/// was defined.
+struct Canonical<'a> {
+    comp: &'a ir::Component,
+    ids: HashMap<Term, usize>,
+    out: String,
+}
+
+impl<'a> Canonical<'a> {
+    fn new(comp: &'a ir::Component) -> Self {
+        let mut out = format!("filament {}\n", env!("CARGO_PKG_VERSION"));
+        // Calls are only meaningful along with the definitions of the functions
+        for def in comp.funcs.iter() {
+            writeln!(out, "{def}").unwrap();
+        }
+        Self {
+            comp,
+            ids: HashMap::new(),
+            out,
+        }
+    }
+
+    fn time_sub(&mut self, ts: &ir::TimeSub) -> String {
+        match ts {
+            ir::TimeSub::Unit(e) => format!("{}", self.term(Term::Expr(*e))),
+            ir::TimeSub::Sym { l, r } => format!(
+                "{} - {}",
+                self.term(Term::Time(*l)),
+                self.term(Term::Time(*r))
+            ),
+        }
+    }
+
+    /// Define the term and all its sub-terms and return its identifier
+    fn term(&mut self, term: Term) -> usize {
+        if let Some(&id) = self.ids.get(&term) {
+            return id;
+        }
+        let comp = self.comp;
+        let def = match term {
+            Term::Param(_) => "param".to_string(),
+            Term::Event(_) => "event".to_string(),
+            Term::Expr(e) => match comp.get(e) {
+                ir::Expr::Param(p) => {
+                    format!("expr {}", self.term(Term::Param(*p)))
+                }
+                ir::Expr::Concrete(n) => format!("{n}"),
+                ir::Expr::Bin { op, lhs, rhs } => format!(
+                    "{} {op} {}",
+                    self.term(Term::Expr(*lhs)),
+                    self.term(Term::Expr(*rhs))
+                ),
+                ir::Expr::Fn { op, args } => {
+                    let args = args
+                        .iter()
+                        .map(|a| self.term(Term::Expr(*a)).to_string())
+                        .collect::<Vec<_>>();
+                    format!("{op}({})", args.join(", "))
+                }
+            },
+            Term::Time(t) => {
+                let ir::Time { event, offset } = comp.get(t);
+                format!(
+                    "{} + {}",
+                    self.term(Term::Event(*event)),
+                    self.term(Term::Expr(*offset))
+                )
+            }
+            Term::Prop(p) => match comp.get(p) {
+                ir::Prop::True => "true".to_string(),
+                ir::Prop::False => "false".to_string(),
+                ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => format!(
+                    "{} {op} {}",
+                    self.term(Term::Expr(*lhs)),
+                    self.term(Term::Expr(*rhs))
+                ),
+                ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => format!(
+                    "{} {op} {}",
+                    self.term(Term::Time(*lhs)),
+                    self.term(Term::Time(*rhs))
+                ),
+                ir::Prop::TimeSubCmp(ir::CmpOp { op, lhs, rhs }) => {
+                    let l = self.time_sub(lhs);
+                    let r = self.time_sub(rhs);
+                    format!("({l}) {op} ({r})")
+                }
+                ir::Prop::Not(p) => format!("!{}", self.term(Term::Prop(*p))),
+                ir::Prop::And(l, r) => format!(
+                    "{} & {}",
+                    self.term(Term::Prop(*l)),
+                    self.term(Term::Prop(*r))
+                ),
+                ir::Prop::Or(l, r) => format!(
+                    "{} | {}",
+                    self.term(Term::Prop(*l)),
+                    self.term(Term::Prop(*r))
+                ),
+                ir::Prop::Implies(l, r) => format!(
+                    "{} => {}",
+                    self.term(Term::Prop(*l)),
+                    self.term(Term::Prop(*r))
+                ),
+            },
+        };
+        let id = self.ids.len();
+        self.ids.insert(term, id);
+        writeln!(self.out, "{id}: {def}").unwrap();
+        id
+    }
+}
+
+/// Key for the proof obligations of a component
+pub struct Key {
+    /// Name of the file storing the entry
+    file: String,
+    /// Canonical form of the obligations
+    canonical: String,
+}
+
+impl Key {
+    /// Compute the key for proving `facts` in `comp`
+    pub fn new(comp: &ir::Component, facts: &[ir::Fact]) -> Self {
+        let mut canon = Canonical::new(comp);
+        for fact in facts {
+            let id = canon.term(Term::Prop(fact.prop));
+            writeln!(canon.out, "assert {id}").unwrap();
+        }
+        let mut hasher = DefaultHasher::new();
+        canon.out.hash(&mut hasher);
+        Self {
+            file: format!("{:016x}", hasher.finish()),
+            canonical: canon.out,
+        }
+    }
+}
+
+/// Cache of components whose proof obligations have been proven
+pub struct Cache {
+    dir: PathBuf,
+    /// Number of components found in the cache
+    hits: u64,
+    /// Number of components that had to be solved
+    misses: u64,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(err) = fs::create_dir_all(&dir) {
+            log::warn!(
+                "Failed to create solver cache `{}': {err}",
+                dir.display()
+            );
+        }
+        Self {
+            dir,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns true if the obligations have already been proven. The entry
+    /// is compared against the key so that hash collisions are never
+    /// reported as hits.
+    pub fn lookup(&mut self, key: &Key) -> bool {
+        let hit = fs::read_to_string(self.dir.join(&key.file))
+            .map_or(false, |entry| entry == key.canonical);
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        hit
+    }
+
+    /// Record that the obligations have been proven
+    pub fn store(&self, key: &Key) {
+        if let Err(err) = fs::write(self.dir.join(&key.file), &key.canonical) {
+            log::warn!("Failed to write to solver cache: {err}");
+        }
+    }
+
+    /// Add the statistics of another cache for the same directory
+    pub fn merge(&mut self, other: Cache) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+
+    /// Report the cache statistics
+    pub fn report(&self) {
+        log::info!("Solver cache: {} hits, {} misses", self.hits, self.misses);
+    }
+}
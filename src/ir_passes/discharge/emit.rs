@@ -0,0 +1,94 @@
+//! SMT-LIB files with the proof obligations of [super::Discharge].
+//!
+//! Each component is written to its own file which declares all the terms
+//! used by the component and checks every obligation separately so that a
+//! single query can be replayed with any SMT-LIB solver.
+use codespan_reporting::diagnostic as cr;
+use codespan_reporting::files::Files;
+use easy_smt as smt;
+use fil_utils::GlobalPositionTable;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+/// A proof obligation to be written out
+pub struct Obligation {
+    /// The proposition that must be valid
+    pub prop: smt::SExpr,
+    /// The constraint in the source syntax
+    pub cons: String,
+    /// Why the obligation was generated
+    pub reason: String,
+    /// Source locations of the obligation
+    pub labels: Vec<cr::Label<usize>>,
+}
+
+/// Writes the proof obligations of components to a directory
+pub struct Emitter {
+    dir: PathBuf,
+}
+
+impl Emitter {
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(err) = fs::create_dir_all(&dir) {
+            log::warn!(
+                "Failed to create SMT output directory `{}': {err}",
+                dir.display()
+            );
+        }
+        Self { dir }
+    }
+
+    /// Write a file named `name.smt2` containing the declarations followed
+    /// by a separate `check-sat` for each obligation.
+    pub fn write(
+        &self,
+        name: &str,
+        header: &str,
+        sol: &smt::Context,
+        decls: impl IntoIterator<Item = smt::SExpr>,
+        obligations: &[Obligation],
+    ) {
+        let mut out = String::new();
+        writeln!(out, "; {header}").unwrap();
+        writeln!(out, "(set-logic ALL)").unwrap();
+        for decl in decls {
+            writeln!(out, "{}", sol.display(decl)).unwrap();
+        }
+
+        let files = GlobalPositionTable::as_ref().files();
+        for (idx, ob) in obligations.iter().enumerate() {
+            writeln!(out).unwrap();
+            writeln!(out, "; {}", ob.reason).unwrap();
+            writeln!(out, "; constraint: {}", ob.cons).unwrap();
+            for l in &ob.labels {
+                let pos = files.location(l.file_id, l.range.start).unwrap();
+                writeln!(
+                    out,
+                    "; {}:{}:{}: {}",
+                    files.name(l.file_id).unwrap(),
+                    pos.line_number,
+                    pos.column_number,
+                    l.message
+                )
+                .unwrap();
+            }
+            // The obligation is valid if its negation is unsatisfiable
+            let neg = sol.named(format!("obligation{idx}"), sol.not(ob.prop));
+            writeln!(out, "(push 1)").unwrap();
+            writeln!(
+                out,
+                "{}",
+                sol.display(sol.list(vec![sol.atoms().assert, neg]))
+            )
+            .unwrap();
+            writeln!(out, "(check-sat)").unwrap();
+            writeln!(out, "(pop 1)").unwrap();
+        }
+
+        let path = self.dir.join(format!("{name}.smt2"));
+        if let Err(err) = fs::write(&path, out) {
+            log::warn!("Failed to write `{}': {err}", path.display());
+        }
+    }
+}
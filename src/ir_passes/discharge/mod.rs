@@ -0,0 +1,1816 @@
+mod bounds;
+mod cache;
+mod emit;
+mod portfolio;
+mod presburger;
+mod timeline;
+mod verify_mono;
+
+use crate::cmdline;
+use crate::ir_visitor::{Construct, Visitor};
+use crate::log_time;
+use bounds::Decision;
+use cache::{Cache, Key};
+use codespan_reporting::diagnostic as cr;
+use codespan_reporting::diagnostic::Diagnostic;
+use easy_smt as smt;
+use emit::{Emitter, Obligation};
+use fil_ast as ast;
+use fil_ir::{self as ir, linear, Ctx, DisplayCtx};
+use itertools::Itertools;
+use portfolio::{Answer, Portfolio, Query};
+use presburger::Presburger;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use std::{fs, iter, panic, thread};
+pub use verify_mono::VerifyMono;
+
+/// Assignments to parameters using their names
+type Model = Vec<(String, String)>;
+
+#[derive(Default)]
+/// Values of the parameters and events in a counterexample
+pub struct Assign(Vec<(linear::Var, String)>);
+
+impl Assign {
+    /// Values of the parameters
+    fn params(&self) -> impl Iterator<Item = (ir::ParamIdx, &String)> {
+        self.0.iter().filter_map(|(k, v)| match k {
+            linear::Var::Param(p) => Some((*p, v)),
+            _ => None,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.params().next().is_none()
+    }
+
+    /// Integer values of the variables. SMT-LIB writes negative numbers as
+    /// `(- n)`.
+    fn values(&self) -> HashMap<linear::Var, i128> {
+        self.0
+            .iter()
+            .filter_map(|(k, v)| {
+                let n = match v.strip_prefix("(- ") {
+                    Some(n) => -n.trim_end_matches(')').parse::<i128>().ok()?,
+                    None => v.parse().ok()?,
+                };
+                Some((*k, n))
+            })
+            .collect()
+    }
+
+    /// The non-zero assignments with the parameters' names
+    fn named(&self, ctx: &ir::Component) -> Model {
+        self.params()
+            .filter_map(|(k, v)| {
+                // Attempt to parse value as a number
+                match v.parse::<i64>() {
+                    Ok(v) if v == 0 => None,
+                    _ => Some((ctx.display(k), v.clone())),
+                }
+            })
+            .collect()
+    }
+
+    fn display(&self, ctx: &ir::Component) -> String {
+        self.named(ctx)
+            .into_iter()
+            .map(|(k, v)| format!("{k} = {v}"))
+            .join(", ")
+    }
+}
+
+/// Result of checking the validity of a proposition
+enum Validity {
+    Valid,
+    /// The proposition is falsified by the assignment
+    Invalid(Assign),
+    /// The proposition is only falsified by assignments for which the
+    /// bit-vector arithmetic wraps around. Contains such an assignment.
+    Wraps(Assign),
+    /// The solver could not decide the proposition. Contains the reason.
+    Unknown(String),
+}
+
+/// Pass to discharge top-level `assert` statements in the IR and turn them into
+/// `assume` if they are true. Any assertions within the body are left as-is.
+/// Run [super::HoistFacts] before this pass to ensure that all facts are
+/// top-level.
+pub struct Discharge {
+    sol: smt::Context,
+    /// Which solver are we using
+    sol_base: cmdline::Solver,
+    /// In-process solver used when [cmdline::Solver::Builtin] is selected
+    builtin: Presburger,
+    /// Solvers raced when [cmdline::Solver::Portfolio] is selected. The first
+    /// solver to decide all the obligations of a component is used.
+    portfolio: Option<Portfolio>,
+    /// Defined functions
+    func_map: HashMap<ast::Fn, smt::SExpr>,
+    /// Commands declaring the functions
+    func_decls: Vec<smt::SExpr>,
+    /// Commands declaring the terms of the current component
+    decls: Vec<smt::SExpr>,
+    // Defined names
+    param_map: ir::DenseIndexInfo<ir::Param, smt::SExpr>,
+    ev_map: ir::DenseIndexInfo<ir::Event, smt::SExpr>,
+    // Composite expressions
+    expr_map: ir::DenseIndexInfo<ir::Expr, smt::SExpr>,
+    time_map: ir::DenseIndexInfo<ir::Time, smt::SExpr>,
+    // Propositions
+    prop_map: ir::DenseIndexInfo<ir::Prop, smt::SExpr>,
+    // Propositions that have already been checked
+    checked: HashMap<ir::PropIdx, Validity>,
+
+    // counter for activation literals generated
+    act_lit_count: u32,
+
+    /// Report the unsatisfied constraint and generate a model
+    show_models: bool,
+    /// Label a minimal set of assumptions that contradict a failing
+    /// constraint
+    explain: bool,
+    /// Report a minimal set of assumptions used to prove each constraint
+    explain_proofs: bool,
+    /// Report the undecided constraints as warnings
+    warn_unknown: bool,
+    /// Width of the bit-vectors used to encode the terms. Obligations that
+    /// only fail when the arithmetic wraps around are reported with the
+    /// wrapping counterexample.
+    bv_width: Option<u32>,
+    /// Time limit for checking the constraints of a component
+    comp_timeout: Option<Duration>,
+    /// Time at which checking the current component gives up
+    deadline: Option<Instant>,
+
+    to_prove: Vec<ir::Fact>,
+
+    // Diagnostics to be reported along with the counterexample for the
+    // failing constraint
+    diagnostics: Vec<(cr::Diagnostic<usize>, Model)>,
+    /// Number of distinct obligations checked
+    obligation_count: u64,
+    /// Number of obligations that could not be decided without the solver
+    solver_count: u64,
+
+    /// Results cached across runs
+    cache: Option<Cache>,
+    /// Key for the obligations of the current component
+    cache_key: Option<Key>,
+    /// Writes the obligations of each component to a self-contained SMT-LIB
+    /// file
+    emitter: Option<Emitter>,
+}
+
+impl Discharge {
+    fn new(opts: &cmdline::Opts) -> Self {
+        let mut out = Self {
+            sol: Self::conf_solver(opts),
+            sol_base: opts.solver,
+            builtin: Presburger::new(
+                Self::query_timeout(opts).map(Duration::from_millis),
+            ),
+            portfolio: matches!(opts.solver, cmdline::Solver::Portfolio)
+                .then(|| Self::conf_portfolio(opts)),
+            obligation_count: 0,
+            solver_count: 0,
+            act_lit_count: 0,
+            to_prove: vec![],
+            show_models: opts.show_models,
+            explain: opts.explain,
+            explain_proofs: opts.explain_proofs,
+            warn_unknown: opts.warn_unknown,
+            bv_width: Self::bv_width(opts),
+            comp_timeout: opts.component_timeout.map(Duration::from_millis),
+            deadline: None,
+            func_map: Default::default(),
+            func_decls: Default::default(),
+            decls: Default::default(),
+            param_map: Default::default(),
+            prop_map: Default::default(),
+            time_map: Default::default(),
+            ev_map: Default::default(),
+            expr_map: Default::default(),
+            checked: Default::default(),
+            diagnostics: Default::default(),
+            cache: opts.solver_cache.clone().map(Cache::new),
+            cache_key: None,
+            emitter: opts.emit_smt.clone().map(Emitter::new),
+        };
+
+        out.define_funcs();
+        if out.has_solver() {
+            out.sol.push().unwrap();
+        }
+        out
+    }
+
+    /// Time limit in milliseconds for each query. A single query may not
+    /// take longer than the limit for the whole component.
+    fn query_timeout(opts: &cmdline::Opts) -> Option<u64> {
+        match (opts.solver_timeout, opts.component_timeout) {
+            (Some(q), Some(c)) => Some(q.min(c)),
+            (q, c) => q.or(c),
+        }
+    }
+
+    /// Width of the bit-vector encoding if it is supported by the solver
+    fn bv_width(opts: &cmdline::Opts) -> Option<u32> {
+        let width = opts.bv_width?;
+        if !matches!(opts.solver, cmdline::Solver::Z3 | cmdline::Solver::CVC5) {
+            log::warn!(
+                "Ignoring --bv-width because the {} solver does not support bit-vectors",
+                opts.solver
+            );
+            return None;
+        }
+        Some(width)
+    }
+
+    /// Command line to start a solver process
+    fn solver_cmd(
+        solver: cmdline::Solver,
+        timeout: Option<u64>,
+    ) -> (&'static str, Vec<String>) {
+        match solver {
+            cmdline::Solver::Z3 => {
+                let mut s_opts = vec!["-smt2".to_string(), "-in".to_string()];
+                s_opts.extend(timeout.map(|t| format!("-t:{t}")));
+                ("z3", s_opts)
+            }
+            cmdline::Solver::CVC5 => {
+                let mut s_opts = vec![
+                    "--incremental".to_string(),
+                    "--force-logic=ALL".to_string(),
+                ];
+                s_opts.extend(timeout.map(|t| format!("--tlimit-per={t}")));
+                ("cvc5", s_opts)
+            }
+            cmdline::Solver::Builtin | cmdline::Solver::Portfolio => {
+                unreachable!("{solver} does not run a solver process")
+            }
+        }
+    }
+
+    /// Configure solver to use in this pass
+    fn conf_solver(opts: &cmdline::Opts) -> smt::Context {
+        match opts.solver {
+            cmdline::Solver::Z3 => log::debug!("Using z3 solver"),
+            cmdline::Solver::CVC5 => log::debug!("Using cvc5 solver"),
+            cmdline::Solver::Builtin | cmdline::Solver::Portfolio => {
+                log::debug!("Using {} solver", opts.solver);
+                // The context is only used to construct expressions
+                return smt::ContextBuilder::new().build().unwrap();
+            }
+        }
+        let (name, s_opts) =
+            Self::solver_cmd(opts.solver, Self::query_timeout(opts));
+        smt::ContextBuilder::new()
+            .replay_file(
+                opts.solver_replay_file
+                    .as_ref()
+                    .map(|s| fs::File::create(s).unwrap()),
+            )
+            .solver(name, s_opts)
+            .build()
+            .unwrap()
+    }
+
+    /// Configure the solvers raced in the portfolio
+    fn conf_portfolio(opts: &cmdline::Opts) -> Portfolio {
+        let timeout = Self::query_timeout(opts);
+        Portfolio::new(
+            [cmdline::Solver::Z3, cmdline::Solver::CVC5]
+                .into_iter()
+                .map(|solver| {
+                    let (name, s_opts) = Self::solver_cmd(solver, timeout);
+                    (solver, name, s_opts)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Construct for Discharge {
+    fn from(opts: &cmdline::Opts, _: &mut ir::Context) -> Self {
+        Self::new(opts)
+    }
+
+    fn clear_data(&mut self) {
+        self.param_map.clear();
+        self.prop_map.clear();
+        self.time_map.clear();
+        self.ev_map.clear();
+        self.expr_map.clear();
+        self.checked.clear();
+        self.diagnostics.clear();
+        self.act_lit_count = 0;
+        self.to_prove.clear();
+        self.decls.clear();
+        self.cache_key = None;
+        self.deadline = self.comp_timeout.map(|t| Instant::now() + t);
+
+        if let Some(portfolio) = &mut self.portfolio {
+            portfolio.reset();
+        }
+        if self.is_builtin() {
+            self.builtin = Presburger::new(self.builtin.timeout());
+        } else if self.has_solver() {
+            // Create a new solver context
+            self.sol.pop().unwrap();
+            self.sol.push().unwrap();
+        }
+    }
+}
+
+impl Discharge {
+    /// Are we using the in-process solver?
+    fn is_builtin(&self) -> bool {
+        matches!(self.sol_base, cmdline::Solver::Builtin)
+    }
+
+    /// Is the SMT context connected to a solver process?
+    fn has_solver(&self) -> bool {
+        !matches!(
+            self.sol_base,
+            cmdline::Solver::Builtin | cmdline::Solver::Portfolio
+        )
+    }
+
+    fn fmt_param(&self, param: ir::ParamIdx, ctx: &ir::Component) -> String {
+        match self.sol_base {
+            // CVC5 does not correctly print out quoted SExps
+            cmdline::Solver::CVC5
+            | cmdline::Solver::Builtin
+            | cmdline::Solver::Portfolio => {
+                format!("param{}", param.get())
+            }
+            cmdline::Solver::Z3 => {
+                format!("|{}@param{}|", ctx.display(param), param.get())
+            }
+        }
+    }
+
+    fn fmt_event(&self, event: ir::EventIdx, ctx: &ir::Component) -> String {
+        match self.sol_base {
+            cmdline::Solver::CVC5
+            | cmdline::Solver::Builtin
+            | cmdline::Solver::Portfolio => {
+                format!("event{}", event.get())
+            }
+            cmdline::Solver::Z3 => {
+                format!("|{}@event{}|", ctx.display(event), event.get())
+            }
+        }
+    }
+
+    fn fmt_expr(expr: ir::ExprIdx) -> String {
+        format!("e{}", expr.get())
+    }
+
+    fn fmt_prop(prop: ir::PropIdx) -> String {
+        format!("prop{}", prop.get())
+    }
+
+    fn fmt_time(time: ir::TimeIdx) -> String {
+        format!("t{}", time.get())
+    }
+
+    /// Sort of parameters, events, and times
+    fn num_sort(&self) -> smt::SExpr {
+        match self.bv_width {
+            Some(w) => self.sol.bit_vec_sort(self.sol.numeral(w)),
+            None => self.sol.int_sort(),
+        }
+    }
+
+    /// Convert a bit-vector value in a model to a decimal number
+    fn bv_value(val: String) -> String {
+        let num = if let Some(bits) = val.strip_prefix("#b") {
+            u128::from_str_radix(bits, 2).ok()
+        } else if let Some(hex) = val.strip_prefix("#x") {
+            u128::from_str_radix(hex, 16).ok()
+        } else {
+            // Values of the form `(_ bvN W)`
+            val.strip_prefix("(_ bv")
+                .and_then(|v| v.split_whitespace().next())
+                .and_then(|n| n.parse().ok())
+        };
+        num.map_or(val, |n| n.to_string())
+    }
+
+    fn new_act_lit(&mut self) -> smt::SExpr {
+        self.act_lit_count += 1;
+        self.sol
+            .declare_const(
+                format!("act_lit{}", self.act_lit_count),
+                self.sol.bool_sort(),
+            )
+            .unwrap()
+    }
+
+    /// Declare a function. Without a solver process, the function is only
+    /// named.
+    fn declare_fun(
+        &mut self,
+        name: String,
+        args: Vec<smt::SExpr>,
+        out: smt::SExpr,
+    ) -> smt::SExpr {
+        let atom = self.sol.atom(&name);
+        self.func_decls.push(self.sol.list(vec![
+            self.sol.atoms().declare_fun,
+            atom,
+            self.sol.list(args.clone()),
+            out,
+        ]));
+        if !self.has_solver() {
+            atom
+        } else {
+            self.sol.declare_fun(name, args, out).unwrap()
+        }
+    }
+
+    /// Define a function with a body. Without a solver process, the function
+    /// is only named. Scoped functions are only defined for the current
+    /// component.
+    fn define_fun(
+        &mut self,
+        name: &str,
+        args: Vec<(&str, smt::SExpr)>,
+        out: smt::SExpr,
+        body: smt::SExpr,
+        scoped: bool,
+    ) -> smt::SExpr {
+        let atom = self.sol.atom(name);
+        let params = args
+            .iter()
+            .map(|(n, s)| self.sol.list(vec![self.sol.atom(*n), *s]))
+            .collect_vec();
+        let cmd = self.sol.list(vec![
+            self.sol.atoms().define_fun,
+            atom,
+            self.sol.list(params),
+            out,
+            body,
+        ]);
+        if scoped {
+            self.decls.push(cmd);
+        } else {
+            self.func_decls.push(cmd);
+        }
+        if !self.has_solver() {
+            atom
+        } else {
+            self.sol.define_fun(name, args, out, body).unwrap()
+        }
+    }
+
+    /// Declare a constant or define it to be `body`. The command is recorded
+    /// when emitting the obligations or racing the portfolio and without a
+    /// solver process, the term is only named.
+    fn declare_term(
+        &mut self,
+        name: String,
+        sort: smt::SExpr,
+        body: Option<smt::SExpr>,
+    ) -> smt::SExpr {
+        let atom = self.sol.atom(&name);
+        if self.emitter.is_some() || self.portfolio.is_some() {
+            let atoms = self.sol.atoms();
+            let cmd = match body {
+                None => {
+                    vec![atoms.declare_fun, atom, self.sol.list(vec![]), sort]
+                }
+                Some(b) => {
+                    vec![atoms.define_fun, atom, self.sol.list(vec![]), sort, b]
+                }
+            };
+            self.decls.push(self.sol.list(cmd));
+        }
+        if !self.has_solver() {
+            return atom;
+        }
+        match body {
+            None => self.sol.declare_fun(name, vec![], sort).unwrap(),
+            Some(b) => self.sol.define_const(name, sort, b).unwrap(),
+        }
+    }
+
+    /// Defines primitive functions used in the encoding like `pow` and `log`
+    fn define_funcs(&mut self) {
+        let is = self.num_sort();
+
+        macro_rules! sol_fn(
+            ($name:tt($($args:ident),*) -> $out:ident) => {
+                let f = self.declare_fun(
+                    stringify!($name).to_lowercase(),
+                    vec![$($args),*],
+                    $out,
+                );
+                self.func_map.insert(ast::Fn::$name, f);
+            }
+        );
+
+        self.func_map = Default::default();
+        self.func_decls = Default::default();
+
+        sol_fn!(Pow2(is) -> is);
+        sol_fn!(Log2(is) -> is);
+        sol_fn!(SinB(is, is) -> is);
+        sol_fn!(CosB(is, is) -> is);
+        sol_fn!(Clog2(is) -> is);
+
+        // Functions with a closed form are defined so that the solver can
+        // reason about them directly
+        let (a, b) = (self.sol.atom("a"), self.sol.atom("b"));
+        let bv = self.bv_width.is_some();
+        let one = if bv {
+            self.bv_num(1)
+        } else {
+            self.sol.numeral(1)
+        };
+        let (lte, sub) = if bv {
+            (self.sol.bvule(a, b), self.sol.bvsub(a, b))
+        } else {
+            (self.sol.lte(a, b), self.sol.sub(a, b))
+        };
+        let rev_sub = if bv {
+            self.sol.bvsub(b, a)
+        } else {
+            self.sol.sub(b, a)
+        };
+        let ceil_div = if bv {
+            let n = self.sol.bvadd(a, self.sol.bvsub(b, one));
+            self.sol.bvudiv(n, b)
+        } else {
+            self.sol.div(self.sol.plus(a, self.sol.sub(b, one)), b)
+        };
+        let defs = [
+            (ast::Fn::Min, self.sol.ite(lte, a, b)),
+            (ast::Fn::Max, self.sol.ite(lte, b, a)),
+            (ast::Fn::CeilDiv, ceil_div),
+            (ast::Fn::AbsDiff, self.sol.ite(lte, rev_sub, sub)),
+        ];
+        for (f, body) in defs {
+            let name = f.to_string();
+            let def = self.define_fun(
+                &name,
+                vec![("a", is), ("b", is)],
+                is,
+                body,
+                false,
+            );
+            self.func_map.insert(f, def);
+        }
+    }
+
+    /// Define the functions of the program using their bodies so that the
+    /// solver can see through calls to them
+    fn define_user_funcs(&mut self, comp: &ir::Component) {
+        let is = self.num_sort();
+        for def in comp.funcs.iter() {
+            let name = format!("fn_{}", def.name);
+            let params =
+                def.params.iter().map(|p| format!("p_{p}")).collect_vec();
+            let args = params.iter().map(|p| (p.as_str(), is)).collect_vec();
+            let body = self.fn_body_to_sexp(def.body.inner());
+            let f = self.define_fun(&name, args, is, body, true);
+            self.func_map.insert(ast::Fn::User(*def.name.inner()), f);
+        }
+    }
+
+    /// Encode the body of a function defined in the program
+    fn fn_body_to_sexp(&mut self, expr: &ast::Expr) -> smt::SExpr {
+        match expr {
+            ast::Expr::Concrete(n) if self.bv_width.is_some() => {
+                self.bv_num(*n)
+            }
+            ast::Expr::Concrete(n) => self.sol.numeral(*n),
+            ast::Expr::Abstract(p) => self.sol.atom(format!("p_{p}")),
+            ast::Expr::ParamAccess { .. } => {
+                unreachable!("functions cannot refer to instance parameters")
+            }
+            ast::Expr::Op { op, left, right } => {
+                let l = self.fn_body_to_sexp(left);
+                let r = self.fn_body_to_sexp(right);
+                self.bin_to_sexp(*op, l, r)
+            }
+            ast::Expr::App { func, args } => {
+                let args = args.iter().map(|a| self.fn_body_to_sexp(a));
+                let args = args.collect_vec();
+                self.sol.list(
+                    iter::once(self.func_map[func.inner()])
+                        .chain(args)
+                        .collect_vec(),
+                )
+            }
+        }
+    }
+
+    /// Term of the solver encoding the variable
+    fn var_sexp(&self, var: linear::Var) -> smt::SExpr {
+        match var {
+            linear::Var::Param(p) => self.param_map[p],
+            linear::Var::Event(e) => self.ev_map[e],
+            linear::Var::Term(e) => self.expr_map[e],
+        }
+    }
+
+    /// Parameters and events of the consequent whose values are reported
+    /// with a counterexample
+    fn model_vars(prop: ir::PropIdx, ctx: &ir::Component) -> Vec<linear::Var> {
+        let cons = prop.consequent(ctx);
+        ctx.prop_params(cons)
+            .into_iter()
+            .map(linear::Var::Param)
+            .chain(ctx.prop_events(cons).into_iter().map(linear::Var::Event))
+            .unique()
+            .collect()
+    }
+
+    /// Get bindings for the provided variables in a model.
+    fn get_assignments(&mut self, relevant_vars: Vec<linear::Var>) -> Assign {
+        if relevant_vars.is_empty() {
+            return Assign(vec![]);
+        }
+        let mut rev_map = HashMap::with_capacity(relevant_vars.len());
+        // SExprs corresponding to the paramters
+        let sexps = relevant_vars
+            .iter()
+            .unique()
+            .map(|p| {
+                let s = self.var_sexp(*p);
+                log::debug!("{} -> {:?}", self.sol.display(s), p);
+                rev_map.insert(s, *p);
+                s
+            })
+            .collect_vec();
+        let num_vars = sexps.len();
+
+        let model = self.sol.get_value(sexps).unwrap();
+        assert!(model.len() == num_vars,
+            "{num_vars} relevant variables but the model contains assignments for {} variables",
+            model.len()
+        );
+
+        Assign(
+            model
+                .into_iter()
+                .flat_map(|(p, v)| {
+                    let Some(&p) = rev_map.get(&p) else {
+                        unreachable!(
+                            "missing binding for sexp {}",
+                            self.sol.display(p)
+                        );
+                    };
+                    let v = self.sol.display(v).to_string();
+                    if self.bv_width.is_some() {
+                        Some((p, Self::bv_value(v)))
+                    } else {
+                        Some((p, v))
+                    }
+                })
+                .collect_vec(),
+        )
+    }
+
+    /// Check the validity of the proposition using the SMT solver.
+    fn smt_valid(
+        &mut self,
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> Validity {
+        let actlit = self.new_act_lit();
+        let sexp = self.prop_map[prop];
+        let imp = self.sol.imp(actlit, self.sol.not(sexp));
+        self.sol.assert(imp).unwrap();
+        // Disable the activation literal
+        let res = log_time!(
+            self.sol.check_assuming([actlit]).unwrap(),
+            ctx.display(prop.consequent(ctx));
+            100
+        );
+        let out = match res {
+            smt::Response::Sat if self.bv_width.is_some() => {
+                self.bv_counterexample(actlit, prop, ctx)
+            }
+            smt::Response::Sat => Validity::Invalid(self.model(prop, ctx)),
+            smt::Response::Unsat => Validity::Valid,
+            smt::Response::Unknown => {
+                let why = self.reason_unknown();
+                Validity::Unknown(format!(
+                    "{} returned unknown: {why}",
+                    self.sol_base
+                ))
+            }
+        };
+        // Deassert the actlit after the `get-model` call.
+        self.sol.assert(self.sol.not(actlit)).unwrap();
+        out
+    }
+
+    /// Assignment to the variables of the consequent in the current model
+    fn model(&mut self, prop: ir::PropIdx, ctx: &ir::Component) -> Assign {
+        if self.show_models {
+            self.get_assignments(Self::model_vars(prop, ctx))
+        } else {
+            Assign::default()
+        }
+    }
+
+    /// Check whether the negation of the proposition, enabled by `actlit`,
+    /// is still satisfiable when the arithmetic in the proposition may not
+    /// overflow. If it is not, the counterexample relies on wrap-around.
+    fn bv_counterexample(
+        &mut self,
+        actlit: smt::SExpr,
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> Validity {
+        let wrapping = self.model(prop, ctx);
+        let no_wrap = self.new_act_lit();
+        let conds = self.no_overflow(prop, ctx);
+        let conds =
+            self.sol.and_many(iter::once(self.sol.true_()).chain(conds));
+        self.sol.assert(self.sol.imp(no_wrap, conds)).unwrap();
+        let out = match self.sol.check_assuming([actlit, no_wrap]).unwrap() {
+            smt::Response::Unsat => Validity::Wraps(wrapping),
+            smt::Response::Sat => Validity::Invalid(self.model(prop, ctx)),
+            smt::Response::Unknown => Validity::Invalid(wrapping),
+        };
+        self.sol.assert(self.sol.not(no_wrap)).unwrap();
+        out
+    }
+
+    /// Conditions under which the bit-vector arithmetic in the proposition
+    /// computes the same values as unbounded integers.
+    fn no_overflow(
+        &self,
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> Vec<smt::SExpr> {
+        let width = self.bv_width.unwrap();
+        let sol = &self.sol;
+        let mut conds = vec![];
+        let mut exprs = vec![];
+        let mut props = vec![prop];
+        let mut times = vec![];
+        let mut seen = HashSet::new();
+        while let Some(p) = props.pop() {
+            match ctx.get(p) {
+                ir::Prop::True | ir::Prop::False => (),
+                ir::Prop::Cmp(c) => exprs.extend([c.lhs, c.rhs]),
+                ir::Prop::TimeCmp(c) => times.extend([c.lhs, c.rhs]),
+                ir::Prop::TimeSubCmp(c) => {
+                    for ts in [&c.lhs, &c.rhs] {
+                        match ts {
+                            ir::TimeSub::Unit(e) => exprs.push(*e),
+                            ir::TimeSub::Sym { l, r } => {
+                                // The difference of times may not be negative
+                                conds.push(sol.bvuge(
+                                    self.time_map[*l],
+                                    self.time_map[*r],
+                                ));
+                                times.extend([*l, *r]);
+                            }
+                        }
+                    }
+                }
+                ir::Prop::Not(p) => props.push(*p),
+                ir::Prop::And(l, r)
+                | ir::Prop::Or(l, r)
+                | ir::Prop::Implies(l, r) => props.extend([*l, *r]),
+            }
+        }
+        for t in times {
+            let ir::Time { event, offset } = ctx.get(t);
+            conds.push(sol.bvuge(self.time_map[t], self.ev_map[*event]));
+            exprs.push(*offset);
+        }
+        while let Some(e) = exprs.pop() {
+            if !seen.insert(e) {
+                continue;
+            }
+            let out = self.expr_map[e];
+            match ctx.get(e) {
+                ir::Expr::Param(_) => (),
+                ir::Expr::Concrete(n) => {
+                    if width < 64 && *n >= 1 << width {
+                        conds.push(sol.false_());
+                    }
+                }
+                ir::Expr::Bin { op, lhs, rhs } => {
+                    let l = self.expr_map[*lhs];
+                    let r = self.expr_map[*rhs];
+                    match op {
+                        ast::Op::Add => conds.push(sol.bvuge(out, l)),
+                        ast::Op::Sub => conds.push(sol.bvuge(l, r)),
+                        ast::Op::Mul => {
+                            let zero = sol.eq(r, self.bv_num(0));
+                            let undo = sol.eq(sol.bvudiv(out, r), l);
+                            conds.push(sol.or(zero, undo));
+                        }
+                        ast::Op::Div | ast::Op::Mod => (),
+                    }
+                    exprs.extend([*lhs, *rhs]);
+                }
+                ir::Expr::Fn { args, .. } => exprs.extend(args),
+            }
+        }
+        conds
+    }
+
+    /// A bit-vector literal. Values that do not fit in the width wrap around.
+    fn bv_num(&self, n: u64) -> smt::SExpr {
+        self.sol.list(vec![
+            self.sol.atom("_"),
+            self.sol.atom(format!("bv{n}")),
+            self.sol.numeral(self.bv_width.unwrap()),
+        ])
+    }
+
+    /// Ask the solver why the last query returned unknown
+    fn reason_unknown(&mut self) -> String {
+        let cmd = self.sol.list(vec![
+            self.sol.atom("get-info"),
+            self.sol.atom(":reason-unknown"),
+        ]);
+        let Ok(resp) = self.sol.raw_send(cmd).and_then(|_| self.sol.raw_recv())
+        else {
+            return "no reason given".to_string();
+        };
+        // The response has the form `(:reason-unknown <reason>)`
+        let why = match self.sol.get(resp) {
+            smt::SExprData::List(&[_, why]) => why,
+            _ => resp,
+        };
+        self.sol
+            .display(why)
+            .to_string()
+            .trim_matches('"')
+            .to_string()
+    }
+
+    /// Log how many of the obligations reached the solver
+    fn report(obligations: u64, solver: u64) {
+        log::info!(
+            "{} of {obligations} obligations decided without the solver, {solver} reached the solver",
+            obligations - solver
+        );
+    }
+
+    /// Returns true if the time limit for the current component is exceeded
+    fn timed_out(&self) -> bool {
+        self.deadline.map_or(false, |d| Instant::now() > d)
+    }
+
+    /// Reason reported for the constraints that were not checked in time
+    fn timeout_reason(&self) -> String {
+        format!(
+            "checking the component took longer than {}ms",
+            self.comp_timeout.unwrap().as_millis()
+        )
+    }
+
+    /// Race the solvers of the portfolio on the satisfiability of each
+    /// assertion. The values of the given parameters are reported for the
+    /// satisfiable assertions.
+    fn race(
+        &mut self,
+        asserts: Vec<(smt::SExpr, Vec<linear::Var>)>,
+        ctx: &ir::Component,
+    ) -> Vec<Validity> {
+        let mut names = HashMap::new();
+        let queries = asserts
+            .iter()
+            .map(|(assert, params)| Query {
+                assert: self.sol.display(*assert).to_string(),
+                values: params
+                    .iter()
+                    .unique()
+                    .map(|p| {
+                        let name = self.sol.display(self.var_sexp(*p));
+                        names.insert(name.to_string(), *p);
+                        name.to_string()
+                    })
+                    .collect(),
+            })
+            .collect_vec();
+        let decls = self
+            .func_decls
+            .iter()
+            .chain(&self.decls)
+            .map(|d| self.sol.display(*d).to_string())
+            .collect_vec();
+        let comp = match &ctx.src_info {
+            Some(info) => format!("`{}'", info.name),
+            None => "a generated component".to_string(),
+        };
+
+        let portfolio = self.portfolio.as_mut().unwrap();
+        match portfolio.race(&comp, &decls, &queries, self.deadline) {
+            Ok((solver, answers)) => answers
+                .into_iter()
+                .map(|ans| match ans {
+                    Answer::Unsat => Validity::Valid,
+                    Answer::Sat(vals) => Validity::Invalid(Assign(
+                        vals.into_iter()
+                            .filter_map(|(k, v)| Some((*names.get(&k)?, v)))
+                            .collect(),
+                    )),
+                    Answer::Unknown(why) => Validity::Unknown(format!(
+                        "{solver} returned unknown: {why}"
+                    )),
+                })
+                .collect(),
+            Err(why) => {
+                let why = if self.timed_out() {
+                    self.timeout_reason()
+                } else {
+                    format!("portfolio gave up: {why}")
+                };
+                asserts
+                    .iter()
+                    .map(|_| Validity::Unknown(why.clone()))
+                    .collect()
+            }
+        }
+    }
+
+    /// Check the validity of the propositions by racing the portfolio
+    fn portfolio_valid(
+        &mut self,
+        props: &[ir::PropIdx],
+        ctx: &ir::Component,
+    ) -> Vec<Validity> {
+        let asserts = props
+            .iter()
+            .map(|p| {
+                let vars = if self.show_models {
+                    Self::model_vars(*p, ctx)
+                } else {
+                    vec![]
+                };
+                (self.sol.not(self.prop_map[*p]), vars)
+            })
+            .collect();
+        self.race(asserts, ctx)
+    }
+
+    /// Check the validity of the proposition using the in-process solver.
+    fn builtin_valid(
+        &mut self,
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> Validity {
+        let res = log_time!(
+            self.builtin.check(prop, ctx),
+            ctx.display(prop.consequent(ctx));
+            100
+        );
+        match res {
+            Ok(None) => Validity::Valid,
+            Ok(Some(model)) if self.show_models => Validity::Invalid(Assign(
+                Self::model_vars(prop, ctx)
+                    .into_iter()
+                    .map(|v| (v, model.value(v).to_string()))
+                    .collect(),
+            )),
+            Ok(Some(_)) => Validity::Invalid(Assign::default()),
+            Err(why) => {
+                Validity::Unknown(format!("builtin solver gave up: {why}"))
+            }
+        }
+    }
+
+    /// Check whether the proposition is valid and report a diagnostic if it
+    /// is not or if the solver cannot decide it.
+    fn check_valid(&mut self, fact: ir::Fact, ctx: &ir::Component) {
+        let prop = fact.prop;
+        #[allow(clippy::map_entry)]
+        if !self.checked.contains_key(&prop) {
+            let out = if self.timed_out() {
+                Validity::Unknown(self.timeout_reason())
+            } else if self.is_builtin() {
+                self.builtin_valid(prop, ctx)
+            } else if self.portfolio.is_some() {
+                self.portfolio_valid(&[prop], ctx).pop().unwrap()
+            } else {
+                self.smt_valid(prop, ctx)
+            };
+            self.checked.insert(prop, out);
+        }
+        if self.explain_proofs && matches!(self.checked[&prop], Validity::Valid)
+        {
+            if let Some(diag) = self.explain_proof(&fact, ctx) {
+                self.diagnostics.push((diag, vec![]));
+            }
+            return;
+        }
+        let reason = ctx
+            .get(fact.reason)
+            .as_assert()
+            .map(|ir::info::Assert(reason)| reason);
+        let cons = ctx.display(fact.prop.consequent(ctx));
+        let mut model = vec![];
+        let diag = match (&self.checked[&prop], reason) {
+            (Validity::Valid, _) => return,
+            (Validity::Invalid(_) | Validity::Wraps(_), None) => {
+                // No information was given on who generated this error
+                Diagnostic::error().with_notes(vec![
+                    format!("Cannot prove constraint: {cons}"),
+                    "No information was given on who generated this error"
+                        .to_string(),
+                ])
+            }
+            (
+                Validity::Invalid(assign) | Validity::Wraps(assign),
+                Some(reason),
+            ) => {
+                let mut diag = reason.diag(ctx);
+                if self.show_models {
+                    diag = reason.diag(ctx).with_notes(vec![format!(
+                        "Cannot prove constraint: {cons}"
+                    )]);
+                    if !assign.is_empty() {
+                        diag = diag.with_notes(vec![format!(
+                            "Counterexample: {} (unmentioned parameters are 0)",
+                            assign.display(ctx)
+                        )]);
+                        model = assign.named(ctx);
+                    }
+                    // Times are evaluated with unbounded integers which do
+                    // not match a wrapping counterexample.
+                    let wraps =
+                        matches!(self.checked[&prop], Validity::Wraps(_));
+                    if let Some(t) =
+                        timeline::render(reason, &assign.values(), ctx)
+                            .filter(|_| !wraps)
+                    {
+                        diag = diag.with_notes(vec![t]);
+                    }
+                }
+                diag
+            }
+            (Validity::Unknown(why), reason) => {
+                let notes = vec![
+                    format!("Cannot decide constraint: {cons}"),
+                    why.clone(),
+                ];
+                let mut diag = match reason {
+                    Some(reason) => {
+                        let diag = reason.diag(ctx);
+                        let msg = format!(
+                            "could not decide constraint: {}",
+                            diag.message
+                        );
+                        diag.with_message(msg).with_notes(notes)
+                    }
+                    None => Diagnostic::error().with_notes(notes),
+                };
+                if self.warn_unknown {
+                    diag.severity = cr::Severity::Warning;
+                }
+                diag
+            }
+        };
+        let diag = if matches!(self.checked[&prop], Validity::Wraps(_)) {
+            diag.with_notes(vec![format!(
+                "Counterexample relies on wrap-around: the constraint holds when {}-bit arithmetic does not overflow",
+                self.bv_width.unwrap()
+            )])
+        } else {
+            diag
+        };
+        // Undecided constraints are not explained since the queries would
+        // also time out.
+        let diag = if self.explain
+            && !matches!(self.checked[&prop], Validity::Unknown(_))
+        {
+            self.explain_failure(diag, prop, ctx)
+        } else {
+            diag
+        };
+        self.diagnostics.push((diag, model));
+    }
+
+    /// Split an obligation into the assumptions in its antecedent and its
+    /// consequent.
+    fn split(
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> (Vec<ir::PropIdx>, ir::PropIdx) {
+        match ctx.get(prop) {
+            ir::Prop::Implies(ante, cons) => {
+                let (mut assumes, cons) = Self::split(*cons, ctx);
+                assumes.splice(0..0, Self::conjuncts(*ante, ctx));
+                (assumes.into_iter().unique().collect(), cons)
+            }
+            _ => (vec![], prop),
+        }
+    }
+
+    /// Flatten a proposition into its conjuncts
+    fn conjuncts(prop: ir::PropIdx, ctx: &ir::Component) -> Vec<ir::PropIdx> {
+        let mut out = vec![];
+        let mut stack = vec![prop];
+        while let Some(p) = stack.pop() {
+            match ctx.get(p) {
+                ir::Prop::And(l, r) => stack.extend([*r, *l]),
+                ir::Prop::True => (),
+                _ => out.push(p),
+            }
+        }
+        out.into_iter().unique().collect()
+    }
+
+    /// Location of the primary label generated by the reason
+    fn reason_label(
+        info: ir::InfoIdx,
+        ctx: &ir::Component,
+    ) -> Option<cr::Label<usize>> {
+        let ir::info::Assert(reason) = ctx.get(info).as_assert()?;
+        reason
+            .diag(ctx)
+            .labels
+            .into_iter()
+            .find(|l| l.style == cr::LabelStyle::Primary)
+    }
+
+    /// Labels pointing to the sources of the assumptions and notes for the
+    /// assumptions without a known source.
+    fn assume_labels(
+        assumes: &[ir::PropIdx],
+        ctx: &ir::Component,
+    ) -> (Vec<cr::Label<usize>>, Vec<String>) {
+        let mut sources = HashMap::new();
+        for (prop, info) in &ctx.assume_info {
+            for c in Self::conjuncts(*prop, ctx) {
+                sources.entry(c).or_insert(*info);
+            }
+        }
+
+        let mut labels = vec![];
+        let mut unknown = vec![];
+        for a in assumes {
+            let disp = ctx.display(*a);
+            match sources.get(a).map(|i| Self::reason_label(*i, ctx)) {
+                Some(Some(l)) => labels.push(
+                    cr::Label::secondary(l.file_id, l.range)
+                        .with_message(format!("assuming `{disp}'")),
+                ),
+                // Assumptions generated by the compiler without a location
+                Some(None) => (),
+                // Path conditions from `if`, loops, and existential bindings
+                None => unknown.push(disp),
+            }
+        }
+        let notes = if unknown.is_empty() {
+            vec![]
+        } else {
+            vec![format!("Also assuming: {}", unknown.join(", "))]
+        };
+        (labels, notes)
+    }
+
+    /// Label a minimal set of assumptions that contradict the consequent of
+    /// the failing obligation. If there is no such set, the assumptions are
+    /// too weak to prove the consequent.
+    fn explain_failure(
+        &mut self,
+        diag: cr::Diagnostic<usize>,
+        prop: ir::PropIdx,
+        ctx: &ir::Component,
+    ) -> cr::Diagnostic<usize> {
+        let (assumes, cons) = Self::split(prop, ctx);
+        let with_cons = |core: &[ir::PropIdx]| {
+            core.iter().copied().chain(iter::once(cons)).collect_vec()
+        };
+        if !self.implied(&with_cons(&assumes), None, ctx) {
+            return diag.with_notes(vec![format!(
+                "No assumption contradicts `{}': the assumptions are too weak to prove it",
+                ctx.display(cons)
+            )]);
+        }
+        let core = self.minimal_core(assumes, |this, core| {
+            this.implied(&with_cons(core), None, ctx)
+        });
+        let (labels, mut notes) = Self::assume_labels(&core, ctx);
+        notes.push(format!(
+            "`{}' can never hold under the labeled assumptions",
+            ctx.display(cons)
+        ));
+        diag.with_labels(labels).with_notes(notes)
+    }
+
+    /// Report a minimal set of assumptions that the proof of a valid
+    /// obligation relies on.
+    fn explain_proof(
+        &mut self,
+        fact: &ir::Fact,
+        ctx: &ir::Component,
+    ) -> Option<cr::Diagnostic<usize>> {
+        let (assumes, cons) = Self::split(fact.prop, ctx);
+        let core = self.minimal_core(assumes, |this, core| {
+            this.implied(core, Some(cons), ctx)
+        });
+        if core.is_empty() {
+            return None;
+        }
+        // Reuse the labels of the reason with the primary label marking the
+        // proven constraint.
+        let mut labels = ctx
+            .get(fact.reason)
+            .as_assert()
+            .map(|ir::info::Assert(reason)| reason.diag(ctx).labels)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|l| match l.style {
+                cr::LabelStyle::Primary => {
+                    l.with_message("constraint is proven")
+                }
+                cr::LabelStyle::Secondary => l,
+            })
+            .collect_vec();
+        let (assume_labels, notes) = Self::assume_labels(&core, ctx);
+        labels.extend(assume_labels);
+        let n = core.len();
+        Some(
+            Diagnostic::note()
+                .with_message(format!(
+                    "proof of `{}' relies on {n} assumption{}",
+                    ctx.display(cons),
+                    if n == 1 { "" } else { "s" }
+                ))
+                .with_labels(labels)
+                .with_notes(notes),
+        )
+    }
+
+    /// Remove assumptions that are not needed for `holds` until every
+    /// remaining assumption is necessary.
+    fn minimal_core<F>(
+        &mut self,
+        mut core: Vec<ir::PropIdx>,
+        holds: F,
+    ) -> Vec<ir::PropIdx>
+    where
+        F: Fn(&mut Self, &[ir::PropIdx]) -> bool,
+    {
+        let mut i = 0;
+        while i < core.len() {
+            let a = core.remove(i);
+            if !holds(self, &core) {
+                core.insert(i, a);
+                i += 1;
+            }
+        }
+        core
+    }
+
+    /// Returns true if the conjunction of the assumptions implies the
+    /// consequent, or is unsatisfiable if there is no consequent.
+    fn implied(
+        &mut self,
+        assumes: &[ir::PropIdx],
+        cons: Option<ir::PropIdx>,
+        ctx: &ir::Component,
+    ) -> bool {
+        if self.timed_out() {
+            return false;
+        } else if self.is_builtin() {
+            return self.builtin.implied(assumes, cons, ctx);
+        }
+        let ante = self.sol.and_many(
+            iter::once(self.sol.true_())
+                .chain(assumes.iter().map(|a| self.prop_map[*a])),
+        );
+        let cons = cons.map_or(self.sol.false_(), |c| self.prop_map[c]);
+        let imp = self.sol.imp(ante, cons);
+        if self.portfolio.is_some() {
+            let res = self.race(vec![(self.sol.not(imp), vec![])], ctx);
+            return matches!(res[..], [Validity::Valid]);
+        }
+        let actlit = self.new_act_lit();
+        let query = self.sol.imp(actlit, self.sol.not(imp));
+        self.sol.assert(query).unwrap();
+        let res = self.sol.check_assuming([actlit]).unwrap();
+        self.sol.assert(self.sol.not(actlit)).unwrap();
+        matches!(res, smt::Response::Unsat)
+    }
+
+    /// Warn if the assumptions that hold on every path through the component
+    /// are contradictory since every obligation is then trivially proven.
+    /// Reports a minimal set of contradictory assumptions.
+    fn check_vacuity(&mut self, ctx: &ir::Component) {
+        let assumes = ctx
+            .top_assumes
+            .iter()
+            .flat_map(|a| Self::conjuncts(*a, ctx))
+            .unique()
+            .collect_vec();
+        // Most components only make difference assumptions which the bounds
+        // show to be satisfiable without a solver. Bounds reason about
+        // unbounded integers.
+        let sat = self.bv_width.is_none()
+            && bounds::satisfiable(&assumes, ctx) == Some(true);
+        if assumes.is_empty() || sat || !self.implied(&assumes, None, ctx) {
+            return;
+        }
+        let core = self
+            .minimal_core(assumes, |this, core| this.implied(core, None, ctx));
+        let (labels, mut notes) = Self::assume_labels(&core, ctx);
+        let labels = labels
+            .into_iter()
+            .map(|l| {
+                cr::Label::primary(l.file_id, l.range).with_message(l.message)
+            })
+            .collect_vec();
+        notes.push(
+            "Every constraint in the component is trivially satisfied"
+                .to_string(),
+        );
+        let diag = Diagnostic::warning()
+            .with_message("component's assumptions are contradictory")
+            .with_labels(labels)
+            .with_notes(notes);
+        self.diagnostics.push((diag, vec![]));
+    }
+
+    /// Check the facts individually and add diagnostics for the failing ones
+    fn failing_props(&mut self, comp: &ir::Component) {
+        let props = std::mem::take(&mut self.to_prove);
+        for fact in props {
+            self.check_valid(fact, comp);
+        }
+    }
+
+    /// Top-level proof obligations of the component
+    fn obligations(comp: &ir::Component) -> Vec<ir::Fact> {
+        comp.cmds
+            .iter()
+            .filter_map(|cmd| match cmd {
+                ir::Command::Fact(f) => Some(f.clone()),
+                _ => None,
+            })
+            .collect_vec()
+    }
+
+    /// Returns true if the obligations of the component have been proven by
+    /// an earlier run.
+    fn cached(&mut self, comp: &ir::Component) -> bool {
+        // Explanations and SMT-LIB files are only generated when the
+        // component is checked. The cache records proofs over unbounded
+        // integers which do not carry over to bit-vectors.
+        let skip = self.explain_proofs
+            || self.emitter.is_some()
+            || self.bv_width.is_some();
+        let Some(cache) = self.cache.as_mut().filter(|_| !skip) else {
+            return false;
+        };
+        let facts = Self::obligations(comp);
+        if facts.is_empty() {
+            return false;
+        }
+        let key = Key::new(comp, &facts);
+        if cache.lookup(&key) {
+            return true;
+        }
+        self.cache_key = Some(key);
+        false
+    }
+
+    /// Declare the terms used by the component in the solver
+    fn declare(&mut self, comp: &ir::Component) {
+        // The builtin solver works directly on the component but emitting the
+        // obligations requires their encoding.
+        if self.is_builtin() && self.emitter.is_none() {
+            return;
+        }
+
+        self.define_user_funcs(comp);
+
+        // Declare all parameters
+        let int = self.num_sort();
+        for (idx, _) in comp.params().iter() {
+            let sexp = self.declare_term(self.fmt_param(idx, comp), int, None);
+            self.param_map.push(idx, sexp);
+        }
+
+        // Declare all events
+        for (idx, _) in comp.events().iter() {
+            let sexp = self.declare_term(self.fmt_event(idx, comp), int, None);
+            self.ev_map.push(idx, sexp);
+        }
+
+        // Declare all expressions
+        for (idx, expr) in comp.exprs().iter() {
+            let assign = self.expr_to_sexp(expr);
+            let sexp =
+                self.declare_term(Self::fmt_expr(idx), int, Some(assign));
+            self.expr_map.push(idx, sexp);
+        }
+
+        // Declare all time expressions
+        for (idx, ir::Time { event, offset }) in comp.times().iter() {
+            let (ev, off) = (self.ev_map[*event], self.expr_map[*offset]);
+            let assign = if self.bv_width.is_some() {
+                self.sol.bvadd(ev, off)
+            } else {
+                self.sol.plus(ev, off)
+            };
+            let sexp =
+                self.declare_term(Self::fmt_time(idx), int, Some(assign));
+            self.time_map.push(idx, sexp);
+        }
+
+        // Declare all propositions
+        let bs = self.sol.bool_sort();
+        for (idx, prop) in comp.props().iter() {
+            // Define assertion equating the proposition to its assignment
+            let assign = self.prop_to_sexp(prop);
+            let sexp =
+                self.declare_term(Discharge::fmt_prop(idx), bs, Some(assign));
+            self.prop_map.push(idx, sexp);
+        }
+    }
+
+    /// Decide the facts in `to_prove` that follow from difference bounds on
+    /// their variables and return the propositions that need the solver.
+    fn pre_discharge(&mut self, ctx: &ir::Component) -> Vec<ir::PropIdx> {
+        let props = self.to_prove.iter().map(|f| f.prop).unique().collect_vec();
+        self.obligation_count += props.len() as u64;
+        let mut pending = vec![];
+        for prop in props {
+            // Difference bounds reason about unbounded integers
+            let decision = if self.bv_width.is_none() {
+                bounds::decide(prop, ctx)
+            } else {
+                None
+            };
+            let out = match decision {
+                Some(Decision::Valid) => Validity::Valid,
+                Some(Decision::Invalid(vals)) if self.show_models => {
+                    Validity::Invalid(Assign(
+                        Self::model_vars(prop, ctx)
+                            .into_iter()
+                            .map(|v| {
+                                let n = vals.get(&v).copied().unwrap_or(0);
+                                (v, n.to_string())
+                            })
+                            .collect(),
+                    ))
+                }
+                Some(Decision::Invalid(_)) => {
+                    Validity::Invalid(Assign::default())
+                }
+                None => {
+                    pending.push(prop);
+                    continue;
+                }
+            };
+            self.checked.insert(prop, out);
+        }
+        self.solver_count += pending.len() as u64;
+        pending
+    }
+
+    /// Prove the facts in `to_prove` and add diagnostics for the failing ones
+    fn prove(&mut self, idx: ir::CompIdx, comp: &ir::Component) {
+        self.check_vacuity(comp);
+        if self.to_prove.is_empty() {
+            return;
+        }
+        self.emit(idx, comp);
+        let pending = self.pre_discharge(comp);
+
+        if pending.is_empty()
+            || self.is_builtin()
+            || self.explain_proofs
+            || self.timed_out()
+        {
+            // The builtin solver checks each fact individually. Explaining
+            // proofs also requires checking each fact without assuming the
+            // others.
+            // Facts are reported as undecided once the time limit is reached.
+            self.failing_props(comp);
+        } else if self.portfolio.is_some() {
+            // Race the solvers on all the facts at once
+            let res = self.portfolio_valid(&pending, comp);
+            self.checked.extend(pending.into_iter().zip(res));
+            self.failing_props(comp);
+        } else {
+            // Attempt to prove all facts
+            let total_prop =
+                self.sol.and_many(pending.iter().map(|p| self.prop_map[*p]));
+            let total_prop = self.sol.not(total_prop);
+            self.sol.assert(total_prop).unwrap();
+
+            // If there is at least one failing or undecided prop, roll back to
+            // individually checking the props for error reporting
+            if matches!(self.sol.check().unwrap(), smt::Response::Unsat) {
+                self.checked
+                    .extend(pending.into_iter().map(|p| (p, Validity::Valid)));
+            }
+            self.failing_props(comp);
+        }
+
+        if let (Some(cache), Some(key)) = (&self.cache, &self.cache_key) {
+            if self.diagnostics.is_empty() {
+                cache.store(key);
+            }
+        }
+    }
+
+    /// Write the obligations of the component to an SMT-LIB file
+    fn emit(&self, idx: ir::CompIdx, comp: &ir::Component) {
+        let Some(emitter) = &self.emitter else {
+            return;
+        };
+        let obligations = self
+            .to_prove
+            .iter()
+            .map(|fact| {
+                let (reason, labels) = match comp.get(fact.reason).as_assert() {
+                    Some(ir::info::Assert(reason)) => {
+                        let diag = reason.diag(comp);
+                        (diag.message, diag.labels)
+                    }
+                    None => (
+                        "No information was given on who generated this error"
+                            .to_string(),
+                        vec![],
+                    ),
+                };
+                Obligation {
+                    prop: self.prop_map[fact.prop],
+                    cons: comp.display(fact.prop.consequent(comp)),
+                    reason,
+                    labels,
+                }
+            })
+            .collect_vec();
+        let header = match &comp.src_info {
+            Some(info) => {
+                format!("Proof obligations of component `{}'", info.name)
+            }
+            None => format!("Proof obligations of component {}", idx.get()),
+        };
+        emitter.write(
+            &format!("comp{}", idx.get()),
+            &header,
+            &self.sol,
+            self.func_decls.iter().chain(&self.decls).copied(),
+            &obligations,
+        );
+    }
+
+    /// Check the obligations of the component without traversing it and
+    /// return the diagnostics for the failing ones.
+    fn check_comp(
+        &mut self,
+        idx: ir::CompIdx,
+        comp: &ir::Component,
+    ) -> Vec<(cr::Diagnostic<usize>, Model)> {
+        if self.cached(comp) {
+            return vec![];
+        }
+        self.declare(comp);
+        self.to_prove = Self::obligations(comp);
+        assert!(
+            self.to_prove.iter().all(|f| f.is_assert()),
+            "assumptions should have been eliminated by `hoist-facts` pass"
+        );
+        self.prove(idx, comp);
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Check the components using `jobs` solvers in parallel. Each solver
+    /// repeatedly picks the next unchecked component. The diagnostics are
+    /// reported in the same order as a sequential run.
+    fn par_pass(
+        opts: &cmdline::Opts,
+        ctx: &ir::Context,
+        jobs: usize,
+    ) -> Result<(), u64> {
+        let comps = ctx.comps.idx_iter().collect_vec();
+        let next = AtomicUsize::new(0);
+        let results = thread::scope(|s| {
+            let workers = (0..jobs.min(comps.len()))
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut dis = Self::new(opts);
+                        let mut diags = vec![];
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&idx) = comps.get(i) else {
+                                break;
+                            };
+                            dis.clear_data();
+                            diags.push((i, dis.check_comp(idx, ctx.get(idx))));
+                        }
+                        let counts = (dis.obligation_count, dis.solver_count);
+                        (diags, dis.cache.take(), counts)
+                    })
+                })
+                .collect_vec();
+            workers
+                .into_iter()
+                .map(|w| w.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect_vec()
+        });
+
+        let mut cache: Option<Cache> = None;
+        let mut diags = vec![];
+        let (mut obligations, mut solved) = (0, 0);
+        for (ds, c, (o, s)) in results {
+            diags.extend(ds);
+            obligations += o;
+            solved += s;
+            match (&mut cache, c) {
+                (Some(cache), Some(c)) => cache.merge(c),
+                (None, c) => cache = c,
+                (Some(_), None) => (),
+            }
+        }
+
+        // Report the errors in the order of the components
+        diags.sort_by_key(|(i, _)| *i);
+        let error_count =
+            diags.iter().map(|(_, ds)| Self::emit_diags(ds)).sum();
+        Self::finish(cache.as_ref(), obligations, solved, error_count)
+    }
+
+    /// Emit the diagnostics of a component and return the number of errors
+    fn emit_diags(diags: &[(cr::Diagnostic<usize>, Model)]) -> u64 {
+        let mut error_count = 0;
+        for (diag, model) in diags {
+            fil_utils::emit(diag, model);
+            if diag.severity == cr::Severity::Error {
+                error_count += 1;
+            }
+        }
+        error_count
+    }
+
+    /// Report the statistics of the pass once every component is checked
+    fn finish(
+        cache: Option<&Cache>,
+        obligations: u64,
+        solved: u64,
+        error_count: u64,
+    ) -> Result<(), u64> {
+        if let Some(cache) = cache {
+            cache.report();
+        }
+        Self::report(obligations, solved);
+        if error_count > 0 {
+            Err(error_count)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn bin_to_sexp(
+        &mut self,
+        op: ast::Op,
+        l: smt::SExpr,
+        r: smt::SExpr,
+    ) -> smt::SExpr {
+        let sol = &mut self.sol;
+        if self.bv_width.is_some() {
+            return match op {
+                ast::Op::Add => sol.bvadd(l, r),
+                ast::Op::Sub => sol.bvsub(l, r),
+                ast::Op::Mul => sol.bvmul(l, r),
+                ast::Op::Div => sol.bvudiv(l, r),
+                ast::Op::Mod => sol.bvurem(l, r),
+            };
+        }
+        match op {
+            ast::Op::Add => sol.plus(l, r),
+            ast::Op::Sub => sol.sub(l, r),
+            ast::Op::Mul => sol.times(l, r),
+            ast::Op::Div => sol.div(l, r),
+            ast::Op::Mod => sol.modulo(l, r),
+        }
+    }
+
+    fn expr_to_sexp(&mut self, expr: &ir::Expr) -> smt::SExpr {
+        match expr {
+            ir::Expr::Param(p) => self.param_map[*p],
+            ir::Expr::Concrete(n) if self.bv_width.is_some() => self.bv_num(*n),
+            ir::Expr::Concrete(n) => self.sol.numeral(*n),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.expr_map[*lhs];
+                let r = self.expr_map[*rhs];
+                self.bin_to_sexp(*op, l, r)
+            }
+            ir::Expr::Fn { op, args } => {
+                let args = args.iter().map(|e| self.expr_map[*e]);
+                self.sol.list(
+                    iter::once(self.func_map[op]).chain(args).collect_vec(),
+                )
+            }
+        }
+    }
+
+    fn cmp_to_sexp<F, T>(
+        &mut self,
+        cmp: &ir::CmpOp<T>,
+        mut transform: F,
+    ) -> smt::SExpr
+    where
+        F: FnMut(&T, &Self) -> smt::SExpr,
+    {
+        let ir::CmpOp { op, lhs, rhs } = cmp;
+        let l = transform(lhs, self);
+        let r = transform(rhs, self);
+        if self.bv_width.is_some() {
+            return match op {
+                ir::Cmp::Gt => self.sol.bvugt(l, r),
+                ir::Cmp::Gte => self.sol.bvuge(l, r),
+                ir::Cmp::Eq => self.sol.eq(l, r),
+            };
+        }
+        match op {
+            ir::Cmp::Gt => self.sol.gt(l, r),
+            ir::Cmp::Gte => self.sol.gte(l, r),
+            ir::Cmp::Eq => self.sol.eq(l, r),
+        }
+    }
+
+    /// Convert a proposition to an SMT expression.
+    /// REQUIRES: Sub-terms mentioned in the proposition have already been defined.
+    fn prop_to_sexp(&mut self, prop: &ir::Prop) -> smt::SExpr {
+        let sol = &mut self.sol;
+        match prop {
+            ir::Prop::True => sol.true_(),
+            ir::Prop::False => sol.false_(),
+            ir::Prop::Cmp(c) => self.cmp_to_sexp(c, |e, ctx| ctx.expr_map[*e]),
+            ir::Prop::TimeCmp(c) => {
+                self.cmp_to_sexp(c, |t, ctx| ctx.time_map[*t])
+            }
+            ir::Prop::TimeSubCmp(c) => {
+                self.cmp_to_sexp(c, |ts, ctx| match ts {
+                    ir::TimeSub::Unit(e) => ctx.expr_map[*e],
+                    ir::TimeSub::Sym { l, r } => {
+                        let l = ctx.time_map[*l];
+                        let r = ctx.time_map[*r];
+                        if ctx.bv_width.is_some() {
+                            ctx.sol.bvsub(l, r)
+                        } else {
+                            ctx.sol.sub(l, r)
+                        }
+                    }
+                })
+            }
+            ir::Prop::Not(p) => sol.not(self.prop_map[*p]),
+            ir::Prop::And(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.and(l, r)
+            }
+            ir::Prop::Or(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.or(l, r)
+            }
+            ir::Prop::Implies(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.imp(l, r)
+            }
+        }
+    }
+}
+
+impl Visitor for Discharge {
+    fn name() -> &'static str {
+        "discharge"
+    }
+
+    fn do_pass(opts: &cmdline::Opts, ctx: &mut ir::Context) -> Result<(), u64> {
+        if opts.jobs > 1 {
+            if opts.solver_replay_file.is_none() {
+                return Self::par_pass(opts, ctx, opts.jobs);
+            }
+            log::warn!("Dumping the solver log requires checking components sequentially");
+        }
+        let mut dis = Self::new(opts);
+        let mut error_count = 0;
+        for idx in ctx.comps.idx_iter() {
+            dis.clear_data();
+            error_count += Self::emit_diags(&dis.check_comp(idx, ctx.get(idx)));
+        }
+        Self::finish(
+            dis.cache.as_ref(),
+            dis.obligation_count,
+            dis.solver_count,
+            error_count,
+        )
+    }
+}
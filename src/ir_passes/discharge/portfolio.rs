@@ -0,0 +1,388 @@
+//! Race several SMT solvers on the same queries and use the first definitive
+//! answer.
+//!
+//! Each solver runs in its own process which is driven over its standard input
+//! and output. The processes are kept alive while checking a component: the
+//! declarations are sent once and each query is checked within a `push`/`pop`
+//! scope. Once a solver has decided every query, the solvers that are still
+//! running are killed and restarted by the next race.
+use crate::cmdline::Solver;
+use itertools::Itertools;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+/// A query checking the satisfiability of an assertion
+pub struct Query {
+    /// The assertion in SMT-LIB syntax
+    pub assert: String,
+    /// Constants whose values are reported when the assertion is satisfiable
+    pub values: Vec<String>,
+}
+
+/// Answer of a solver to a [Query]
+pub enum Answer {
+    Unsat,
+    /// Satisfiable with the values of the requested constants
+    Sat(Vec<(String, String)>),
+    /// The solver could not decide the query. Contains the reason.
+    Unknown(String),
+}
+
+/// Solvers raced against each other
+pub struct Portfolio {
+    solvers: Vec<(Solver, &'static str, Vec<String>)>,
+    /// Processes of the solvers for the current component
+    running: Vec<Process>,
+}
+
+impl Portfolio {
+    pub fn new(solvers: Vec<(Solver, &'static str, Vec<String>)>) -> Self {
+        Self {
+            solvers,
+            running: vec![],
+        }
+    }
+
+    /// Stop the solvers. The next race starts new processes.
+    pub fn reset(&mut self) {
+        self.running.clear();
+    }
+
+    /// Start the solvers that are not running and send them the declarations
+    fn start(&mut self, decls: &[String]) {
+        for (solver, prog, args) in &self.solvers {
+            if self.running.iter().any(|p| p.prog == *prog) {
+                continue;
+            }
+            match Process::spawn(*solver, prog, args, decls) {
+                Ok(p) => self.running.push(p),
+                Err(err) => log::warn!("Failed to start {prog}: {err}"),
+            }
+        }
+    }
+
+    /// Run every solver on the queries and return the answers of the first
+    /// solver that decides all of them. If no solver decides all of them, the
+    /// answers of the first solver to finish are returned. All solvers are
+    /// killed once the `deadline` is reached. Solvers that are started by the
+    /// race are sent the declarations first. The winner is logged along with
+    /// `name`, which describes the queries.
+    pub fn race(
+        &mut self,
+        name: &str,
+        decls: &[String],
+        queries: &[Query],
+        deadline: Option<Instant>,
+    ) -> Result<(Solver, Vec<Answer>), String> {
+        self.start(decls);
+        if self.running.is_empty() {
+            return Err("no solver in the portfolio could be started".into());
+        }
+
+        let start = Instant::now();
+        // Processes that answered every query and can be reused
+        let mut done = vec![false; self.running.len()];
+        let res = thread::scope(|s| {
+            let (tx, rx) = mpsc::channel();
+            let mut children = vec![];
+            for (i, p) in self.running.iter_mut().enumerate() {
+                let Process {
+                    solver,
+                    child,
+                    racer,
+                    ..
+                } = p;
+                children.push(child);
+                let (tx, solver) = (tx.clone(), *solver);
+                s.spawn(move || {
+                    // The receiver is gone if the race has already been decided
+                    let _ = tx.send((i, solver, racer.run(queries)));
+                });
+            }
+
+            let mut first = None;
+            let mut failure = None;
+            for _ in 0..children.len() {
+                let msg = match deadline {
+                    Some(d) => rx
+                        .recv_timeout(
+                            d.saturating_duration_since(Instant::now()),
+                        )
+                        .map_err(|_| "timed out".to_string()),
+                    None => rx.recv().map_err(|e| e.to_string()),
+                };
+                match msg {
+                    Ok((i, solver, Ok(answers))) => {
+                        done[i] = true;
+                        let decided = answers
+                            .iter()
+                            .all(|a| !matches!(a, Answer::Unknown(_)));
+                        if decided {
+                            first = Some((solver, answers));
+                            break;
+                        }
+                        first.get_or_insert((solver, answers));
+                    }
+                    Ok((_, solver, Err(err))) => {
+                        log::warn!("{solver} failed: {err}");
+                        failure
+                            .get_or_insert(format!("{solver} failed: {err}"));
+                    }
+                    Err(err) => {
+                        failure = Some(err);
+                        break;
+                    }
+                }
+            }
+
+            // Stop the solvers that are still running
+            for (child, done) in children.into_iter().zip(&done) {
+                if !done {
+                    let _ = child.kill();
+                }
+            }
+
+            match first {
+                Some((solver, answers)) => {
+                    log::info!(
+                        "portfolio: {solver} won on {} quer{} of {name} in {}ms",
+                        queries.len(),
+                        if queries.len() == 1 { "y" } else { "ies" },
+                        start.elapsed().as_millis()
+                    );
+                    Ok((solver, answers))
+                }
+                None => Err(failure.unwrap_or_default()),
+            }
+        });
+        let mut done = done.into_iter();
+        self.running.retain(|_| done.next().unwrap());
+        res
+    }
+}
+
+/// A running solver process. The process is killed when dropped.
+struct Process {
+    solver: Solver,
+    prog: &'static str,
+    child: Child,
+    racer: Racer,
+}
+
+impl Process {
+    fn spawn(
+        solver: Solver,
+        prog: &'static str,
+        args: &[String],
+        decls: &[String],
+    ) -> io::Result<Self> {
+        let mut child = Command::new(prog)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut racer = Racer {
+            stdin: BufWriter::new(child.stdin.take().unwrap()),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+        };
+        racer.send("(set-option :print-success false)")?;
+        racer.send("(set-option :produce-models true)")?;
+        for decl in decls {
+            racer.send(decl)?;
+        }
+        Ok(Self {
+            solver,
+            prog,
+            child,
+            racer,
+        })
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Connection to a solver process
+struct Racer {
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Racer {
+    /// Send a command that does not produce a response
+    fn send(&mut self, cmd: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{cmd}")
+    }
+
+    /// Send a command and wait for its response. Errors reported by the
+    /// solver are returned as failures.
+    fn ask(&mut self, cmd: &str) -> Result<SExp, String> {
+        self.send(cmd).map_err(|e| e.to_string())?;
+        self.stdin.flush().map_err(|e| e.to_string())?;
+        let resp = SExp::read(&mut self.stdout).map_err(|e| e.to_string())?;
+        match resp.error() {
+            Some(msg) => Err(format!("solver reported an error: {msg}")),
+            None => Ok(resp),
+        }
+    }
+
+    /// Check the satisfiability of each query in its own scope
+    fn run(&mut self, queries: &[Query]) -> Result<Vec<Answer>, String> {
+        let mut answers = Vec::with_capacity(queries.len());
+        for Query { assert, values } in queries {
+            self.send("(push 1)").map_err(|e| e.to_string())?;
+            self.send(&format!("(assert {assert})"))
+                .map_err(|e| e.to_string())?;
+            let answer = match self.ask("(check-sat)")? {
+                SExp::Atom(a) if a == "unsat" => Answer::Unsat,
+                SExp::Atom(a) if a == "sat" => {
+                    if values.is_empty() {
+                        Answer::Sat(vec![])
+                    } else {
+                        let cmd = format!("(get-value ({}))", values.join(" "));
+                        Answer::Sat(self.ask(&cmd)?.bindings())
+                    }
+                }
+                SExp::Atom(a) if a == "unknown" => {
+                    let why = self.ask("(get-info :reason-unknown)")?;
+                    Answer::Unknown(why.reason())
+                }
+                resp => return Err(format!("unexpected response {resp}")),
+            };
+            answers.push(answer);
+            self.send("(pop 1)").map_err(|e| e.to_string())?;
+        }
+        Ok(answers)
+    }
+}
+
+/// A response of the solver
+enum SExp {
+    Atom(String),
+    List(Vec<SExp>),
+}
+
+impl SExp {
+    /// Read the next s-expression from the reader
+    fn read(r: &mut impl BufRead) -> io::Result<Self> {
+        let mut stack: Vec<Vec<SExp>> = vec![];
+        loop {
+            let c = Self::next(r)?;
+            let done = match c {
+                c if c.is_ascii_whitespace() => None,
+                b'(' => {
+                    stack.push(vec![]);
+                    None
+                }
+                b')' => {
+                    let Some(list) = stack.pop() else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "unbalanced parentheses",
+                        ));
+                    };
+                    Some(SExp::List(list))
+                }
+                c => Some(SExp::Atom(Self::atom(c, r)?)),
+            };
+            match (done, stack.last_mut()) {
+                (Some(e), Some(list)) => list.push(e),
+                (Some(e), None) => return Ok(e),
+                (None, _) => (),
+            }
+        }
+    }
+
+    fn next(r: &mut impl BufRead) -> io::Result<u8> {
+        let mut buf = [0];
+        r.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Read the rest of an atom starting with `first`
+    fn atom(first: u8, r: &mut impl BufRead) -> io::Result<String> {
+        let mut out = vec![first];
+        // Quoted strings and symbols extend until the closing quote
+        let close = match first {
+            b'"' => Some(b'"'),
+            b'|' => Some(b'|'),
+            _ => None,
+        };
+        while let Some(&c) = r.fill_buf()?.first() {
+            match close {
+                Some(q) => {
+                    out.push(c);
+                    r.consume(1);
+                    if c == q {
+                        break;
+                    }
+                }
+                None if c.is_ascii_whitespace() || c == b'(' || c == b')' => {
+                    break
+                }
+                None => {
+                    out.push(c);
+                    r.consume(1);
+                }
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// The bindings in the response to a `get-value` command
+    fn bindings(&self) -> Vec<(String, String)> {
+        let SExp::List(binds) = self else {
+            return vec![];
+        };
+        binds
+            .iter()
+            .filter_map(|b| match b {
+                SExp::List(kv) if kv.len() == 2 => {
+                    Some((kv[0].to_string(), kv[1].to_string()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The message of an `(error ...)` response
+    fn error(&self) -> Option<String> {
+        match self {
+            SExp::List(l) => match &l[..] {
+                [SExp::Atom(a), msg] if a == "error" => {
+                    Some(msg.to_string().trim_matches('"').to_string())
+                }
+                _ => None,
+            },
+            SExp::Atom(_) => None,
+        }
+    }
+
+    /// The reason in the response to `(get-info :reason-unknown)`
+    fn reason(&self) -> String {
+        match self {
+            SExp::List(kv) if kv.len() == 2 => {
+                kv[1].to_string().trim_matches('"').to_string()
+            }
+            _ => "no reason given".to_string(),
+        }
+    }
+}
+
+impl Display for SExp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SExp::Atom(a) => write!(f, "{a}"),
+            SExp::List(l) => write!(f, "({})", l.iter().join(" ")),
+        }
+    }
+}
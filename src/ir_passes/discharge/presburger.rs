@@ -0,0 +1,825 @@
+//! An in-process decision procedure for the linear integer arithmetic fragment
+//! of the propositions generated by Filament.
+//!
+//! Propositions are encoded into linear constraints over parameters and events.
+//! Multiplication and division by constants are interpreted exactly and the
+//! piecewise-linear functions `min`, `max`, `abs_diff`, and `ceil_div` by a
+//! constant are defined by case splits. Calls to user-defined functions are
+//! inlined. All other non-linear terms (such as `pow2(N)` or `N * M`) are
+//! treated as uninterpreted integer variables. Satisfiability of the constraints is decided
+//! using Fourier-Motzkin elimination with integer tightening, and disjunctions
+//! are handled by case splitting.
+//!
+//! The procedure is sound but incomplete: if the negation of a proposition has
+//! no rational solution, the proposition is valid. Otherwise, we attempt to
+//! build an integer model and check it against the original proposition. If
+//! either step fails, the solver reports that it does not know the answer.
+use fil_ast as ast;
+use fil_ir::{self as ir, linear, Ctx};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use std::iter;
+use std::time::{Duration, Instant};
+
+/// Maximum number of case splits explored for a single query.
+const MAX_SPLITS: u64 = 10_000;
+/// Maximum number of constraints generated while eliminating a variable.
+const MAX_CONSTRAINTS: usize = 2_000;
+
+/// Result of an operation that may fail to decide the query. The error
+/// describes why the solver gave up.
+type Res<T> = Result<T, String>;
+
+/// Variables in the order they were eliminated along with the constraints that
+/// mentioned them at the time.
+type Stages = Vec<(Var, Vec<Linear>)>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+/// Integer variables in the linear encoding.
+enum Var {
+    Param(ir::ParamIdx),
+    Event(ir::EventIdx),
+    /// A term like `pow2(N)`, `N * M`, or `max(N, M)`. Piecewise-linear
+    /// terms are defined by the facts about the variable.
+    Term(ir::ExprIdx),
+    /// The quotient of dividing an expression by a positive constant.
+    Quot(ir::ExprIdx, u64),
+    /// A term in the body of a user-defined function inlined at the
+    /// expression. Terms are numbered in the order they are encoded.
+    Aux(ir::ExprIdx, u32),
+}
+
+impl From<linear::Var> for Var {
+    fn from(v: linear::Var) -> Self {
+        match v {
+            linear::Var::Param(p) => Var::Param(p),
+            linear::Var::Event(e) => Var::Event(e),
+            linear::Var::Term(e) => Var::Term(e),
+        }
+    }
+}
+
+/// A linear term `c + a_1*x_1 + ... + a_n*x_n`. When used as a constraint,
+/// represents `c + a_1*x_1 + ... + a_n*x_n >= 0`.
+type Linear = linear::Linear<Var>;
+
+/// Result of normalizing a linear constraint.
+enum Norm {
+    /// The constraint is always true
+    Trivial,
+    /// The constraint is always false
+    Contradiction,
+    /// The normalized constraint
+    Cons(Linear),
+}
+
+/// Normalize the constraint `l >= 0` by dividing the coefficients with their
+/// greatest common divisor and rounding down the constant. Over the integers,
+/// this tightens the constraint.
+fn normalize(l: Linear) -> Norm {
+    let g = l.coeffs().fold(0, |g, (_, c)| gcd(g, c.abs()));
+    if g == 0 {
+        if l.constant_term() >= 0 {
+            Norm::Trivial
+        } else {
+            Norm::Contradiction
+        }
+    } else {
+        Norm::Cons(l.div_floor(g))
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Clone)]
+/// A proposition in negation normal form over linear constraints.
+enum Formula {
+    Atom(Linear),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    /// `l >= 0`
+    fn gte_zero(l: Linear) -> Self {
+        Formula::Atom(l)
+    }
+
+    /// Encode the comparison `lhs op rhs` if `positive` and its negation
+    /// otherwise.
+    fn cmp(
+        op: &ir::Cmp,
+        lhs: Linear,
+        rhs: Linear,
+        positive: bool,
+    ) -> Res<Self> {
+        let diff = lhs.sub(&rhs)?;
+        let one = Linear::constant(1);
+        Ok(match (op, positive) {
+            // l - r - 1 >= 0
+            (ir::Cmp::Gt, true) => Formula::gte_zero(diff.sub(&one)?),
+            // r - l >= 0
+            (ir::Cmp::Gt, false) => Formula::gte_zero(diff.scale(-1)?),
+            // l - r >= 0
+            (ir::Cmp::Gte, true) => Formula::gte_zero(diff),
+            // r - l - 1 >= 0
+            (ir::Cmp::Gte, false) => {
+                Formula::gte_zero(diff.scale(-1)?.sub(&one)?)
+            }
+            (ir::Cmp::Eq, true) => Formula::And(vec![
+                Formula::gte_zero(diff.scale(-1)?),
+                Formula::gte_zero(diff),
+            ]),
+            (ir::Cmp::Eq, false) => Formula::Or(vec![
+                Formula::gte_zero(diff.sub(&one)?),
+                Formula::gte_zero(diff.scale(-1)?.sub(&one)?),
+            ]),
+        })
+    }
+
+    /// Add all variables mentioned in the formula to `acc`.
+    fn vars(&self, acc: &mut HashSet<Var>) {
+        match self {
+            Formula::Atom(l) => acc.extend(l.coeffs().map(|(v, _)| v)),
+            Formula::And(fs) | Formula::Or(fs) => {
+                fs.iter().for_each(|f| f.vars(acc))
+            }
+        }
+    }
+}
+
+/// Integer assignment to the variables of a query.
+pub struct Model(HashMap<Var, i128>);
+
+impl Model {
+    /// Model assigning the values to the parameters and events
+    pub fn from_values(vals: &HashMap<linear::Var, i128>) -> Self {
+        Self(vals.iter().map(|(v, n)| (Var::from(*v), *n)).collect())
+    }
+
+    /// Value assigned to a parameter. Unconstrained parameters are zero.
+    pub fn param(&self, p: ir::ParamIdx) -> i128 {
+        self.get(Var::Param(p))
+    }
+
+    /// Value assigned to a variable. Unconstrained variables are zero.
+    pub fn value(&self, v: linear::Var) -> i128 {
+        self.get(Var::from(v))
+    }
+
+    fn get(&self, v: Var) -> i128 {
+        self.0.get(&v).copied().unwrap_or(0)
+    }
+
+    /// Evaluate an expression under the model using the precise semantics of
+    /// each operator. Returns `None` if the expression is undefined.
+    fn expr(&self, e: ir::ExprIdx, comp: &ir::Component) -> Option<i128> {
+        match comp.get(e) {
+            ir::Expr::Param(p) => Some(self.param(*p)),
+            ir::Expr::Concrete(n) => Some(*n as i128),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.expr(*lhs, comp)?;
+                let r = self.expr(*rhs, comp)?;
+                match op {
+                    ast::Op::Add => l.checked_add(r),
+                    ast::Op::Sub => l.checked_sub(r),
+                    ast::Op::Mul => l.checked_mul(r),
+                    ast::Op::Div => l.checked_div_euclid(r),
+                    ast::Op::Mod => l.checked_rem_euclid(r),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| {
+                        self.expr(*a, comp).and_then(|v| u64::try_from(v).ok())
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                comp.funcs.eval(*op, args).map(|n| n as i128)
+            }
+        }
+    }
+
+    /// Evaluate a time under the model
+    pub fn time(&self, t: ir::TimeIdx, comp: &ir::Component) -> Option<i128> {
+        let ir::Time { event, offset } = comp.get(t);
+        self.get(Var::Event(*event))
+            .checked_add(self.expr(*offset, comp)?)
+    }
+
+    fn timesub(&self, ts: &ir::TimeSub, comp: &ir::Component) -> Option<i128> {
+        match ts {
+            ir::TimeSub::Unit(e) => self.expr(*e, comp),
+            ir::TimeSub::Sym { l, r } => {
+                self.time(*l, comp)?.checked_sub(self.time(*r, comp)?)
+            }
+        }
+    }
+
+    fn cmp<T>(
+        &self,
+        cmp: &ir::CmpOp<T>,
+        mut eval: impl FnMut(&T) -> Option<i128>,
+    ) -> Option<bool> {
+        let ir::CmpOp { op, lhs, rhs } = cmp;
+        let l = eval(lhs)?;
+        let r = eval(rhs)?;
+        Some(match op {
+            ir::Cmp::Gt => l > r,
+            ir::Cmp::Gte => l >= r,
+            ir::Cmp::Eq => l == r,
+        })
+    }
+
+    /// Evaluate a proposition under the model.
+    fn prop(&self, p: ir::PropIdx, comp: &ir::Component) -> Option<bool> {
+        match comp.get(p) {
+            ir::Prop::True => Some(true),
+            ir::Prop::False => Some(false),
+            ir::Prop::Cmp(c) => self.cmp(c, |e| self.expr(*e, comp)),
+            ir::Prop::TimeCmp(c) => self.cmp(c, |t| self.time(*t, comp)),
+            ir::Prop::TimeSubCmp(c) => self.cmp(c, |ts| self.timesub(ts, comp)),
+            ir::Prop::Not(p) => self.prop(*p, comp).map(|b| !b),
+            ir::Prop::And(l, r) => {
+                Some(self.prop(*l, comp)? && self.prop(*r, comp)?)
+            }
+            ir::Prop::Or(l, r) => {
+                Some(self.prop(*l, comp)? || self.prop(*r, comp)?)
+            }
+            ir::Prop::Implies(l, r) => {
+                Some(!self.prop(*l, comp)? || self.prop(*r, comp)?)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+/// The builtin solver. Caches the linear encoding of expressions for the
+/// component currently being checked.
+pub struct Presburger {
+    /// Linear encoding of expressions
+    exprs: HashMap<ir::ExprIdx, Linear>,
+    /// Facts about variables that represent composite terms. These are
+    /// added to every query that mentions the variable.
+    defs: HashMap<Var, Vec<Formula>>,
+    /// Number of case splits performed by the current query
+    splits: u64,
+    /// Time limit for each query
+    timeout: Option<Duration>,
+    /// Time at which the current query gives up
+    deadline: Option<Instant>,
+}
+
+impl Presburger {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Time limit for each query
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Check whether the proposition is valid.
+    /// Returns `Ok(None)` if the proposition is valid, `Ok(Some(model))` with
+    /// a model falsifying the proposition if it is invalid, and an error if
+    /// the solver could not decide the proposition.
+    pub fn check(
+        &mut self,
+        prop: ir::PropIdx,
+        comp: &ir::Component,
+    ) -> Res<Option<Model>> {
+        let neg = self.prop(prop, false, comp)?;
+        let Some(model) = self.refute(neg)? else {
+            return Ok(None);
+        };
+
+        // The model might be spurious because of the uninterpreted terms so
+        // make sure that it actually falsifies the proposition.
+        match model.prop(prop, comp) {
+            Some(false) => Ok(Some(model)),
+            Some(true) => {
+                Err("counterexample relies on non-linear terms".to_string())
+            }
+            None => {
+                Err("counterexample evaluates an undefined term".to_string())
+            }
+        }
+    }
+
+    /// Returns true if the conjunction of `assumes` implies `cons`, or is
+    /// unsatisfiable if there is no `cons`.
+    /// Undecided queries are treated as not implied.
+    pub fn implied(
+        &mut self,
+        assumes: &[ir::PropIdx],
+        cons: Option<ir::PropIdx>,
+        comp: &ir::Component,
+    ) -> bool {
+        let neg = assumes
+            .iter()
+            .map(|a| (*a, true))
+            .chain(cons.map(|c| (c, false)))
+            .map(|(p, positive)| self.prop(p, positive, comp))
+            .collect::<Res<Vec<_>>>();
+        match neg {
+            Ok(fs) => matches!(self.refute(Formula::And(fs)), Ok(None)),
+            Err(_) => false,
+        }
+    }
+
+    /// Search for a model of the formula along with the facts about the
+    /// composite terms it mentions.
+    fn refute(&mut self, neg: Formula) -> Res<Option<Model>> {
+        self.splits = 0;
+        self.deadline = self.timeout.map(|t| Instant::now() + t);
+
+        // Add the facts about all the composite terms mentioned in the
+        // formula.
+        let mut vars = HashSet::new();
+        neg.vars(&mut vars);
+        let mut facts = Vec::new();
+        let mut queue: Vec<_> = vars.iter().copied().collect();
+        while let Some(v) = queue.pop() {
+            let Some(defs) = self.defs.get(&v) else {
+                continue;
+            };
+            for def in defs {
+                let mut new = HashSet::new();
+                def.vars(&mut new);
+                queue.extend(new.into_iter().filter(|v| vars.insert(*v)));
+                facts.push(def.clone());
+            }
+        }
+
+        let pending = iter::once(&neg).chain(&facts).collect();
+        self.search(vec![], pending)
+    }
+
+    /// Linear encoding of an expression.
+    fn expr(&mut self, e: ir::ExprIdx, comp: &ir::Component) -> Res<Linear> {
+        if let Some(l) = self.exprs.get(&e) {
+            return Ok(l.clone());
+        }
+        let out = match comp.get(e) {
+            ir::Expr::Param(p) => Linear::var(Var::Param(*p)),
+            ir::Expr::Concrete(n) => Linear::constant(*n as i128),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.expr(*lhs, comp)?;
+                let r = self.expr(*rhs, comp)?;
+                match (op, l.as_constant(), r.as_constant()) {
+                    (ast::Op::Add, _, _) => l.add(&r)?,
+                    (ast::Op::Sub, _, _) => l.sub(&r)?,
+                    (ast::Op::Mul, _, Some(n)) => l.scale(n)?,
+                    (ast::Op::Mul, Some(n), _) => r.scale(n)?,
+                    (ast::Op::Div | ast::Op::Mod, _, Some(n)) if n > 0 => {
+                        let var = Var::Quot(*lhs, n as u64);
+                        let q = self.quotient(var, &l, n)?;
+                        if matches!(op, ast::Op::Div) {
+                            q
+                        } else {
+                            l.sub(&q.scale(n)?)?
+                        }
+                    }
+                    (ast::Op::Mul | ast::Op::Div, _, _) => {
+                        self.nonlinear(Var::Term(e), op, &l, &r)?
+                    }
+                    _ => Linear::var(Var::Term(e)),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| self.expr(*a, comp))
+                    .collect::<Res<Vec<_>>>()?;
+                match op {
+                    ast::Fn::User(name) => {
+                        self.inline(e, *name, args, &mut 0, comp)?
+                    }
+                    _ => self.app(Var::Term(e), *op, &args)?,
+                }
+            }
+        };
+        self.exprs.insert(e, out.clone());
+        Ok(out)
+    }
+
+    /// Linear encoding of the body of the user-defined function `name`
+    /// applied to `args` at the expression `e`. `aux` counts the variables
+    /// introduced for the terms of the inlined bodies.
+    fn inline(
+        &mut self,
+        e: ir::ExprIdx,
+        name: ast::Id,
+        args: Vec<Linear>,
+        aux: &mut u32,
+        comp: &ir::Component,
+    ) -> Res<Linear> {
+        let Some(def) = comp.funcs.get(name) else {
+            return Err(format!("function `{name}' is not defined"));
+        };
+        let bind = def.params.iter().map(|p| *p.inner()).zip(args).collect();
+        self.body(e, def.body.inner(), &bind, aux, comp)
+    }
+
+    /// Linear encoding of an expression in the body of an inlined function
+    /// with the parameters bound to `bind`. Follows the encoding of
+    /// [ir::Expr] but the terms are abstracted with [Var::Aux] variables.
+    fn body(
+        &mut self,
+        e: ir::ExprIdx,
+        body: &ast::Expr,
+        bind: &HashMap<ast::Id, Linear>,
+        aux: &mut u32,
+        comp: &ir::Component,
+    ) -> Res<Linear> {
+        Ok(match body {
+            ast::Expr::Concrete(n) => Linear::constant(*n as i128),
+            ast::Expr::Abstract(p) => {
+                bind.get(p.inner()).cloned().ok_or_else(|| {
+                    format!("parameter `{p}' is not bound in the function")
+                })?
+            }
+            ast::Expr::ParamAccess { .. } => Linear::var(Self::aux(e, aux)),
+            ast::Expr::Op { op, left, right } => {
+                let l = self.body(e, left, bind, aux, comp)?;
+                let r = self.body(e, right, bind, aux, comp)?;
+                let var = Self::aux(e, aux);
+                match (op, l.as_constant(), r.as_constant()) {
+                    (ast::Op::Add, _, _) => l.add(&r)?,
+                    (ast::Op::Sub, _, _) => l.sub(&r)?,
+                    (ast::Op::Mul, _, Some(n)) => l.scale(n)?,
+                    (ast::Op::Mul, Some(n), _) => r.scale(n)?,
+                    (ast::Op::Div | ast::Op::Mod, _, Some(n)) if n > 0 => {
+                        let q = self.quotient(var, &l, n)?;
+                        if matches!(op, ast::Op::Div) {
+                            q
+                        } else {
+                            l.sub(&q.scale(n)?)?
+                        }
+                    }
+                    (ast::Op::Mul | ast::Op::Div, _, _) => {
+                        self.nonlinear(var, op, &l, &r)?
+                    }
+                    _ => Linear::var(var),
+                }
+            }
+            ast::Expr::App { func, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| self.body(e, a, bind, aux, comp))
+                    .collect::<Res<Vec<_>>>()?;
+                match *func.inner() {
+                    ast::Fn::User(name) => {
+                        self.inline(e, name, args, aux, comp)?
+                    }
+                    f => self.app(Self::aux(e, aux), f, &args)?,
+                }
+            }
+        })
+    }
+
+    /// Abstract the application of `f` to `args` with the variable `var`.
+    /// The piecewise-linear functions are defined by splitting on the
+    /// comparison of their arguments:
+    /// * `min(a, b)`: `a <= b & t == a | a > b & t == b`
+    /// * `max(a, b)`: `a >= b & t == a | a < b & t == b`
+    /// * `abs_diff(a, b)`: `a >= b & t == a - b | a < b & t == b - a`
+    /// * `ceil_div(a, n)` for a constant `n > 0`: `0 <= n*t - a < n`
+    ///
+    /// Other functions are uninterpreted.
+    fn app(&mut self, var: Var, f: ast::Fn, args: &[Linear]) -> Res<Linear> {
+        let t = Linear::var(var);
+        let case = |cond: ir::Cmp, a: &Linear, b: &Linear, val: Linear| {
+            Ok::<_, String>(Formula::And(vec![
+                Formula::cmp(&cond, a.clone(), b.clone(), true)?,
+                Formula::cmp(&ir::Cmp::Eq, t.clone(), val, true)?,
+            ]))
+        };
+        let defs = match (f, args) {
+            (ast::Fn::Min, [a, b]) => vec![Formula::Or(vec![
+                case(ir::Cmp::Gte, b, a, a.clone())?,
+                case(ir::Cmp::Gt, a, b, b.clone())?,
+            ])],
+            (ast::Fn::Max, [a, b]) => vec![Formula::Or(vec![
+                case(ir::Cmp::Gte, a, b, a.clone())?,
+                case(ir::Cmp::Gt, b, a, b.clone())?,
+            ])],
+            (ast::Fn::AbsDiff, [a, b]) => vec![Formula::Or(vec![
+                case(ir::Cmp::Gte, a, b, a.sub(b)?)?,
+                case(ir::Cmp::Gt, b, a, b.sub(a)?)?,
+            ])],
+            (ast::Fn::CeilDiv, [a, b]) => match b.as_constant() {
+                Some(n) if n > 0 => {
+                    let rem = t.scale(n)?.sub(a)?;
+                    let upper = Linear::constant(n - 1).sub(&rem)?;
+                    vec![Formula::gte_zero(rem), Formula::gte_zero(upper)]
+                }
+                _ => return Ok(t),
+            },
+            _ => return Ok(t),
+        };
+        self.defs.insert(var, defs);
+        Ok(t)
+    }
+
+    /// The next variable for a term of the functions inlined at `e`
+    fn aux(e: ir::ExprIdx, aux: &mut u32) -> Var {
+        *aux += 1;
+        Var::Aux(e, *aux)
+    }
+
+    /// Define the quotient `q` of dividing `l` by `n > 0` using the variable
+    /// `var`: `0 <= l - n*q < n`
+    fn quotient(&mut self, var: Var, l: &Linear, n: i128) -> Res<Linear> {
+        let q = Linear::var(var);
+        if let Entry::Vacant(entry) = self.defs.entry(var) {
+            let rem = l.sub(&q.scale(n)?)?;
+            let upper = Linear::constant(n - 1).sub(&rem)?;
+            entry
+                .insert(vec![Formula::gte_zero(rem), Formula::gte_zero(upper)]);
+        }
+        Ok(q)
+    }
+
+    /// Abstract the non-linear term `l op r` with the variable `var` and
+    /// record lemmas relating the variable to the operands:
+    /// * `l * r`: `l >= 0 & r >= 0 => t >= 0`, `l >= 1 & r >= 0 => t >= r`
+    ///   and `r >= 1 & l >= 0 => t >= l`
+    /// * `l / r`: `r >= 1 & l >= 0 => 0 <= t <= l` and
+    ///   `r >= 1 & l >= r => t >= 1`
+    fn nonlinear(
+        &mut self,
+        var: Var,
+        op: &ast::Op,
+        l: &Linear,
+        r: &Linear,
+    ) -> Res<Linear> {
+        let t = Linear::var(var);
+        let one = Linear::constant(1);
+        let zero = Linear::default();
+        // Encodes `lhs >= rhs`
+        let gte = |lhs: &Linear, rhs: &Linear| lhs.sub(rhs).map(Formula::Atom);
+        // Encodes `lhs < rhs`
+        let lt = |lhs: &Linear, rhs: &Linear| {
+            rhs.sub(lhs)?.sub(&one).map(Formula::Atom)
+        };
+        let lemmas = match op {
+            ast::Op::Mul => vec![
+                Formula::Or(vec![
+                    lt(l, &zero)?,
+                    lt(r, &zero)?,
+                    gte(&t, &zero)?,
+                ]),
+                Formula::Or(vec![lt(l, &one)?, lt(r, &zero)?, gte(&t, r)?]),
+                Formula::Or(vec![lt(r, &one)?, lt(l, &zero)?, gte(&t, l)?]),
+            ],
+            ast::Op::Div => vec![
+                Formula::Or(vec![
+                    lt(r, &one)?,
+                    lt(l, &zero)?,
+                    Formula::And(vec![gte(&t, &zero)?, gte(l, &t)?]),
+                ]),
+                Formula::Or(vec![lt(r, &one)?, lt(l, r)?, gte(&t, &one)?]),
+            ],
+            _ => unreachable!("{op} is not a non-linear operator"),
+        };
+        self.defs.insert(var, lemmas);
+        Ok(t)
+    }
+
+    fn time(&mut self, t: ir::TimeIdx, comp: &ir::Component) -> Res<Linear> {
+        let ir::Time { event, offset } = comp.get(t);
+        Ok(Linear::var(Var::Event(*event)).add(&self.expr(*offset, comp)?)?)
+    }
+
+    fn timesub(
+        &mut self,
+        ts: &ir::TimeSub,
+        comp: &ir::Component,
+    ) -> Res<Linear> {
+        match ts {
+            ir::TimeSub::Unit(e) => self.expr(*e, comp),
+            ir::TimeSub::Sym { l, r } => {
+                Ok(self.time(*l, comp)?.sub(&self.time(*r, comp)?)?)
+            }
+        }
+    }
+
+    /// Encode the proposition if `positive` and its negation otherwise.
+    fn prop(
+        &mut self,
+        p: ir::PropIdx,
+        positive: bool,
+        comp: &ir::Component,
+    ) -> Res<Formula> {
+        Ok(match comp.get(p) {
+            ir::Prop::True if positive => Formula::And(vec![]),
+            ir::Prop::False if !positive => Formula::And(vec![]),
+            ir::Prop::True | ir::Prop::False => Formula::Or(vec![]),
+            ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = self.expr(*lhs, comp)?;
+                let r = self.expr(*rhs, comp)?;
+                Formula::cmp(op, l, r, positive)?
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = self.time(*lhs, comp)?;
+                let r = self.time(*rhs, comp)?;
+                Formula::cmp(op, l, r, positive)?
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = self.timesub(lhs, comp)?;
+                let r = self.timesub(rhs, comp)?;
+                Formula::cmp(op, l, r, positive)?
+            }
+            ir::Prop::Not(p) => self.prop(*p, !positive, comp)?,
+            ir::Prop::And(l, r) | ir::Prop::Or(l, r) => {
+                let fs = vec![
+                    self.prop(*l, positive, comp)?,
+                    self.prop(*r, positive, comp)?,
+                ];
+                if matches!(comp.get(p), ir::Prop::And(..)) == positive {
+                    Formula::And(fs)
+                } else {
+                    Formula::Or(fs)
+                }
+            }
+            ir::Prop::Implies(l, r) => {
+                let fs = vec![
+                    self.prop(*l, !positive, comp)?,
+                    self.prop(*r, positive, comp)?,
+                ];
+                if positive {
+                    Formula::Or(fs)
+                } else {
+                    Formula::And(fs)
+                }
+            }
+        })
+    }
+
+    /// Search for an integer model of the conjunction of `facts` and `pending`.
+    /// Disjunctions are handled by splitting on their alternatives.
+    fn search(
+        &mut self,
+        mut facts: Vec<Linear>,
+        pending: Vec<&Formula>,
+    ) -> Res<Option<Model>> {
+        let mut disjs: Vec<&[Formula]> = Vec::new();
+        let mut stack = pending;
+        while let Some(f) = stack.pop() {
+            match f {
+                Formula::Atom(l) => facts.push(l.clone()),
+                Formula::And(fs) => stack.extend(fs),
+                Formula::Or(fs) if fs.len() == 1 => stack.push(&fs[0]),
+                Formula::Or(fs) => disjs.push(fs),
+            }
+        }
+
+        if disjs.iter().any(|fs| fs.is_empty()) {
+            return Ok(None);
+        }
+        let Some(stages) = eliminate(facts.clone())? else {
+            return Ok(None);
+        };
+        if disjs.is_empty() {
+            return integer_model(stages).map(Some);
+        }
+
+        // Split on the disjunction with the fewest alternatives
+        let (idx, _) = disjs
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, fs)| fs.len())
+            .unwrap();
+        let split = disjs.swap_remove(idx);
+        for alt in split {
+            self.splits += 1;
+            if self.splits > MAX_SPLITS {
+                return Err(format!(
+                    "exceeded the limit of {MAX_SPLITS} case splits"
+                ));
+            }
+            if self.deadline.map_or(false, |d| Instant::now() > d) {
+                return Err(format!(
+                    "timed out after {}ms",
+                    self.timeout.unwrap().as_millis()
+                ));
+            }
+            // The remaining disjunctions are re-processed in the new branch
+            let rest = disjs.iter().map(|fs| Formula::Or(fs.to_vec()));
+            let rest = rest.collect::<Vec<_>>();
+            let next = iter::once(alt).chain(&rest).collect();
+            if let Some(model) = self.search(facts.clone(), next)? {
+                return Ok(Some(model));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Fourier-Motzkin elimination over the rationals. Returns `None` if the
+/// constraints are unsatisfiable.
+fn eliminate(cons: Vec<Linear>) -> Res<Option<Stages>> {
+    let mut cur: HashSet<Linear> = HashSet::with_capacity(cons.len());
+    for c in cons {
+        match normalize(c) {
+            Norm::Trivial => (),
+            Norm::Contradiction => return Ok(None),
+            Norm::Cons(c) => {
+                cur.insert(c);
+            }
+        }
+    }
+
+    let mut stages = Vec::new();
+    loop {
+        // Pick the variable that generates the fewest new constraints
+        let mut bounds: BTreeMap<Var, (usize, usize)> = BTreeMap::new();
+        for c in &cur {
+            for (v, a) in c.coeffs() {
+                let (lo, hi) = bounds.entry(v).or_default();
+                if a > 0 {
+                    *lo += 1
+                } else {
+                    *hi += 1
+                }
+            }
+        }
+        let Some((&var, _)) = bounds.iter().min_by_key(|(_, (lo, hi))| lo * hi)
+        else {
+            break;
+        };
+
+        let (with, without): (Vec<_>, Vec<_>) =
+            cur.into_iter().partition(|c| c.coeff(var) != 0);
+        cur = without.into_iter().collect();
+        for lo in with.iter().filter(|c| c.coeff(var) > 0) {
+            for hi in with.iter().filter(|c| c.coeff(var) < 0) {
+                let a = lo.coeff(var);
+                let b = -hi.coeff(var);
+                let comb = lo.scale(b)?.add(&hi.scale(a)?)?;
+                match normalize(comb) {
+                    Norm::Trivial => (),
+                    Norm::Contradiction => return Ok(None),
+                    Norm::Cons(c) => {
+                        cur.insert(c);
+                    }
+                }
+            }
+        }
+        if cur.len() > MAX_CONSTRAINTS {
+            return Err(format!(
+                "variable elimination generated more than {MAX_CONSTRAINTS} constraints"
+            ));
+        }
+        stages.push((var, with));
+    }
+
+    Ok(Some(stages))
+}
+
+/// Build an integer model by assigning the eliminated variables in reverse
+/// order. Each variable is assigned the value closest to zero that satisfies
+/// its bounds.
+fn integer_model(stages: Stages) -> Res<Model> {
+    let mut model = Model(HashMap::new());
+    for (var, cons) in stages.into_iter().rev() {
+        let (mut lo, mut hi) = (None::<i128>, None::<i128>);
+        for c in cons {
+            let a = c.coeff(var);
+            let rest = c
+                .coeffs()
+                .filter(|(v, _)| *v != var)
+                .try_fold(c.constant_term(), |acc, (v, n)| {
+                    n.checked_mul(model.get(v)).and_then(|n| n.checked_add(acc))
+                })
+                .ok_or_else(|| "overflow while building model".to_string())?;
+            if a > 0 {
+                // a*x + rest >= 0  <=>  x >= ceil(-rest / a)
+                let b = -((rest).div_euclid(a));
+                lo = Some(lo.map_or(b, |lo| lo.max(b)));
+            } else {
+                // a*x + rest >= 0  <=>  x <= floor(rest / -a)
+                let b = rest.div_euclid(-a);
+                hi = Some(hi.map_or(b, |hi| hi.min(b)));
+            }
+        }
+        let val = match (lo, hi) {
+            (Some(lo), Some(hi)) if lo > hi => {
+                return Err("could not find an integer model".to_string())
+            }
+            (lo, hi) => 0.max(lo.unwrap_or(0)).min(hi.unwrap_or(i128::MAX)),
+        };
+        model.0.insert(var, val);
+    }
+    Ok(model)
+}
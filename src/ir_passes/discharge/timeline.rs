@@ -0,0 +1,102 @@
+//! Cycle-by-cycle timelines for failing liveness constraints.
+//!
+//! The times in the constraint are evaluated under the counterexample and
+//! drawn as rows of cells, one per cycle, marking the triggers of the events
+//! along with the cycles in which the destination requires the value and the
+//! source provides it.
+use super::presburger::Model;
+use fil_ir::{self as ir, linear, Ctx, DisplayCtx};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::iter;
+
+/// Maximum number of cycles drawn in a timeline
+const MAX_CYCLES: i128 = 40;
+
+/// Render the timeline of a failing liveness constraint. Returns `None` if the
+/// reason is not a liveness constraint or if its times cannot be evaluated
+/// or span too many cycles.
+pub fn render(
+    reason: &ir::info::Reason,
+    vals: &HashMap<linear::Var, i128>,
+    ctx: &ir::Component,
+) -> Option<String> {
+    let ir::info::Reason::Liveness {
+        dst_liveness,
+        src_liveness,
+        ..
+    } = reason
+    else {
+        return None;
+    };
+    let model = Model::from_values(vals);
+    let window = |r: &ir::Range| {
+        Some((model.time(r.start, ctx)?, model.time(r.end, ctx)?))
+    };
+    let req = window(dst_liveness)?;
+    let avail = window(src_liveness)?;
+    let events = [dst_liveness, src_liveness]
+        .iter()
+        .flat_map(|r| [r.start, r.end])
+        .map(|t| ctx.get(t).event)
+        .unique()
+        .map(|e| (ctx.display(e), model.value(linear::Var::Event(e))))
+        .collect_vec();
+
+    let lo = events
+        .iter()
+        .map(|(_, v)| *v)
+        .chain([req.0, avail.0])
+        .min()?;
+    let hi = events
+        .iter()
+        .map(|(_, v)| *v + 1)
+        .chain([req.1, avail.1])
+        .max()?;
+    if hi - lo > MAX_CYCLES {
+        return None;
+    }
+    let cycles = (lo..hi).collect_vec();
+    let within = |(s, e): (i128, i128), c: i128| s <= c && c < e;
+
+    // Marks fill their cells so that windows are drawn as bars
+    let width = cycles.iter().map(|c| c.to_string().len()).max()?;
+    let bar = |mark: &str| mark.repeat(width);
+    let mut rows = vec![(
+        "cycle".to_string(),
+        cycles.iter().map(|c| c.to_string()).collect_vec(),
+    )];
+    for (name, v) in events {
+        let cells = cycles
+            .iter()
+            .map(|c| if *c == v { "^" } else { "" }.to_string());
+        rows.push((name, cells.collect()));
+    }
+    let cells =
+        cycles
+            .iter()
+            .map(|c| match (within(req, *c), within(avail, *c)) {
+                (true, true) => bar("="),
+                (true, false) => bar("!"),
+                _ => String::new(),
+            });
+    rows.push(("required".to_string(), cells.collect()));
+    let cells = cycles.iter().map(|c| {
+        if within(avail, *c) {
+            bar("=")
+        } else {
+            String::new()
+        }
+    });
+    rows.push(("available".to_string(), cells.collect()));
+
+    let label_width = rows.iter().map(|(l, _)| l.len()).max()?;
+    let lines = rows.into_iter().map(|(label, cells)| {
+        let cells = cells.iter().map(|c| format!("{c:<width$}")).join(" ");
+        format!("{label:<label_width$} | {cells}")
+            .trim_end()
+            .to_string()
+    });
+    let header = "Timeline of the counterexample (`!` marks cycles where the value is required but not available):";
+    Some(iter::once(header.to_string()).chain(lines).join("\n"))
+}
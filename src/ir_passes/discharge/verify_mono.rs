@@ -0,0 +1,186 @@
+use super::Presburger;
+use crate::cmdline;
+use crate::ir_visitor::{Action, Construct, Visitor, VisitorData};
+use codespan_reporting::diagnostic as cr;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use itertools::Itertools;
+use std::cmp::Ordering;
+
+/// Check the facts of monomorphized components. Run
+/// [super::super::Monomorphize::transform_with_facts] and
+/// [super::super::HoistFacts] before this pass.
+///
+/// All parameters of a monomorphized component are concrete, so most facts
+/// can be evaluated directly. Facts that still mention events or bundle
+/// parameters are linear and are decided by the in-process solver.
+/// The facts are removed from the components once they have been checked.
+pub struct VerifyMono {
+    /// Number of errors encountered
+    error_count: u64,
+}
+
+impl VerifyMono {
+    /// Evaluate a proposition if it does not depend on the values of events
+    /// or parameters.
+    fn eval(prop: ir::PropIdx, comp: &ir::Component) -> Option<bool> {
+        match comp.get(prop) {
+            ir::Prop::True => Some(true),
+            ir::Prop::False => Some(false),
+            ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = lhs.as_concrete(comp)?;
+                let r = rhs.as_concrete(comp)?;
+                Some(Self::cmp(op, l.cmp(&r)))
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = comp.get(*lhs);
+                let r = comp.get(*rhs);
+                if l.event != r.event {
+                    return None;
+                }
+                let l = l.offset.as_concrete(comp)?;
+                let r = r.offset.as_concrete(comp)?;
+                Some(Self::cmp(op, l.cmp(&r)))
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { op, lhs, rhs }) => {
+                let l = Self::time_sub(lhs, comp)?;
+                let r = Self::time_sub(rhs, comp)?;
+                Some(Self::cmp(op, l.cmp(&r)))
+            }
+            ir::Prop::Not(p) => Self::eval(*p, comp).map(|b| !b),
+            ir::Prop::And(l, r) => {
+                match (Self::eval(*l, comp), Self::eval(*r, comp)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            ir::Prop::Or(l, r) => {
+                match (Self::eval(*l, comp), Self::eval(*r, comp)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            ir::Prop::Implies(l, r) => {
+                match (Self::eval(*l, comp), Self::eval(*r, comp)) {
+                    (Some(false), _) | (_, Some(true)) => Some(true),
+                    (Some(true), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Value of a time difference if it does not depend on the events
+    fn time_sub(ts: &ir::TimeSub, comp: &ir::Component) -> Option<i128> {
+        match ts {
+            ir::TimeSub::Unit(e) => e.as_concrete(comp).map(|n| n as i128),
+            ir::TimeSub::Sym { l, r } => {
+                let l = comp.get(*l);
+                let r = comp.get(*r);
+                if l.event != r.event {
+                    return None;
+                }
+                let lo = l.offset.as_concrete(comp)?;
+                let ro = r.offset.as_concrete(comp)?;
+                Some(lo as i128 - ro as i128)
+            }
+        }
+    }
+
+    fn cmp(op: &ir::Cmp, ord: Ordering) -> bool {
+        match op {
+            ir::Cmp::Gt => ord == Ordering::Greater,
+            ir::Cmp::Gte => ord != Ordering::Less,
+            ir::Cmp::Eq => ord == Ordering::Equal,
+        }
+    }
+
+    /// Check a fact and return the diagnostic if it does not hold
+    fn check(
+        fact: &ir::Fact,
+        comp: &ir::Component,
+    ) -> Option<cr::Diagnostic<usize>> {
+        let prop = fact.prop;
+        let why = match Self::eval(prop, comp) {
+            Some(true) => return None,
+            Some(false) => None,
+            None => match Presburger::new(None).check(prop, comp) {
+                Ok(None) => return None,
+                Ok(Some(_)) => None,
+                Err(why) => Some(why),
+            },
+        };
+
+        let reason = comp
+            .get(fact.reason)
+            .as_assert()
+            .map(|ir::info::Assert(reason)| reason);
+        let mut diag = match reason {
+            Some(reason) => reason.diag(comp),
+            None => cr::Diagnostic::error().with_notes(vec![
+                "No information was given on who generated this error"
+                    .to_string(),
+            ]),
+        };
+        let cons = prop.consequent(comp);
+        if let Some(why) = why {
+            let msg = format!("could not decide constraint: {}", diag.message);
+            diag = diag.with_message(msg).with_notes(vec![
+                format!("Cannot decide constraint: {}", comp.display(cons)),
+                format!("builtin solver gave up: {why}"),
+            ]);
+        } else if !cons.is_false(comp) {
+            // Constraints on parameters are already evaluated to `false'
+            diag = diag.with_notes(vec![format!(
+                "Cannot prove constraint: {}",
+                comp.display(cons)
+            )]);
+        }
+        if !comp.mono_binding.is_empty() {
+            let binding = comp
+                .mono_binding
+                .iter()
+                .map(|(p, v)| format!("{p} = {v}"))
+                .join(", ");
+            diag = diag.with_notes(vec![format!(
+                "Constraint fails for the instance with {binding}"
+            )]);
+        }
+        Some(diag)
+    }
+}
+
+impl Construct for VerifyMono {
+    fn from(_: &cmdline::Opts, _: &mut ir::Context) -> Self {
+        Self { error_count: 0 }
+    }
+
+    fn clear_data(&mut self) {}
+}
+
+impl Visitor for VerifyMono {
+    fn name() -> &'static str {
+        "verify-mono"
+    }
+
+    fn fact(&mut self, f: &mut ir::Fact, data: &mut VisitorData) -> Action {
+        assert!(
+            f.is_assert(),
+            "assumptions should have been eliminated by `hoist-facts` pass"
+        );
+        if let Some(diag) = Self::check(f, &data.comp) {
+            fil_utils::emit(&diag, &[]);
+            self.error_count += 1;
+        }
+        Action::Change(vec![])
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        if self.error_count > 0 {
+            Some(self.error_count)
+        } else {
+            None
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ir_passes::lower::max_states;
+use crate::ir_passes::max_states;
 use fil_ir::{self as ir, Ctx, DisplayCtx};
 use itertools::Itertools;
 
@@ -84,12 +84,34 @@ impl DumpInterface {
             )
         };
 
+        // For each parameter bound by the signature like:
+        // W: 1..64
+        // Generate the JSON information:
+        // {
+        //   "name": "W",
+        //   "range": "1..64"
+        // }
+        let params = main
+            .params()
+            .iter()
+            .filter(|(_, p)| p.is_sig_owned())
+            .map(|(_, p)| {
+                let info: &ir::info::Param = main.get(p.info).into();
+                let range = info
+                    .range
+                    .as_ref()
+                    .map_or("null".to_string(), |r| format!("\"{r}\""));
+                format!("{{ \"name\": \"{}\", \"range\": {range} }}", info.name)
+            })
+            .collect_vec()
+            .join(",\n");
+
         let inputs = main.inputs().map(pd_to_info).collect_vec().join(",\n");
         let outputs = main.outputs().map(pd_to_info).collect_vec().join(",\n");
 
         // Look ma, a JSON serializer!
         println!(
-            "{{\n\"interfaces\": [\n{interfaces}\n],\n\"inputs\": [\n{inputs}\n],\n\"outputs\": [\n{outputs}\n]\n}}",
+            "{{\n\"interfaces\": [\n{interfaces}\n],\n\"params\": [\n{params}\n],\n\"inputs\": [\n{inputs}\n],\n\"outputs\": [\n{outputs}\n]\n}}",
         );
     }
 }
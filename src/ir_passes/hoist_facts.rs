@@ -54,8 +54,13 @@ impl Visitor for HoistFacts {
         data: &mut VisitorData,
     ) {
         let ctx = &mut data.comp;
+        let top = self.stack.is_empty();
         cmds.iter().for_each(|cmd| match cmd {
             ir::Command::Fact(fact) if fact.is_assume() => {
+                ctx.assume_info.push((fact.prop, fact.reason));
+                if top {
+                    ctx.top_assumes.push(fact.prop);
+                }
                 self.add_to_pc(fact.prop)
             }
             ir::Command::Exists(ir::Exists { param, expr }) => {
@@ -191,6 +191,10 @@ impl Visitor for IntervalCheck {
     ) -> Action {
         let comp = &mut data.comp;
         let ir::Connect { src, dst, info } = con;
+        // Constants are available at all times
+        let ir::Source::Port(src) = src else {
+            return Action::Continue;
+        };
         let src_t = src.bundle_typ(comp);
         let dst_t = dst.bundle_typ(comp);
         let in_range = Self::in_range(&dst_t, comp)
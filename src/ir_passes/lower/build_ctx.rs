@@ -1,7 +1,8 @@
 use super::fsm::{FsmBind, FsmType};
-use super::utils::{cell_to_port_def, NameGenerator};
+use super::utils::{cell_to_port_def, NameGenerator, CHUNK_WIDTH, CONCAT};
 use super::Fsm;
 use calyx_ir::{self as calyx, RRC};
+use fil_ast as ast;
 use fil_ir::{self as ir, Ctx, DenseIndexInfo, DisplayCtx};
 use itertools::Itertools;
 use std::{collections::HashMap, rc::Rc};
@@ -202,15 +203,56 @@ impl<'a> BuildCtx<'a> {
         (cell.get(name), guard)
     }
 
+    /// Builds the cells producing a constant of the given width. Constants of
+    /// 64 or more bits are built by concatenating smaller chunks.
+    fn compile_constant(
+        &mut self,
+        c: &ast::Constant,
+        width: u64,
+    ) -> RRC<calyx::Port> {
+        if width < 64 {
+            let cell = self.builder.add_constant(c.extract(0, width), width);
+            let out = cell.borrow().get("out");
+            return out;
+        }
+
+        // Chunks of the constant starting from the least significant bits
+        let mut chunks = (0..width).step_by(CHUNK_WIDTH as usize).map(|lo| {
+            let w = (width - lo).min(CHUNK_WIDTH);
+            (self.builder.add_constant(c.extract(lo, w), w), w)
+        });
+        let (low, mut acc_w) = chunks.next().unwrap();
+        let mut acc = low.borrow().get("out");
+        for (chunk, w) in chunks.collect_vec() {
+            let cat = self.builder.add_primitive(
+                "concat",
+                CONCAT,
+                &[w, acc_w, w + acc_w],
+            );
+            let left = self.builder.build_assignment(
+                cat.borrow().get("left"),
+                chunk.borrow().get("out"),
+                calyx::Guard::True,
+            );
+            let right = self.builder.build_assignment(
+                cat.borrow().get("right"),
+                acc,
+                calyx::Guard::True,
+            );
+            self.builder
+                .component
+                .continuous_assignments
+                .extend([left, right]);
+            acc = cat.borrow().get("out");
+            acc_w += w;
+        }
+        acc
+    }
+
     /// Compiles an [ir::Connect] by building the port assignments in calyx
     pub fn compile_connect(&mut self, con: &ir::Connect) {
         let ir::Connect { dst, src, .. } = con;
 
-        assert!(
-            src.is_port(self.comp),
-            "Bundles should have been compiled away."
-        );
-
         assert!(
             dst.is_port(self.comp),
             "Bundles should have been compiled away."
@@ -218,10 +260,25 @@ impl<'a> BuildCtx<'a> {
 
         log::debug!("Compiling connect: {}", self.comp.display(con));
 
-        // ignores the guard of the destination (bind check already verifies that it is available for at least as long as src)
-        let (dst, _) = self.compile_port(dst.port);
-        let (src, g) = self.compile_port(src.port);
-        let assign = self.builder.build_assignment(dst, src, g);
+        let assign = match src {
+            ir::Source::Port(src) => {
+                assert!(
+                    src.is_port(self.comp),
+                    "Bundles should have been compiled away."
+                );
+                // ignores the guard of the destination (bind check already verifies that it is available for at least as long as src)
+                let (dst, _) = self.compile_port(dst.port);
+                let (src, g) = self.compile_port(src.port);
+                self.builder.build_assignment(dst, src, g)
+            }
+            // constants are always available so they are driven whenever the destination requires them
+            ir::Source::Const(c) => {
+                let (dst, g) = self.compile_port(dst.port);
+                let width = dst.borrow().width;
+                let src = self.compile_constant(c, width);
+                self.builder.build_assignment(dst, src, g)
+            }
+        };
         self.builder.component.continuous_assignments.push(assign);
     }
 
@@ -1,7 +1,7 @@
 use super::{
     build_ctx::{Binding, BuildCtx},
     max_states,
-    utils::{NameGenerator, INTERFACE_PORTS},
+    utils::{has_wide_constants, NameGenerator, CONCAT_DEF, INTERFACE_PORTS},
 };
 use calyx_frontend as frontend;
 use calyx_ir as calyx;
@@ -286,6 +286,16 @@ impl Compile {
             }
         }
 
+        // Add the primitive used to build wide constants
+        if ctx.iter().any(|(_, comp)| has_wide_constants(comp)) {
+            let mut ns =
+                frontend::NamespaceDef::construct_from_str(CONCAT_DEF)?;
+            let (_, prims) = ns.externs.pop().unwrap();
+            for prim in prims {
+                ws.lib.add_inline_primitive(prim);
+            }
+        }
+
         // define a fake main component (needed to generate the ir calyx context)
         let main =
             frontend::ast::ComponentDef::new("main", false, None, vec![]);
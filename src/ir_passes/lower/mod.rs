@@ -1,10 +1,12 @@
 mod build_ctx;
 mod compile;
 mod fsm;
+mod sv;
 mod utils;
 
 use build_ctx::BuildCtx;
 use fsm::{Fsm, FsmType};
 
 pub use compile::Compile;
+pub use sv::CompileSv;
 pub use utils::max_states;
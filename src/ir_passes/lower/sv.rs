@@ -0,0 +1,1104 @@
+use super::{max_states, FsmType};
+use fil_ir::{self as ir, Ctx, DisplayCtx, Traversal};
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fs,
+    io::{self, Write},
+};
+
+/// Clock and reset ports added to every non-external module
+const CLOCK_PORTS: [&str; 2] = ["clk", "reset"];
+
+/// SystemVerilog keywords that cannot be used as identifiers
+const KEYWORDS: [&str; 248] = [
+    "accept_on",
+    "alias",
+    "always",
+    "always_comb",
+    "always_ff",
+    "always_latch",
+    "and",
+    "assert",
+    "assign",
+    "assume",
+    "automatic",
+    "before",
+    "begin",
+    "bind",
+    "bins",
+    "binsof",
+    "bit",
+    "break",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "byte",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "chandle",
+    "checker",
+    "class",
+    "clocking",
+    "cmos",
+    "config",
+    "const",
+    "constraint",
+    "context",
+    "continue",
+    "cover",
+    "covergroup",
+    "coverpoint",
+    "cross",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "dist",
+    "do",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endchecker",
+    "endclass",
+    "endclocking",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endgroup",
+    "endinterface",
+    "endmodule",
+    "endpackage",
+    "endprimitive",
+    "endprogram",
+    "endproperty",
+    "endspecify",
+    "endsequence",
+    "endtable",
+    "endtask",
+    "enum",
+    "event",
+    "eventually",
+    "expect",
+    "export",
+    "extends",
+    "extern",
+    "final",
+    "first_match",
+    "for",
+    "force",
+    "foreach",
+    "forever",
+    "fork",
+    "forkjoin",
+    "function",
+    "generate",
+    "genvar",
+    "global",
+    "highz0",
+    "highz1",
+    "if",
+    "iff",
+    "ifnone",
+    "ignore_bins",
+    "illegal_bins",
+    "implements",
+    "implies",
+    "import",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "inside",
+    "instance",
+    "int",
+    "integer",
+    "interconnect",
+    "interface",
+    "intersect",
+    "join",
+    "join_any",
+    "join_none",
+    "large",
+    "let",
+    "liblist",
+    "library",
+    "local",
+    "localparam",
+    "logic",
+    "longint",
+    "macromodule",
+    "matches",
+    "medium",
+    "modport",
+    "module",
+    "nand",
+    "negedge",
+    "nettype",
+    "new",
+    "nexttime",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "null",
+    "or",
+    "output",
+    "package",
+    "packed",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "priority",
+    "program",
+    "property",
+    "protected",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "pure",
+    "rand",
+    "randc",
+    "randcase",
+    "randsequence",
+    "rcmos",
+    "real",
+    "realtime",
+    "ref",
+    "reg",
+    "reject_on",
+    "release",
+    "repeat",
+    "restrict",
+    "return",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "s_always",
+    "s_eventually",
+    "s_nexttime",
+    "s_until",
+    "s_until_with",
+    "scalared",
+    "sequence",
+    "shortint",
+    "shortreal",
+    "showcancelled",
+    "signed",
+    "small",
+    "soft",
+    "solve",
+    "specify",
+    "specparam",
+    "static",
+    "string",
+    "strong",
+    "strong0",
+    "strong1",
+    "struct",
+    "super",
+    "supply0",
+    "supply1",
+    "sync_accept_on",
+    "sync_reject_on",
+    "table",
+    "tagged",
+    "task",
+    "this",
+    "throughout",
+    "time",
+    "timeprecision",
+    "timeunit",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "type",
+    "typedef",
+    "union",
+    "unique",
+    "unique0",
+    "unsigned",
+    "until",
+    "until_with",
+    "untyped",
+    "use",
+    "uwire",
+    "var",
+    "vectored",
+    "virtual",
+    "void",
+    "wait",
+    "wait_order",
+    "wand",
+    "weak",
+    "weak0",
+    "weak1",
+    "while",
+    "wildcard",
+    "wire",
+    "with",
+    "within",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Generates unique identifiers for a scope from source-level names
+struct Namespace {
+    used: HashSet<String>,
+    /// Rename source-level names that are SystemVerilog keywords
+    sanitize: bool,
+}
+
+impl Namespace {
+    fn new(sanitize: bool) -> Self {
+        Self {
+            used: HashSet::new(),
+            sanitize,
+        }
+    }
+
+    /// Reserve a name that must be used as is
+    fn reserve(&mut self, name: impl ToString) -> String {
+        let name = name.to_string();
+        self.used.insert(name.clone());
+        name
+    }
+
+    /// Returns `base` if it is not used yet and `base_1`, `base_2`, ...
+    /// otherwise.
+    fn fresh(&mut self, base: &str) -> String {
+        let base = if self.sanitize && KEYWORDS.contains(&base) {
+            format!("{base}_")
+        } else {
+            base.to_string()
+        };
+        let mut name = base.clone();
+        let mut n = 1;
+        while !self.used.insert(name.clone()) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        name
+    }
+}
+
+/// The kind of a port in the signature of a module
+enum PortKind {
+    /// A port defined by the signature of the component
+    Data(ir::PortIdx),
+    /// A port without a timing annotation
+    Unannotated(u64),
+    /// The interface port of an event
+    Interface(ir::EventIdx),
+    /// Clock or reset port added by the compiler
+    Clock,
+}
+
+/// A port in the signature of a module
+struct SigPort {
+    name: String,
+    kind: PortKind,
+    input: bool,
+}
+
+/// Returns the ports of the module generated for a component in the same
+/// order as the Calyx backend. The toplevel and external components keep the
+/// names from their source interface. Other components use the surface-level
+/// names of their ports and events.
+fn signature(comp: &ir::Component, sanitize: bool) -> Vec<SigPort> {
+    let mut ns = Namespace::new(sanitize);
+    for (name, _) in comp.unannotated_ports.iter() {
+        ns.reserve(name);
+    }
+    if !comp.is_ext {
+        for name in CLOCK_PORTS {
+            ns.reserve(name);
+        }
+    }
+
+    let data = comp
+        .ports()
+        .idx_iter()
+        .filter_map(|idx| match &comp.get(idx).owner {
+            ir::PortOwner::Sig { dir } => Some((idx, dir.is_out())),
+            _ => None,
+        })
+        .collect_vec();
+    // Bundles are split into ports with the same name so we number them
+    let bases = data
+        .iter()
+        .map(|(idx, _)| {
+            comp.get(*idx)
+                .info
+                .get_name(comp)
+                .unwrap_or_else(|| "p".to_string())
+        })
+        .collect_vec();
+    let counts = bases.iter().counts();
+    let mut seen: HashMap<&String, usize> = HashMap::new();
+    let mut ports = data
+        .iter()
+        .zip(&bases)
+        .map(|(&(idx, input), base)| {
+            let name = match &comp.src_info {
+                Some(src) => src.ports.get(idx).to_string(),
+                None if counts[base] > 1 => {
+                    let n = seen.entry(base).or_default();
+                    *n += 1;
+                    ns.fresh(&format!("{base}_{}", *n - 1))
+                }
+                None => ns.fresh(base),
+            };
+            SigPort {
+                name,
+                kind: PortKind::Data(idx),
+                // Signature ports are represented from the perspective of the body
+                input,
+            }
+        })
+        .chain(comp.unannotated_ports.iter().map(|(name, width)| SigPort {
+            name: name.to_string(),
+            kind: PortKind::Unannotated(*width),
+            input: true,
+        }))
+        .collect_vec();
+
+    for (idx, ev) in comp.events().iter() {
+        if !ev.has_interface {
+            continue;
+        }
+        let name = match &comp.src_info {
+            Some(src) => src.interface_ports.get(idx).to_string(),
+            None => {
+                let info: &ir::info::Event = comp.get(ev.info).into();
+                ns.fresh(info.interface_name.unwrap_or(info.name).as_ref())
+            }
+        };
+        ports.push(SigPort {
+            name,
+            kind: PortKind::Interface(idx),
+            input: true,
+        });
+    }
+
+    // External modules must keep their signature
+    if !comp.is_ext {
+        for name in CLOCK_PORTS {
+            if !ports.iter().any(|p| p.name == name) {
+                ports.push(SigPort {
+                    name: name.to_string(),
+                    kind: PortKind::Clock,
+                    input: true,
+                });
+            }
+        }
+    }
+    ports
+}
+
+/// Names of the modules and the ports in their signatures
+struct Names {
+    modules: HashMap<ir::CompIdx, String>,
+    sigs: HashMap<ir::CompIdx, Vec<SigPort>>,
+    /// Rename source-level names that are SystemVerilog keywords
+    sanitize: bool,
+}
+
+impl Names {
+    fn new(ctx: &ir::Context, preserve_names: bool) -> Self {
+        let sanitize = !preserve_names;
+        let mut ns = Namespace::new(sanitize);
+        let mut modules = HashMap::new();
+        // The toplevel and external modules keep their names
+        for (idx, comp) in ctx.comps.iter() {
+            if let (true, Some(src)) =
+                (comp.is_ext || ctx.is_main(idx), &comp.src_info)
+            {
+                modules.insert(idx, ns.reserve(src.name));
+            }
+        }
+        // Components instantiated with different parameters have the same
+        // source name so their modules are named after the parameters.
+        let base = |comp: &ir::Component| {
+            comp.src_name
+                .map_or_else(|| "comp".to_string(), |n| n.to_string())
+        };
+        let rest = ctx
+            .comps
+            .iter()
+            .filter(|(idx, _)| !modules.contains_key(idx))
+            .collect_vec();
+        let counts = rest.iter().map(|(_, c)| base(c)).counts();
+        for (idx, comp) in rest {
+            let mut name = base(comp);
+            if counts[&name] > 1 {
+                for (_, v) in &comp.mono_binding {
+                    name = format!("{name}_{v}");
+                }
+            }
+            modules.insert(idx, ns.fresh(&name));
+        }
+
+        let sigs = ctx
+            .comps
+            .iter()
+            .map(|(idx, comp)| (idx, signature(comp, sanitize)))
+            .collect();
+        Self {
+            modules,
+            sigs,
+            sanitize,
+        }
+    }
+
+    /// Name of the module generated for a component
+    fn module(&self, idx: ir::CompIdx) -> &str {
+        &self.modules[&idx]
+    }
+
+    /// Ports in the signature of a component's module
+    fn sig(&self, idx: ir::CompIdx) -> &[SigPort] {
+        &self.sigs[&idx]
+    }
+
+    /// Name of a signature port of a component
+    fn port(&self, comp: ir::CompIdx, port: ir::PortIdx) -> &str {
+        self.sig(comp)
+            .iter()
+            .find(|p| matches!(p.kind, PortKind::Data(idx) if idx == port))
+            .map(|p| p.name.as_str())
+            .unwrap()
+    }
+
+    /// Name of the interface port of an event of a component, if it exists
+    fn interface(&self, comp: ir::CompIdx, ev: ir::EventIdx) -> Option<&str> {
+        self.sig(comp)
+            .iter()
+            .find(|p| matches!(p.kind, PortKind::Interface(idx) if idx == ev))
+            .map(|p| p.name.as_str())
+    }
+}
+
+/// Declaration of a signal with the given width
+fn logic(width: u64, name: &str) -> String {
+    if width == 1 {
+        format!("logic {name}")
+    } else {
+        format!("logic [{}:0] {name}", width - 1)
+    }
+}
+
+/// Wraps a guard in parentheses if it is a disjunction
+fn paren(parts: Vec<String>) -> String {
+    if parts.len() == 1 {
+        parts.into_iter().next().unwrap()
+    } else {
+        format!("({})", parts.join(" || "))
+    }
+}
+
+/// An FSM that reifies an event with an interface port.
+struct Fsm {
+    /// Name of the interface port that triggers the FSM
+    go: String,
+    typ: FsmType,
+    /// Names of the registers holding the state of the FSM. Shift registers
+    /// use one register while counter chains use one per counter.
+    regs: Vec<String>,
+    /// Names of the signals that start each counter after the first
+    dones: Vec<String>,
+}
+
+impl Fsm {
+    fn new(go: String, typ: FsmType, ns: &mut Namespace) -> Self {
+        let (regs, dones) = match typ {
+            // State 0 is the interface port itself
+            FsmType::Simple(states) if states <= 1 => (vec![], vec![]),
+            FsmType::Simple(_) => {
+                (vec![ns.fresh(&format!("{go}_fsm"))], vec![])
+            }
+            FsmType::Counter(_) => {
+                unreachable!("counters are only used within counter chains")
+            }
+            FsmType::CounterChain(states, delay) => {
+                let num = Self::counters(states, delay);
+                let regs = (0..num)
+                    .map(|n| ns.fresh(&format!("{go}_fsm{n}")))
+                    .collect_vec();
+                let dones = regs[..regs.len() - 1]
+                    .iter()
+                    .map(|r| ns.fresh(&format!("{r}_done")))
+                    .collect();
+                (regs, dones)
+            }
+        };
+        Self {
+            go,
+            typ,
+            regs,
+            dones,
+        }
+    }
+
+    /// Number of counters needed for a chain with the given number of states
+    fn counters(states: u64, delay: u64) -> u64 {
+        states / delay + (states % delay != 0) as u64
+    }
+
+    /// Name of the register holding the state of the `n`th counter.
+    fn counter(&self, n: u64) -> &str {
+        &self.regs[n as usize]
+    }
+
+    /// Signal that starts the `n`th counter.
+    fn counter_go(&self, n: u64) -> &str {
+        if n == 0 {
+            &self.go
+        } else {
+            &self.dones[n as usize - 1]
+        }
+    }
+
+    /// Number of bits needed to represent the states of a counter
+    fn bitwidth(states: u64) -> u64 {
+        (64 - (states - 1).leading_zeros()) as u64
+    }
+
+    /// Adds the declarations of the FSM's registers and returns the logic
+    /// implementing it. Returns `None` if no registers are needed.
+    fn emit(&self, decls: &mut Vec<String>) -> Option<String> {
+        match self.typ {
+            FsmType::Simple(states) => {
+                // State 0 is the interface port itself
+                if states <= 1 {
+                    return None;
+                }
+                let fsm = &self.regs[0];
+                decls.push(format!("  logic [{}:1] {fsm};", states - 1));
+                let prev = if states == 2 {
+                    self.go.clone()
+                } else {
+                    format!("{{{fsm}[{}:1], {}}}", states - 2, self.go)
+                };
+                Some(format!(
+                    "  // `{fsm}[i]' is high `i' cycles after `{go}'\n  \
+                     always_ff @(posedge clk) begin\n    \
+                     if (reset) {fsm} <= '0;\n    \
+                     else {fsm} <= {prev};\n  \
+                     end",
+                    go = self.go
+                ))
+            }
+            FsmType::Counter(_) => {
+                unreachable!("counters are only used within counter chains")
+            }
+            FsmType::CounterChain(states, delay) => {
+                let num = Self::counters(states, delay);
+                let width = Self::bitwidth(delay);
+                let last = format!("{width}'d{}", delay - 1);
+                let mut lines = vec![format!(
+                    "  // `{}': {num} chained counter(s) with {delay} states each",
+                    self.go
+                )];
+                for n in 0..num {
+                    let cnt = self.counter(n);
+                    let go = self.counter_go(n);
+                    decls.push(format!("  {};", logic(width, cnt)));
+                    // The done signal starts the next counter
+                    let (done_rst, done_upd) = if n + 1 < num {
+                        let done = self.counter_go(n + 1);
+                        decls.push(format!("  logic {done};"));
+                        (
+                            format!("\n      {done} <= 1'b0;"),
+                            format!("\n      {done} <= {cnt} == {last};"),
+                        )
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    lines.push(format!(
+                        "  always_ff @(posedge clk) begin\n    \
+                         if (reset) begin\n      \
+                         {cnt} <= '0;{done_rst}\n    \
+                         end else begin\n      \
+                         if ({cnt} == {last}) {cnt} <= '0;\n      \
+                         else if ({go} || {cnt} != '0) {cnt} <= {cnt} + {width}'d1;{done_upd}\n    \
+                         end\n  \
+                         end"
+                    ));
+                }
+                Some(lines.join("\n"))
+            }
+        }
+    }
+
+    /// Guard that is active for the states in `start..end` of the `n`th counter.
+    fn counter_guard(&self, n: u64, start: u64, end: u64) -> Vec<String> {
+        let FsmType::CounterChain(_, delay) = self.typ else {
+            unreachable!("counter guard requested for a non-counter FSM")
+        };
+        let width = Self::bitwidth(delay);
+        let cnt = self.counter(n);
+        let mut parts = Vec::with_capacity(2);
+        // The counter is in state 0 only when it has been triggered
+        let start = if start == 0 {
+            parts.push(format!(
+                "({} && {cnt} == {width}'d0)",
+                self.counter_go(n)
+            ));
+            1
+        } else {
+            start
+        };
+        if start + 1 == end {
+            parts.push(format!("{cnt} == {width}'d{start}"));
+        } else if start < end {
+            parts.push(format!(
+                "({cnt} >= {width}'d{start} && {cnt} <= {width}'d{})",
+                end - 1
+            ));
+        }
+        parts
+    }
+
+    /// Guard that is active for the states in `start..end`.
+    fn guard(&self, start: u64, end: u64) -> String {
+        match self.typ {
+            FsmType::Simple(_) => {
+                let mut parts = Vec::with_capacity(2);
+                let start = if start == 0 {
+                    parts.push(self.go.clone());
+                    1
+                } else {
+                    start
+                };
+                if start + 1 == end {
+                    parts.push(format!("{}[{start}]", self.regs[0]));
+                } else if start < end {
+                    parts.push(format!(
+                        "|{}[{}:{start}]",
+                        self.regs[0],
+                        end - 1
+                    ));
+                }
+                paren(parts)
+            }
+            FsmType::Counter(_) => {
+                unreachable!("counters are only used within counter chains")
+            }
+            FsmType::CounterChain(_, delay) => {
+                let fsm_start = start / delay;
+                let fsm_end = (end - 1) / delay;
+                let parts = (fsm_start..=fsm_end)
+                    .flat_map(|n| {
+                        let s =
+                            if n == fsm_start { start - n * delay } else { 0 };
+                        let e =
+                            if n == fsm_end { end - n * delay } else { delay };
+                        self.counter_guard(n, s, e)
+                    })
+                    .collect_vec();
+                paren(parts)
+            }
+        }
+    }
+}
+
+/// Builds the SystemVerilog module for a single component.
+struct Module<'a> {
+    ctx: &'a ir::Context,
+    idx: ir::CompIdx,
+    comp: &'a ir::Component,
+    names: &'a Names,
+    /// Identifiers used in the module
+    ns: Namespace,
+    /// Names of the instances
+    insts: HashMap<ir::InstIdx, String>,
+    /// Signals representing the ports of invocations
+    signals: HashMap<ir::PortIdx, String>,
+    /// Mapping from events to the FSMs that reify them
+    fsms: HashMap<ir::EventIdx, Fsm>,
+    /// Declarations of internal signals
+    decls: Vec<String>,
+    /// FSMs and instances
+    body: Vec<String>,
+    /// Guarded values driving each signal. A signal without drivers is zero.
+    drivers: LinkedHashMap<String, Vec<(Option<String>, String)>>,
+}
+
+impl<'a> Module<'a> {
+    fn new(ctx: &'a ir::Context, idx: ir::CompIdx, names: &'a Names) -> Self {
+        let comp = ctx.get(idx);
+        let mut ns = Namespace::new(names.sanitize);
+        for port in names.sig(idx) {
+            ns.reserve(&port.name);
+        }
+        let insts = comp
+            .instances()
+            .iter()
+            .map(|(inst, i)| {
+                let base = i.info.get_name(comp);
+                (inst, ns.fresh(base.as_deref().unwrap_or("inst")))
+            })
+            .collect();
+        Self {
+            ctx,
+            idx,
+            comp,
+            names,
+            ns,
+            insts,
+            signals: HashMap::new(),
+            fsms: HashMap::new(),
+            decls: Vec::new(),
+            body: Vec::new(),
+            drivers: LinkedHashMap::new(),
+        }
+    }
+
+    /// Name of the signal representing a port
+    fn signal(&self, port: ir::PortIdx) -> String {
+        match &self.comp.get(port).owner {
+            ir::PortOwner::Sig { .. } => {
+                self.names.port(self.idx, port).to_string()
+            }
+            ir::PortOwner::Inv { .. } => self.signals[&port].clone(),
+            ir::PortOwner::Local => {
+                unreachable!("Local ports should have been eliminated.")
+            }
+        }
+    }
+
+    /// Guard that is active during the range. Returns `None` if the event of
+    /// the range does not have an interface port.
+    fn range_guard(&self, range: &ir::Range) -> Option<String> {
+        let start = self.comp.get(range.start);
+        let end = self.comp.get(range.end);
+        assert!(
+            start.event == end.event,
+            "Range `{}` cannot be represented as a simple offset",
+            self.comp.display(range)
+        );
+        self.fsms.get(&start.event).map(|fsm| {
+            fsm.guard(
+                start.offset.concrete(self.comp),
+                end.offset.concrete(self.comp),
+            )
+        })
+    }
+
+    /// Adds an FSM for the event if it has an interface port
+    fn add_fsm(
+        &mut self,
+        event: ir::EventIdx,
+        states: u64,
+        disable_slow_fsms: bool,
+    ) {
+        let ev = self.comp.get(event);
+        let Some(go) = self.names.interface(self.idx, event) else {
+            return;
+        };
+        let ir::TimeSub::Unit(delay) = ev.delay else {
+            self.comp.internal_error(
+                "Non-unit delays should have been compiled away.",
+            );
+        };
+        let typ =
+            FsmType::new(states, delay.concrete(self.comp), disable_slow_fsms);
+        let fsm = Fsm::new(go.to_string(), typ, &mut self.ns);
+        self.body.extend(fsm.emit(&mut self.decls));
+        self.fsms.insert(event, fsm);
+    }
+
+    /// Adds an instance along with the signals for the ports used by its invocations.
+    fn add_instance(&mut self, idx: ir::InstIdx) {
+        let comp = self.comp;
+        let inst = comp.get(idx);
+        let name = self.insts[&idx].clone();
+        let child = self.ctx.get(inst.comp);
+
+        // Ports of the instance used by its invocations.
+        let mut used: HashMap<String, String> = HashMap::new();
+        for inv in comp.invocations().idx_iter() {
+            let invoke = comp.get(inv);
+            if invoke.inst != idx {
+                continue;
+            }
+            for &port in &invoke.ports {
+                let ir::PortOwner::Inv { base, .. } = &comp.get(port).owner
+                else {
+                    unreachable!("invocation defines a non-invocation port")
+                };
+                let (key, owner) = base.take();
+                let base = self.names.port(owner, key).to_string();
+                if let Some(sig) = used.get(&base) {
+                    self.signals.insert(port, sig.clone());
+                    continue;
+                }
+                let sig = self.ns.fresh(&format!("{name}_{base}"));
+                self.signals.insert(port, sig.clone());
+                let p = comp.get(port);
+                let width = p.width.concrete(comp);
+                self.decls.push(format!("  {};", logic(width, &sig)));
+                if p.is_inv_in() {
+                    self.drivers.insert(sig.clone(), vec![]);
+                }
+                used.insert(base, sig);
+            }
+            for eb in &invoke.events {
+                let (key, owner) = eb.base.take();
+                let Some(port) = self.names.interface(owner, key) else {
+                    continue;
+                };
+                let sig = match used.entry(port.to_string()) {
+                    Entry::Occupied(e) => e.get().clone(),
+                    Entry::Vacant(e) => {
+                        let sig = self.ns.fresh(&format!("{name}_{port}"));
+                        self.decls.push(format!("  logic {sig};"));
+                        e.insert(sig).clone()
+                    }
+                };
+                let time = comp.get(eb.arg);
+                let offset = time.offset.concrete(comp);
+                let Some(fsm) = self.fsms.get(&time.event) else {
+                    comp.internal_error(format!(
+                        "Event `{}' used to invoke `{name}' does not have an interface port",
+                        comp.display(time.event)
+                    ))
+                };
+                let guard = fsm.guard(offset, offset + 1);
+                self.drivers
+                    .entry(sig)
+                    .or_default()
+                    .push((Some(guard), "1'b1".to_string()));
+            }
+        }
+
+        // Parameters are only passed to external modules
+        let params = if let (true, Some(src)) = (child.is_ext, &child.src_info)
+        {
+            let params = child
+                .params()
+                .iter()
+                .filter(|(_, p)| p.is_sig_owned())
+                .map(|(p, _)| src.params.get(p))
+                .zip(inst.args.iter())
+                .map(|(p, v)| format!(".{p}({})", v.concrete(comp)))
+                .collect_vec();
+            format!(" #({})", params.join(", "))
+        } else {
+            String::new()
+        };
+
+        let conns = self
+            .names
+            .sig(inst.comp)
+            .iter()
+            .map(|port| {
+                let arg = match port.kind {
+                    PortKind::Clock => port.name.clone(),
+                    PortKind::Unannotated(_)
+                        if CLOCK_PORTS.contains(&port.name.as_str()) =>
+                    {
+                        port.name.clone()
+                    }
+                    _ => match used.get(&port.name) {
+                        Some(sig) => sig.clone(),
+                        None if port.input => "'0".to_string(),
+                        None => String::new(),
+                    },
+                };
+                format!("    .{}({arg})", port.name)
+            })
+            .join(",\n");
+        let comp_name = self.names.module(inst.comp);
+        self.body
+            .push(format!("  {comp_name}{params} {name} (\n{conns}\n  );"));
+    }
+
+    /// Adds the driver for the destination of a connection
+    fn add_connect(&mut self, con: &ir::Connect) {
+        let ir::Connect { dst, src, .. } = con;
+        assert!(
+            dst.is_port(self.comp),
+            "Bundles should have been compiled away."
+        );
+        let (guard, src) = match src {
+            ir::Source::Port(src) => {
+                assert!(
+                    src.is_port(self.comp),
+                    "Bundles should have been compiled away."
+                );
+                let live = &self.comp.get(src.port).live.range;
+                (self.range_guard(live), self.signal(src.port))
+            }
+            // Constants are driven whenever the destination requires them
+            ir::Source::Const(c) => {
+                let port = self.comp.get(dst.port);
+                let width = port.width.concrete(self.comp);
+                (
+                    self.range_guard(&port.live.range),
+                    c.resize(width).to_string(),
+                )
+            }
+        };
+        let dst = self.signal(dst.port);
+        self.drivers.entry(dst).or_default().push((guard, src));
+    }
+
+    /// Right hand side of the assignment to a signal with the given drivers
+    fn driver(drivers: &[(Option<String>, String)]) -> String {
+        // Interface ports are high whenever any invocation triggers them
+        if !drivers.is_empty()
+            && drivers.iter().all(|(g, v)| g.is_some() && v == "1'b1")
+        {
+            return drivers
+                .iter()
+                .map(|(g, _)| g.as_ref().unwrap())
+                .join(" || ");
+        }
+        drivers
+            .iter()
+            .rev()
+            .fold("'0".to_string(), |rest, (g, v)| match g {
+                None => v.clone(),
+                Some(g) => format!("{g} ? {v} : {rest}"),
+            })
+    }
+
+    /// Emits the module for the component
+    fn emit(mut self, idx: ir::CompIdx, disable_slow_fsms: bool) -> String {
+        let comp = self.comp;
+        let names = self.names;
+        let ports = names.sig(self.idx);
+        // Outputs of the component are driven by the body
+        for port in ports {
+            if !port.input {
+                self.drivers.insert(port.name.to_string(), vec![]);
+            }
+        }
+
+        for (event, states) in max_states(comp) {
+            self.add_fsm(event, states, disable_slow_fsms);
+        }
+        for inst in comp.instances().idx_iter() {
+            self.add_instance(inst);
+        }
+        for cmd in &comp.cmds {
+            match cmd {
+                ir::Command::Connect(con) => self.add_connect(con),
+                ir::Command::ForLoop(_) => {
+                    unreachable!("for loops should have been compiled away.")
+                }
+                ir::Command::If(_) => {
+                    unreachable!("if should have been compiled away.")
+                }
+                ir::Command::BundleDef(_) => {
+                    unreachable!(
+                        "bundle definitions should have been compiled away."
+                    )
+                }
+                ir::Command::Exists(_) => {
+                    unreachable!("exists should have been compiled away.")
+                }
+                ir::Command::Instance(_)
+                | ir::Command::Invoke(_)
+                | ir::Command::Fact(_) => (),
+            }
+        }
+
+        let ports = ports
+            .iter()
+            .map(|port| {
+                let width = match port.kind {
+                    PortKind::Data(p) => comp.get(p).width.concrete(comp),
+                    PortKind::Unannotated(w) => w,
+                    PortKind::Interface(_) | PortKind::Clock => 1,
+                };
+                let dir = if port.input { "input" } else { "output" };
+                format!("  {dir} {}", logic(width, &port.name))
+            })
+            .join(",\n");
+
+        let assigns = self
+            .drivers
+            .iter()
+            .map(|(sig, drivers)| {
+                format!("  assign {sig} = {};", Self::driver(drivers))
+            })
+            .collect_vec();
+
+        let name = names.module(idx);
+        let mut out = format!("module {name} (\n{ports}\n);\n");
+        for section in [
+            self.decls.join("\n"),
+            self.body.join("\n\n"),
+            assigns.join("\n"),
+        ] {
+            if !section.is_empty() {
+                out.push_str(&section);
+                out.push_str("\n\n");
+            }
+        }
+        if out.ends_with("\n\n") {
+            out.pop();
+        }
+        out.push_str("endmodule\n");
+        out
+    }
+}
+
+/// Compiles Filament directly into SystemVerilog without going through Calyx.
+/// Generates one module per component. Like [super::Compile], each event with
+/// an interface port is reified using an FSM which is implemented as a shift
+/// register or a chain of counters.
+pub struct CompileSv;
+
+impl CompileSv {
+    /// Compiles the monomorphized context and writes the result to `out`.
+    /// The source of the external modules is copied to the output.
+    /// Unless `preserve_names` is set, source names that are SystemVerilog
+    /// keywords are renamed.
+    pub fn compile(
+        ctx: ir::Context,
+        disable_slow_fsms: bool,
+        preserve_names: bool,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let names = Names::new(&ctx, preserve_names);
+
+        for file in ctx.externals.keys().sorted() {
+            writeln!(out, "// Source: {file}")?;
+            out.write_all(fs::read_to_string(file)?.as_bytes())?;
+            writeln!(out)?;
+        }
+
+        let mut modules = Vec::new();
+        Traversal::from(ctx).apply_pre_order(|ctx, idx| {
+            modules.push(
+                Module::new(ctx, idx, &names).emit(idx, disable_slow_fsms),
+            );
+        });
+        write!(out, "{}", modules.join("\n"))
+    }
+}
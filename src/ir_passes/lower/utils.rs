@@ -19,6 +19,20 @@ pub(super) const INTERFACE_PORTS: [(AttrPair, (&str, u64, calyx::Direction));
     ),
 ];
 
+/// Calyx constants cannot be wider than 64 bits so constants of 64 or more
+/// bits are built by concatenating chunks of this width.
+pub(super) const CHUNK_WIDTH: u64 = 32;
+
+/// Name of the primitive used to concatenate the chunks of wide constants
+pub(super) const CONCAT: &str = "fil_const_concat";
+
+/// Definition of [CONCAT]
+pub(super) const CONCAT_DEF: &str = r#"
+comb primitive fil_const_concat<"share"=1>[LEFT, RIGHT, WIDTH](@data left: LEFT, @data right: RIGHT) -> (out: WIDTH) {
+  assign out = (left << RIGHT) | right;
+}
+"#;
+
 /// Helper struct that generates names for [crate::utils::Idx]s given their [Ctx].
 pub(super) struct NameGenerator {
     use_info: bool,
@@ -120,6 +134,22 @@ impl NameGenerator {
     }
 }
 
+/// Returns true if the component connects a constant that must be built from
+/// chunks.
+pub fn has_wide_constants(comp: &Component) -> bool {
+    comp.cmds.iter().any(|cmd| {
+        let ir::Command::Connect(ir::Connect {
+            src: ir::Source::Const(_),
+            dst,
+            ..
+        }) = cmd
+        else {
+            return false;
+        };
+        comp.get(dst.port).width.concrete(comp) >= 64
+    })
+}
+
 /// Calculates the max states used for every fsm for the given component.
 pub fn max_states(comp: &Component) -> LinkedHashMap<EventIdx, u64> {
     let mut max_states = LinkedHashMap::new();
@@ -10,18 +10,20 @@ mod lower;
 mod mono;
 mod phantom_check;
 mod prop_simplify;
+mod sharing_check;
 mod type_check;
 
 pub use assignment_check::AssignCheck;
 pub use assume::Assume;
 pub use build_domination::BuildDomination;
 pub use bundle_elim::BundleElim;
-pub use discharge::Discharge;
+pub use discharge::{Discharge, VerifyMono};
 pub use dump_interface::DumpInterface;
 pub use hoist_facts::HoistFacts;
 pub use interval_check::IntervalCheck;
-pub use lower::Compile;
+pub use lower::{max_states, Compile, CompileSv};
 pub use mono::Monomorphize;
 pub use phantom_check::PhantomCheck;
 pub use prop_simplify::Simplify;
+pub use sharing_check::SharingCheck;
 pub use type_check::TypeCheck;
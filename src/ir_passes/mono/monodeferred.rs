@@ -4,6 +4,7 @@ use super::{
 use fil_ir::{self as ir, AddCtx, Ctx};
 use ir::DisplayCtx;
 use itertools::Itertools;
+use std::collections::HashMap;
 
 /// Defines methods required to monomorphize a component. Most of the updates
 /// happen to the stored [MonoSig] object which keeps all the maps needed while
@@ -16,8 +17,18 @@ pub struct MonoDeferred<'a, 'pass: 'a> {
     /// Struct to keep track of all the mapping information from things owned by
     /// `underlying` to things owned by `base`
     pub monosig: MonoSig,
+    /// Facts kept by [Monomorphize::transform_with_facts] along with the
+    /// bindings in scope where they occurred. They are monomorphized after the
+    /// signature because they may mention existential and bundle parameters.
+    pub facts: Vec<(ir::Fact, Scope)>,
 }
 
+/// Bindings of the parameters in scope
+type Scope = Vec<(Underlying<ir::Param>, u64)>;
+
+/// Parameters left free by the facts of a scope
+type FreeParams = HashMap<Underlying<ir::Param>, Base<ir::Param>>;
+
 impl MonoDeferred<'_, '_> {
     /// The [CompKey] associated with the underlying component being monomorphized.
     fn comp_key(&self) -> CompKey {
@@ -100,6 +111,13 @@ impl MonoDeferred<'_, '_> {
 
         let src_info = ul.src_info();
         monosig.interface(ul, src_info);
+        monosig.base.set_src_name(ul.src_name());
+        let binding = monosig
+            .binding
+            .iter()
+            .map(|(p, v)| (ul.display(*p), *v))
+            .collect();
+        monosig.base.set_mono_binding(binding);
 
         let unannotated_ports = ul.unannotated_ports().clone();
         monosig.base.set_unannotated_ports(unannotated_ports);
@@ -138,6 +156,28 @@ impl MonoDeferred<'_, '_> {
             }
         }
 
+        // Facts in the same scope share their free parameters while every
+        // unrolled scope gets its own copy of them.
+        let mut scopes: HashMap<Scope, FreeParams> = HashMap::new();
+        for (fact, binding) in std::mem::take(&mut self.facts) {
+            let orig_l = self.monosig.binding.len();
+            self.monosig.binding.extend(binding.iter().copied());
+            self.monosig.free_params =
+                Some(scopes.remove(&binding).unwrap_or_default());
+            let prop = self.prop(fact.prop.ul());
+            let info = self.monosig.info(
+                &self.underlying,
+                self.pass,
+                fact.reason.ul(),
+            );
+            self.monosig
+                .binding
+                .pop_n(self.monosig.binding.len() - orig_l);
+            scopes.insert(binding, self.monosig.free_params.take().unwrap());
+            let cmd = self.monosig.base.fact(fact.is_assume(), prop, info);
+            self.monosig.base.extend_cmds(cmd);
+        }
+
         // Handle event delays after monomorphization because delays might mention existential parameters.
         for (old, &new) in self.monosig.event_map.clone().iter() {
             self.monosig
@@ -196,6 +236,11 @@ impl MonoDeferred<'_, '_> {
                 let l = l.ul();
                 let r = r.ul();
                 let l = self.prop(l);
+                // Facts guarded by a false condition might not be meaningful
+                // for this binding. For example, `N > 0 => N-1 >= 0`.
+                if l.get().is_false(self.monosig.base.comp()) {
+                    return l;
+                }
                 let r = self.prop(r);
                 self.monosig.base.add(ir::Prop::And(l.get(), r.get()))
             }
@@ -203,6 +248,9 @@ impl MonoDeferred<'_, '_> {
                 let l = l.ul();
                 let r = r.ul();
                 let l = self.prop(l);
+                if l.get().is_true(self.monosig.base.comp()) {
+                    return l;
+                }
                 let r = self.prop(r);
                 self.monosig.base.add(ir::Prop::Or(l.get(), r.get()))
             }
@@ -210,6 +258,9 @@ impl MonoDeferred<'_, '_> {
                 let l = l.ul();
                 let r = r.ul();
                 let l = self.prop(l);
+                if l.get().is_false(self.monosig.base.comp()) {
+                    return self.monosig.base.add(ir::Prop::True);
+                }
                 let r = self.prop(r);
                 self.monosig.base.add(ir::Prop::Implies(l.get(), r.get()))
             }
@@ -243,7 +294,10 @@ impl MonoDeferred<'_, '_> {
     fn connect(&mut self, con: &ir::Connect) -> ir::Connect {
         let ir::Connect { src, dst, info } = con;
 
-        let mono_src = self.access(src);
+        let mono_src = match src {
+            ir::Source::Port(src) => self.access(src).into(),
+            ir::Source::Const(c) => ir::Source::Const(c.clone()),
+        };
         let mono_dst = self.access(dst);
         let info = info.ul();
 
@@ -362,6 +416,11 @@ impl MonoDeferred<'_, '_> {
                     .add_exist_val(param.ul(), v);
                 None
             }
+            ir::Command::Fact(fact) if self.pass.keep_facts => {
+                let binding = self.monosig.binding.inner();
+                self.facts.push((fact.clone(), binding));
+                None
+            }
             // XXX(rachit): We completely get rid of facts in the program here.
             // If we want to do this long term, this should be done in a
             // separate pass and monomorphization should fail on facts.
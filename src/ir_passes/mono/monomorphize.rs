@@ -69,10 +69,13 @@ pub struct Monomorphize<'a> {
 
     /// Tracks which components are defined in which files
     pub ext_map: HashMap<String, Vec<ir::CompIdx>>,
+
+    /// Keep the facts of the components instead of removing them
+    pub keep_facts: bool,
 }
 
 impl<'a> Monomorphize<'a> {
-    fn new(old: &'a ir::Context) -> Self {
+    fn new(old: &'a ir::Context, keep_facts: bool) -> Self {
         Monomorphize {
             ctx: ir::Context {
                 comps: IndexStore::default(),
@@ -84,6 +87,7 @@ impl<'a> Monomorphize<'a> {
             processed: HashMap::new(),
             inst_info: HashMap::new(),
             ext_map: HashMap::new(),
+            keep_facts,
         }
     }
 }
@@ -127,6 +131,7 @@ impl<'ctx> Monomorphize<'ctx> {
             underlying: UnderlyingComp::new(self.old.get(comp.idx())),
             pass: self,
             monosig,
+            facts: vec![],
         }
         .comp();
 
@@ -153,6 +158,17 @@ impl Monomorphize<'_> {
     /// Monomorphize the context by tracing starting from the top-level component.
     /// Returns an empty context if there is no top-level component.
     pub fn transform(ctx: &ir::Context) -> ir::Context {
+        Self::run(ctx, false)
+    }
+
+    /// Monomorphize the context while keeping the facts of the components.
+    /// The facts are specialized for each instance and can be checked by
+    /// evaluating them.
+    pub fn transform_with_facts(ctx: &ir::Context) -> ir::Context {
+        Self::run(ctx, true)
+    }
+
+    fn run(ctx: &ir::Context, keep_facts: bool) -> ir::Context {
         let Some(entrypoint) = ctx.entrypoint else {
             log::warn!("Program has no entrypoint. Result will be empty.");
             return ir::Context {
@@ -163,7 +179,7 @@ impl Monomorphize<'_> {
         };
         let entrypoint = entrypoint.ul();
         // Monomorphize the entrypoint
-        let mut mono = Monomorphize::new(ctx);
+        let mut mono = Monomorphize::new(ctx, keep_facts);
         let ck = CompKey::new(entrypoint, vec![]);
         mono.monomorphize(ck.clone());
 
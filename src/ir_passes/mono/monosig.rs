@@ -40,6 +40,10 @@ pub struct MonoSig {
     invoke_map: DenseMap<ir::Invoke>,
     /// Map from underlying instances to base instances
     instance_map: DenseMap<ir::Instance>,
+    /// Parameters left free while monomorphizing a fact. Facts are universally
+    /// quantified over the parameters not bound by the signature or an
+    /// enclosing loop, so such parameters are kept instead of being resolved.
+    pub free_params: Option<HashMap<Underlying<ir::Param>, Base<ir::Param>>>,
 }
 
 impl MonoSig {
@@ -58,6 +62,7 @@ impl MonoSig {
         );
         let mut comp = ir::Component::default();
         comp.is_ext = is_ext;
+        comp.funcs = underlying.funcs.clone();
 
         Self {
             base: BaseComp::new(comp),
@@ -69,6 +74,7 @@ impl MonoSig {
             event_map: DenseMap::default(),
             invoke_map: DenseMap::default(),
             instance_map: DenseMap::default(),
+            free_params: None,
         }
     }
 
@@ -198,13 +204,16 @@ impl MonoSig {
             },
             ir::info::Reason::ParamConstraint { .. }
             | ir::info::Reason::ExistsConstraint { .. }
+            | ir::info::Reason::FnPrecondition { .. }
             | ir::info::Reason::EventConstraint { .. }
             | ir::info::Reason::BundleLenMatch { .. }
             | ir::info::Reason::BundleWidthMatch { .. }
+            | ir::info::Reason::ConstantFits { .. }
             | ir::info::Reason::InBoundsAccess { .. }
             | ir::info::Reason::BundleDelay { .. }
             | ir::info::Reason::WellFormedInterval { .. }
             | ir::info::Reason::EventTrig { .. }
+            | ir::info::Reason::SharedInstance { .. }
             | ir::info::Reason::Misc { .. } => reason.clone(),
         }
     }
@@ -218,6 +227,15 @@ impl MonoSig {
     ) -> Base<ir::Param> {
         if let Some(&idx) = self.param_map.find(p_idx) {
             idx
+        } else if let Some(free) = &mut self.free_params {
+            *free.entry(p_idx).or_insert_with(|| {
+                let info = ul.get(ul.get(p_idx).info.ul()).clone();
+                let param = ir::Param {
+                    owner: ir::ParamOwner::Loop,
+                    info: self.base.add(info).get(),
+                };
+                self.base.add(param)
+            })
         } else {
             let p_rep = ul.display(p_idx);
             // This param is a in a use site and should therefore have been found.
@@ -34,6 +34,9 @@ impl<'a> UnderlyingComp<'a> {
     pub fn src_info(&self) -> &Option<InterfaceSrc> {
         &self.0.src_info
     }
+    pub fn src_name(&self) -> Option<ast::Id> {
+        self.0.src_name
+    }
     pub fn unannotated_ports(&self) -> &Vec<(ast::Id, u64)> {
         &self.0.unannotated_ports
     }
@@ -86,6 +89,29 @@ impl BaseComp {
     pub fn set_src_info(&mut self, other: Option<InterfaceSrc>) {
         self.0.src_info = other;
     }
+    pub fn set_src_name(&mut self, name: Option<ast::Id>) {
+        self.0.src_name = name;
+    }
+    pub fn set_mono_binding(&mut self, binding: Vec<(String, u64)>) {
+        self.0.mono_binding = binding;
+    }
+
+    /// Add an assertion or an assumption of `prop`. Assumptions that are
+    /// false for this instance are dropped.
+    pub fn fact(
+        &mut self,
+        assume: bool,
+        prop: Base<ir::Prop>,
+        info: Base<ir::Info>,
+    ) -> Option<ir::Command> {
+        if !assume {
+            self.0.assert(prop.get(), info.get())
+        } else if prop.get().is_false(&self.0) {
+            None
+        } else {
+            self.0.assume(prop.get(), info.get())
+        }
+    }
 
     pub fn extend_cmds(
         &mut self,
@@ -0,0 +1,301 @@
+use crate::ir_visitor::{Action, Visitor, VisitorData};
+use fil_ast as ast;
+use fil_ir::{self as ir, AddCtx, Ctx};
+use fil_utils::GPosIdx;
+use itertools::Itertools;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+/// A condition under which an invocation executes.
+enum Cond {
+    /// The invocation is within the body of a loop
+    Loop {
+        index: ir::ParamIdx,
+        start: ir::ExprIdx,
+        end: ir::ExprIdx,
+    },
+    /// The invocation is guarded by a proposition
+    Prop(ir::PropIdx),
+}
+
+impl Cond {
+    /// Proposition representing this condition after substituting loop indices
+    /// using the binding.
+    fn prop(
+        &self,
+        bind: &ir::Bind<ir::ParamIdx, ir::ExprIdx>,
+        comp: &mut ir::Component,
+    ) -> ir::PropIdx {
+        match self {
+            Cond::Loop { index, start, end } => {
+                let idx = index.expr(comp);
+                let lo = idx.gte(*start, comp);
+                let hi = idx.lt(*end, comp);
+                let prop = lo.and(hi, comp);
+                ir::Subst::new(prop, bind).apply(comp)
+            }
+            Cond::Prop(p) => ir::Subst::new(*p, bind).apply(comp),
+        }
+    }
+}
+
+#[derive(Default)]
+/// Ensures that shared instances are never used by two invocations at the
+/// same time.
+/// For every pair of invocations of an instance, including an invocation
+/// within a loop and itself in a different iteration, we require that:
+/// * The busy ranges, `[t, t+delay)`, of the two invocations are disjoint.
+/// * The invocations in the next execution of the component, which may start
+///   as soon as the component's event's delay, do not overlap with either
+///   invocation.
+///
+/// Like [super::IntervalCheck], this pass only generates the assertions and
+/// relies on a latter pass to discharge them.
+pub struct SharingCheck {
+    /// Stack of scopes tracking the number of conditions in each scope
+    stack: Vec<usize>,
+    /// Conditions under which the current command executes
+    path_cond: Vec<Cond>,
+    /// Number of conditions in scope when each instance was defined
+    inst_scope: HashMap<ir::InstIdx, usize>,
+    /// Previously visited invocations of each instance along with the
+    /// conditions they execute under that are not shared with the instance.
+    invokes: HashMap<ir::InstIdx, Vec<(ir::InvIdx, Vec<Cond>)>>,
+}
+
+impl SharingCheck {
+    /// Push a new stack frame by tracking the number of conditions
+    fn push(&mut self) {
+        self.stack.push(self.path_cond.len());
+    }
+
+    /// Pop the current stack frame
+    fn pop(&mut self) {
+        let conds = self.stack.pop().unwrap();
+        self.path_cond.truncate(conds);
+    }
+
+    /// Location of the event binding. Default bindings do not have a location
+    /// so we use the location of the invocation instead.
+    fn bind_loc(
+        inv: ir::InvIdx,
+        eb: &ir::EventBind,
+        comp: &ir::Component,
+    ) -> GPosIdx {
+        let &ir::info::EventBind { bind_loc, .. } = comp.get(eb.info).into();
+        bind_loc.into_option().unwrap_or_else(|| {
+            let &ir::info::Invoke { bind_loc, .. } =
+                comp.get(comp[inv].info).into();
+            bind_loc
+        })
+    }
+
+    /// Symbolic difference between two times
+    fn diff(l: ir::TimeIdx, r: ir::TimeIdx) -> ir::TimeSub {
+        ir::TimeSub::Sym { l, r }
+    }
+
+    /// Generate the assertions that ensure that invocation `fst`, executing
+    /// under `conds`, does not conflict with the invocation `snd` being visited.
+    /// If `fst` and `snd` are the same invocation, the conditions describe a
+    /// different iteration of the loops surrounding it.
+    fn conflicts(
+        fst: ir::InvIdx,
+        conds: &[Cond],
+        snd: ir::InvIdx,
+        comp: &mut ir::Component,
+    ) -> Vec<ir::Command> {
+        // Generate fresh copies of the loop indices of the first invocation so
+        // that it may execute in a different iteration than the second.
+        let binding = conds
+            .iter()
+            .filter_map(|c| match c {
+                Cond::Loop { index, .. } => {
+                    let &ir::info::Param { name, bind_loc, .. } =
+                        comp.get(comp.get(*index).info).into();
+                    let name = ast::Id::new(format!("{name}_other"));
+                    let info = comp.add(ir::info::Info::param(name, bind_loc));
+                    let fresh =
+                        comp.add(ir::Param::new(ir::ParamOwner::Loop, info));
+                    Some((*index, fresh.expr(comp)))
+                }
+                Cond::Prop(_) => None,
+            })
+            .collect_vec();
+
+        let bind = ir::Bind::new(binding);
+
+        let mut pre = comp.add(ir::Prop::True);
+        for c in conds {
+            let p = c.prop(&bind, comp);
+            pre = pre.and(p, comp);
+        }
+        // At least one loop index must differ when checking an invocation
+        // against itself.
+        if fst == snd {
+            let mut distinct = comp.add(ir::Prop::False);
+            for (idx, fresh) in bind.iter() {
+                let idx = idx.expr(comp);
+                let neq = idx.equal(*fresh, comp).not(comp);
+                distinct = distinct.or(neq, comp);
+            }
+            pre = pre.and(distinct, comp);
+        }
+
+        let mut cmds = Vec::new();
+        let fst_events = comp[fst].events.clone();
+        let snd_events = comp[snd].events.clone();
+        for (a, b) in fst_events.iter().zip(snd_events.iter()) {
+            let &ir::info::EventBind { ev_delay_loc, .. } =
+                comp.get(a.info).into();
+            let a_loc = Self::bind_loc(fst, a, comp);
+            let b_loc = Self::bind_loc(snd, b, comp);
+
+            let a_start = ir::Subst::new(a.arg, &bind).apply(comp);
+            let a_delay = ir::Subst::new(a.delay.clone(), &bind).apply(comp);
+            let (b_start, b_delay) = (b.arg, b.delay.clone());
+
+            // Busy ranges of both invocations are disjoint. We use symbolic
+            // differences because the start times are not ordered.
+            let a_first =
+                Self::diff(b_start, a_start).gte(a_delay.clone(), comp);
+            let b_first =
+                Self::diff(a_start, b_start).gte(b_delay.clone(), comp);
+            let disjoint = a_first.or(b_first, comp);
+            let prop = pre.implies(disjoint, comp);
+            let reason = comp.add(
+                ir::info::Reason::shared_instance(
+                    ev_delay_loc,
+                    a_delay.clone(),
+                    (a_loc, a_start),
+                    (b_loc, b_start),
+                    None,
+                )
+                .into(),
+            );
+            cmds.extend(comp.assert(prop, reason));
+
+            // Invocations in the next execution of the component start
+            // after both invocations are done.
+            let ev = b_start.event(comp);
+            if a_start.event(comp) != ev {
+                continue;
+            }
+            let ir::TimeSub::Unit(period) = comp[ev].delay.clone() else {
+                continue;
+            };
+            // When checking an invocation against itself, the second
+            // constraint is the same as the first with the indices swapped.
+            let mut pipelined =
+                vec![((a_start, a_delay, a_loc), (b_start, b_loc))];
+            if fst != snd {
+                pipelined.push(((b_start, b_delay, b_loc), (a_start, a_loc)));
+            }
+            for ((start, delay, loc), (next, next_loc)) in pipelined {
+                let &ir::Time { event, offset } = comp.get(next);
+                let offset = offset.add(period, comp);
+                let shifted = comp.add(ir::Time { event, offset });
+                let prop = Self::diff(shifted, start).gte(delay.clone(), comp);
+                let prop = pre.implies(prop, comp);
+                let reason = comp.add(
+                    ir::info::Reason::shared_instance(
+                        ev_delay_loc,
+                        delay,
+                        (loc, start),
+                        (next_loc, next),
+                        Some(ir::TimeSub::Unit(period)),
+                    )
+                    .into(),
+                );
+                cmds.extend(comp.assert(prop, reason));
+            }
+        }
+        cmds
+    }
+}
+
+impl Visitor for SharingCheck {
+    fn name() -> &'static str {
+        "sharing_check"
+    }
+
+    /// Assumptions in a scope constrain all invocations in the scope.
+    fn start_cmds(
+        &mut self,
+        cmds: &mut Vec<ir::Command>,
+        data: &mut VisitorData,
+    ) {
+        let comp = &mut data.comp;
+        for cmd in cmds.iter() {
+            match cmd {
+                ir::Command::Fact(fact) if fact.is_assume() => {
+                    self.path_cond.push(Cond::Prop(fact.prop))
+                }
+                ir::Command::Exists(ir::Exists { param, expr }) => {
+                    let prop = param.expr(comp).equal(*expr, comp);
+                    self.path_cond.push(Cond::Prop(prop));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn instance(&mut self, idx: ir::InstIdx, _: &mut VisitorData) -> Action {
+        self.inst_scope.insert(idx, self.path_cond.len());
+        Action::Continue
+    }
+
+    fn invoke(&mut self, idx: ir::InvIdx, data: &mut VisitorData) -> Action {
+        let comp = &mut data.comp;
+        let inst = comp[idx].inst;
+        let conds = self.path_cond[self.inst_scope[&inst]..].to_vec();
+
+        let prev = self.invokes.entry(inst).or_default();
+        let mut cmds = Vec::new();
+        for (fst, fst_conds) in prev.iter() {
+            cmds.extend(Self::conflicts(*fst, fst_conds, idx, comp));
+        }
+        // Invocations in a loop conflict with themselves in other iterations
+        if conds.iter().any(|c| matches!(c, Cond::Loop { .. })) {
+            cmds.extend(Self::conflicts(idx, &conds, idx, comp));
+        }
+        prev.push((idx, conds));
+
+        if cmds.is_empty() {
+            Action::Continue
+        } else {
+            Action::AddBefore(cmds)
+        }
+    }
+
+    fn do_if(&mut self, i: &mut ir::If, data: &mut VisitorData) -> Action {
+        self.push();
+        self.path_cond.push(Cond::Prop(i.cond));
+        let ac = self.visit_cmds(&mut i.then, data);
+        assert!(ac == Action::Continue);
+        self.pop();
+
+        self.push();
+        let alt = i.cond.not(&mut data.comp);
+        self.path_cond.push(Cond::Prop(alt));
+        let ac = self.visit_cmds(&mut i.alt, data);
+        assert!(ac == Action::Continue);
+        self.pop();
+
+        Action::Continue
+    }
+
+    fn start_loop(&mut self, l: &mut ir::Loop, _: &mut VisitorData) -> Action {
+        self.push();
+        let &mut ir::Loop {
+            index, start, end, ..
+        } = l;
+        self.path_cond.push(Cond::Loop { index, start, end });
+        Action::Continue
+    }
+
+    fn end_loop(&mut self, _: &mut ir::Loop, _: &mut VisitorData) -> Action {
+        self.pop();
+        Action::Continue
+    }
+}
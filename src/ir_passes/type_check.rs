@@ -91,12 +91,40 @@ impl Visitor for TypeCheck {
         let mut cons = vec![];
 
         // Range accesses are well-formed
-        cons.extend(self.port_access(src, src_loc, comp));
+        if let ir::Source::Port(src) = src {
+            cons.extend(self.port_access(src, src_loc, comp));
+        }
         cons.extend(self.port_access(dst, dst_loc, comp));
+        let dst_w = comp.get(dst.port).width;
+        let dst_size = dst.end.sub(dst.start, comp);
+
+        let (src_w, src_size) = match src {
+            ir::Source::Port(src) => {
+                (comp.get(src.port).width, src.end.sub(src.start, comp))
+            }
+            ir::Source::Const(c) => match c.width {
+                Some(w) => (comp.num(w), comp.num(1)),
+                // Unsized constants take the width of the destination but
+                // must fit in it
+                None => {
+                    let reason = comp.add(
+                        ir::info::Reason::constant_fits(
+                            dst_loc,
+                            src_loc,
+                            dst_w,
+                            c.bits(),
+                        )
+                        .into(),
+                    );
+                    let bits = comp.num(c.bits());
+                    let prop = dst_w.gte(bits, comp);
+                    cons.extend(comp.assert(prop, reason));
+                    (dst_w, comp.num(1))
+                }
+            },
+        };
 
         // Ensure that the bitwidths of the ports are the same
-        let src_w = comp.get(src.port).width;
-        let dst_w = comp.get(dst.port).width;
         let reason = comp.add(
             ir::info::Reason::bundle_width_match(
                 dst_loc, src_loc, dst_w, src_w,
@@ -107,8 +135,6 @@ impl Visitor for TypeCheck {
         cons.extend(comp.assert(prop, reason));
 
         // Ensure that the sizes are the same
-        let src_size = src.end.sub(src.start, comp);
-        let dst_size = dst.end.sub(dst.start, comp);
         let reason = comp.add(
             ir::info::Reason::bundle_len_match(
                 dst_loc, src_loc, dst_size, src_size,
@@ -1,6 +1,8 @@
 pub mod cmdline;
+pub mod interp;
 pub mod ir_passes;
 pub mod ir_visitor;
+pub mod pipeline;
 pub mod resolver;
 
 mod macros;
@@ -1,7 +1,9 @@
 use calyx_backend::Backend;
 use calyx_opt::pass_manager::PassManager;
+use codespan_reporting::diagnostic::Diagnostic;
+use fil_ast as ast;
 use fil_ir as ir;
-use filament::ir_passes::BuildDomination;
+use fil_utils::ErrorFormat;
 use filament::{cmdline, ir_passes as ip, resolver::Resolver};
 use filament::{log_pass, log_time, pass_pipeline};
 
@@ -15,6 +17,7 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
         .filter_level(opts.log_level)
         .target(env_logger::Target::Stderr)
         .init();
+    opts.error_format.set_global();
 
     let ns = match Resolver::from(opts).parse_namespace() {
         Ok(mut ns) => {
@@ -22,28 +25,22 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
             ns
         }
         Err(e) => {
-            eprintln!("Error: {e:?}");
+            // Syntax errors point into the file
+            if let Some(diag) = e.diagnostic() {
+                fil_utils::emit(&diag, &[]);
+                return Err(1);
+            }
+            match ErrorFormat::global() {
+                ErrorFormat::Human => eprintln!("Error: {e:?}"),
+                ErrorFormat::Json => report_error(e.kind),
+            }
             return Err(1);
         }
     };
 
     // Transform AST to IR
     let mut ir = log_pass! { opts; ir::transform(ns)?, "astconv" };
-    pass_pipeline! {opts, ir;
-        ip::BuildDomination,
-        ip::TypeCheck,
-        ip::IntervalCheck,
-        ip::PhantomCheck,
-        ip::Assume,
-        ip::HoistFacts
-    }
-    if !opts.unsafe_skip_discharge {
-        pass_pipeline! {opts, ir; ip::Discharge }
-    }
-    pass_pipeline! { opts, ir;
-        BuildDomination
-    };
-    ir = log_pass! { opts; ip::Monomorphize::transform(&ir), "monomorphize"};
+    let mut ir = filament::pipeline::check(opts, &mut ir)?;
     pass_pipeline! { opts, ir;
         ip::Simplify,
         ip::AssignCheck,
@@ -57,19 +54,55 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
         return Ok(());
     }
 
+    // Run the interpreter instead of generating hardware
+    if let Some(data) = &opts.interp {
+        let data = match std::fs::read_to_string(data) {
+            Ok(data) => data,
+            Err(e) => {
+                report_error(format!("failed to read {}: {e}", data.display()));
+                return Err(1);
+            }
+        };
+        match log_time!(
+            filament::interp::Interpreter::run(&ir, &data),
+            "interp"
+        ) {
+            Ok(out) => println!("{out}"),
+            Err(e) => {
+                report_error(e);
+                return Err(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Return if we are only checking
     if opts.check {
         return Ok(());
     }
-    let calyx = log_time!(
-        ip::Compile::compile(ir, opts.disable_slow_fsms, opts.preserve_names),
-        "compile"
-    );
     match opts.backend {
+        // The SystemVerilog backend does not go through Calyx
+        cmdline::Backend::Sv => {
+            let out = &mut std::io::stdout();
+            let res = log_time!(
+                ip::CompileSv::compile(
+                    ir,
+                    opts.disable_slow_fsms,
+                    opts.preserve_names,
+                    out
+                ),
+                "compile-sv"
+            );
+            if let Err(e) = res {
+                report_error(format!("Failed to write SystemVerilog: {e}"));
+                return Err(1);
+            }
+        }
         cmdline::Backend::Verilog => {
-            gen_verilog(calyx).unwrap();
+            gen_verilog(compile_calyx(ir, opts)).unwrap();
         }
         cmdline::Backend::Calyx => {
+            let calyx = compile_calyx(ir, opts);
             let out = &mut std::io::stdout();
             calyx_ir::Printer::write_context(&calyx, false, out).unwrap();
         }
@@ -77,6 +110,24 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
     Ok(())
 }
 
+/// Compile the monomorphized program into Calyx
+fn compile_calyx(ir: ir::Context, opts: &cmdline::Opts) -> calyx_ir::Context {
+    log_time!(
+        ip::Compile::compile(ir, opts.disable_slow_fsms, opts.preserve_names),
+        "compile"
+    )
+}
+
+/// Report an error without any source information
+fn report_error(msg: String) {
+    match ErrorFormat::global() {
+        ErrorFormat::Human => eprintln!("Error: {msg}"),
+        ErrorFormat::Json => {
+            fil_utils::emit(&Diagnostic::error().with_message(msg), &[])
+        }
+    }
+}
+
 fn gen_verilog(mut ctx: calyx_ir::Context) -> Result<(), calyx_utils::Error> {
     let pm = PassManager::default_passes()?;
     let backend_conf = calyx_ir::BackendConf {
@@ -95,14 +146,69 @@ fn gen_verilog(mut ctx: calyx_ir::Context) -> Result<(), calyx_utils::Error> {
     backend.run(ctx, calyx_utils::OutputFile::Stdout)
 }
 
+/// Formats the input files. When running with `--check`, fails with the
+/// number of files that are not formatted.
+fn fmt(opts: &cmdline::FmtOpts) -> Result<(), u64> {
+    let mut unformatted = 0;
+    for path in &opts.files {
+        let src = std::fs::read_to_string(path).map_err(|err| {
+            eprintln!("Error: Failed to read {}: {err}", path.display());
+            1u64
+        })?;
+        let out = ast::format_source(&src, path).map_err(|e| {
+            eprintln!("Error: {e:?}");
+            1u64
+        })?;
+        if opts.check {
+            if out != src {
+                eprintln!("{} is not formatted", path.display());
+                unformatted += 1;
+            }
+        } else if opts.write {
+            if out != src {
+                std::fs::write(path, out).map_err(|err| {
+                    eprintln!(
+                        "Error: Failed to write {}: {err}",
+                        path.display()
+                    );
+                    1u64
+                })?;
+            }
+        } else {
+            print!("{out}");
+        }
+    }
+    if unformatted > 0 {
+        Err(unformatted)
+    } else {
+        Ok(())
+    }
+}
+
 fn main() {
     let opts: cmdline::Opts = argh::from_env();
+    match &opts.command {
+        Some(cmdline::Command::Fmt(fmt_opts)) => {
+            if fmt(fmt_opts).is_err() {
+                std::process::exit(1)
+            }
+            return;
+        }
+        None if opts.input.is_none() => {
+            eprintln!("Required positional arguments not provided:\n    input");
+            std::process::exit(1)
+        }
+        None => (),
+    }
     match run(&opts) {
         Ok(_) => (),
         Err(err) => {
-            eprintln!("Compilation failed with {err} errors.");
-            if !opts.show_models {
-                eprintln!("Run with --show-models to generate assignments for failing constraints.");
+            // Only the diagnostics are printed in the JSON format
+            if opts.error_format == ErrorFormat::Human {
+                eprintln!("Compilation failed with {err} errors.");
+                if !opts.show_models {
+                    eprintln!("Run with --show-models to generate assignments for failing constraints.");
+                }
             }
             std::process::exit(1)
         }
@@ -0,0 +1,41 @@
+//! The checking passes shared by the compiler and the language server.
+use crate::{cmdline, ir_passes as ip, log_pass, pass_pipeline};
+use fil_ir as ir;
+
+/// Run the checking passes on the program and monomorphize it. Stops at the
+/// first pass that reports errors. With `--verify-mono`, the facts are checked
+/// after monomorphization.
+/// Returns the monomorphized program while `ir` retains the checked program.
+pub fn check(
+    opts: &cmdline::Opts,
+    mut ir: &mut ir::Context,
+) -> Result<ir::Context, u64> {
+    pass_pipeline! {opts, ir;
+        ip::BuildDomination,
+        ip::TypeCheck,
+        ip::IntervalCheck,
+        ip::SharingCheck,
+        ip::PhantomCheck,
+        ip::Assume
+    }
+    if !opts.verify_mono {
+        pass_pipeline! {opts, ir; ip::HoistFacts }
+        if !opts.unsafe_skip_discharge {
+            pass_pipeline! {opts, ir; ip::Discharge }
+        }
+    }
+    pass_pipeline! { opts, ir;
+        ip::BuildDomination
+    };
+    let mono = if opts.verify_mono {
+        let mut mono = log_pass! { opts; ip::Monomorphize::transform_with_facts(ir), "monomorphize"};
+        pass_pipeline! { opts, mono;
+            ip::HoistFacts,
+            ip::VerifyMono
+        }
+        mono
+    } else {
+        log_pass! { opts; ip::Monomorphize::transform(ir), "monomorphize"}
+    };
+    Ok(mono)
+}
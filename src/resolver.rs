@@ -1,6 +1,7 @@
 use crate::cmdline;
 use fil_ast as ast;
 use fil_utils::{Error, FilamentResult};
+use itertools::Itertools;
 use std::{
     collections::HashSet,
     fs,
@@ -21,7 +22,10 @@ impl From<&cmdline::Opts> for Resolver {
     fn from(opts: &cmdline::Opts) -> Self {
         Self {
             lib: opts.library.clone(),
-            input: opts.input.clone(),
+            input: opts
+                .input
+                .clone()
+                .expect("no input file to resolve imports from"),
             already_imported: HashSet::new(),
         }
     }
@@ -100,6 +104,30 @@ impl Resolver {
         }
     }
 
+    /// Primitive files in the library that have not been imported along with
+    /// the import that provides them. Used to suggest imports for undefined
+    /// components.
+    fn library(&self) -> Vec<(String, PathBuf)> {
+        let Ok(entries) = fs::read_dir(self.lib.join("primitives")) else {
+            return vec![];
+        };
+        let input = fs::canonicalize(&self.input).ok();
+        entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().map_or(false, |ext| ext == "fil"))
+            .filter(|p| {
+                let c = fs::canonicalize(p).ok();
+                c != input
+                    && c.map_or(false, |c| !self.already_imported.contains(&c))
+            })
+            .sorted()
+            .filter_map(|p| {
+                let file = p.file_name()?.to_string_lossy();
+                Some((format!("primitives/{file}"), p))
+            })
+            .collect()
+    }
+
     pub fn parse_namespace(&mut self) -> FilamentResult<ast::Namespace> {
         // Parse the top-level file
         let mut ns = ast::FilamentParser::parse_file(&self.input)?;
@@ -126,6 +154,7 @@ impl Resolver {
             let base = Self::parent(&path);
             imp.components.append(&mut ns.components);
             ns.components = imp.components;
+            ns.fns.extend(imp.fns);
             ns.externs.extend(
                 imp.externs
                     .into_iter()
@@ -143,6 +172,8 @@ impl Resolver {
             );
         }
 
+        ns.library = self.library();
+
         log::trace!("Imported: {:#?}", self.already_imported);
         log::trace!(
             "Components: {:#?}",
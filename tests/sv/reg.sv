@@ -0,0 +1,14 @@
+module Reg #(
+  parameter WIDTH = 32
+) (
+  input wire logic clk,
+  input wire logic reset,
+  input wire logic write_en,
+  input wire logic [WIDTH-1:0] in,
+  output logic [WIDTH-1:0] out
+);
+  always_ff @(posedge clk) begin
+    if (reset) out <= '0;
+    else if (write_en) out <= in;
+  end
+endmodule
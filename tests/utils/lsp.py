@@ -0,0 +1,93 @@
+#!/usr/bin/env python3
+"""
+Drives `filament-lsp` through a session on a Filament file and prints the
+diagnostics and responses it produces.
+Usage: lsp.py <server> <file.fil> <requests.json> [compiler flags...]
+
+The requests file contains a list of objects with a `method` and the `line`
+and `character` of the position to query.
+"""
+import json
+import os
+import subprocess
+import sys
+
+
+def send(proc, msg):
+    body = json.dumps(msg).encode()
+    proc.stdin.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+    proc.stdin.flush()
+
+
+def recv(proc):
+    length = None
+    while True:
+        line = proc.stdout.readline().decode().strip()
+        if not line:
+            break
+        key, val = line.split(":", 1)
+        if key.lower() == "content-length":
+            length = int(val)
+    return json.loads(proc.stdout.read(length))
+
+
+def relativize(obj, root):
+    """Replace file URIs with paths relative to the current directory"""
+    if isinstance(obj, dict):
+        return {k: relativize(v, root) for k, v in obj.items()}
+    if isinstance(obj, list):
+        return [relativize(v, root) for v in obj]
+    if isinstance(obj, str) and obj.startswith("file://"):
+        return os.path.relpath(obj[len("file://"):], root)
+    if isinstance(obj, str):
+        return obj.replace(root + os.sep, "")
+    return obj
+
+
+def main():
+    server, path, reqs = sys.argv[1:4]
+    root = os.getcwd()
+    uri = "file://" + os.path.abspath(path)
+    proc = subprocess.Popen(
+        [server, *sys.argv[4:]], stdin=subprocess.PIPE, stdout=subprocess.PIPE
+    )
+    ids = iter(range(1, 1000))
+
+    def request(method, params):
+        id = next(ids)
+        send(proc, {"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+        while True:
+            msg = recv(proc)
+            if msg.get("id") == id:
+                return msg.get("result")
+            show(msg)
+
+    def show(msg):
+        if msg.get("method") == "textDocument/publishDiagnostics":
+            print(json.dumps(relativize(msg["params"], root), indent=2, sort_keys=True))
+
+    request("initialize", {"processId": None, "rootUri": None, "capabilities": {}})
+    send(proc, {"jsonrpc": "2.0", "method": "initialized", "params": {}})
+    with open(path) as f:
+        text = f.read()
+    send(proc, {
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": uri, "languageId": "filament", "version": 0, "text": text}},
+    })
+    with open(reqs) as f:
+        for r in json.load(f):
+            params = {
+                "textDocument": {"uri": uri},
+                "position": {"line": r["line"], "character": r["character"]},
+            }
+            res = request(r["method"], params)
+            print(f"{r['method']} {r['line']}:{r['character']}")
+            print(json.dumps(relativize(res, root), indent=2, sort_keys=True))
+    request("shutdown", None)
+    send(proc, {"jsonrpc": "2.0", "method": "exit", "params": None})
+    proc.wait()
+
+
+if __name__ == "__main__":
+    main()